@@ -0,0 +1,144 @@
+//! The typestate builder against the two things it is meant to cost the same as: a builder with
+//! an `Option` per field checking completeness at runtime, and constructing the struct directly.
+//! Every benchmark builds the same values, so the differences are the builders themselves.
+//!
+//! `cargo bench` runs them, `cargo bench -- construct` only the ones matching a filter.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use typestuff::pool::BuilderPool;
+use typestuff::{Item, ItemBuilder};
+
+/// The builder most crates write by hand, every field is checked when building.
+#[derive(Default)]
+struct OptionBuilder {
+    a: Option<String>,
+    b: Option<Vec<i32>>,
+}
+
+impl OptionBuilder {
+    fn a(mut self, a: String) -> Self {
+        self.a = Some(a);
+        self
+    }
+
+    fn b(mut self, b: Vec<i32>) -> Self {
+        self.b = Some(b);
+        self
+    }
+
+    fn build(self) -> Result<Item, &'static str> {
+        Ok(Item { a: self.a.ok_or("a")?, b: self.b.ok_or("b")? })
+    }
+}
+
+fn a() -> String {
+    black_box(String::from("value"))
+}
+
+fn b() -> Vec<i32> {
+    black_box(vec![1, 2, 3])
+}
+
+fn construct(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construct");
+    group.bench_function("direct", |bench| bench.iter(|| Item { a: a(), b: b() }));
+    group.bench_function("typestate", |bench| {
+        bench.iter(|| ItemBuilder::new().a(a()).b(b()).construct())
+    });
+    group.bench_function("option", |bench| {
+        bench.iter(|| OptionBuilder::default().a(a()).b(b()).build().unwrap())
+    });
+    group.finish();
+}
+
+/// Setting a field again drops the previous value.
+fn overwrite(c: &mut Criterion) {
+    let mut group = c.benchmark_group("overwrite");
+    group.bench_function("typestate", |bench| {
+        bench.iter(|| ItemBuilder::new().a(a()).b(b()).a(a()).b(b()).construct())
+    });
+    group.bench_function("option", |bench| {
+        bench.iter(|| OptionBuilder::default().a(a()).b(b()).a(a()).b(b()).build().unwrap())
+    });
+    group.finish();
+}
+
+/// An abandoned builder with one field set, the slots have to drop exactly that one.
+fn drop_half_built(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drop_half_built");
+    group.bench_function("typestate", |bench| bench.iter(|| drop(ItemBuilder::new().a(a()))));
+    group.bench_function("option", |bench| bench.iter(|| drop(OptionBuilder::default().a(a()))));
+    group.finish();
+}
+
+/// Many items built and dropped one after the other. The pool hands the same buffers out every
+/// time and the setters copy into them, the fresh builders allocate new ones for every item.
+fn pool(c: &mut Criterion) {
+    const ITEMS: usize = 1000;
+    let pool = BuilderPool::new(1);
+    let mut group = c.benchmark_group("pool");
+    group.bench_function("pooled", |bench| {
+        bench.iter(|| {
+            for _ in 0..ITEMS {
+                let item = pool.get().a(black_box("value")).b(black_box(&[1, 2, 3]));
+                black_box(item.construct_pooled());
+            }
+        })
+    });
+    group.bench_function("fresh", |bench| {
+        bench.iter(|| {
+            for _ in 0..ITEMS {
+                black_box(ItemBuilder::new().a(a()).b(b()).construct());
+            }
+        })
+    });
+    group.finish();
+}
+
+/// Filling a vector, constructing on the stack and pushing against constructing into the spare
+/// capacity. The large items carry a 4 KiB string and a 1024 element list, both paths move the
+/// buffers without copying them, so what is left to compare is moving the items themselves.
+fn push(c: &mut Criterion) {
+    const ITEMS: usize = 1000;
+    let small = ("small", "value".to_string(), vec![1, 2, 3]);
+    let large = ("large", "x".repeat(4096), (0..1024).collect());
+    let mut group = c.benchmark_group("push");
+    for (size, a, b) in [small, large] {
+        let builders = || {
+            let builder = ItemBuilder::new().a(a.clone()).b(b.clone());
+            vec![builder; ITEMS]
+        };
+        group.bench_function(format!("construct_push/{}", size), |bench| {
+            bench.iter_batched(
+                builders,
+                |builders| {
+                    let mut items = Vec::with_capacity(ITEMS);
+                    for builder in builders {
+                        builder.construct_push(&mut items);
+                    }
+                    items
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("construct_then_push/{}", size), |bench| {
+            bench.iter_batched(
+                builders,
+                |builders| {
+                    let mut items = Vec::with_capacity(ITEMS);
+                    for builder in builders {
+                        items.push(builder.construct());
+                    }
+                    items
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, construct, overwrite, drop_half_built, pool, push);
+criterion_main!(benches);
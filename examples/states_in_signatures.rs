@@ -0,0 +1,64 @@
+//! Builders as arguments, return values and struct fields, with the states spelled out the ways
+//! `typestuff::states` describes. `cargo run --example states_in_signatures` walks through them,
+//! `cargo test` builds this file so the signatures keep compiling.
+
+use typestuff::states::{
+    ASet, BSet, CompleteItemBuilder, EmptyItemBuilder, IsUnset, ItemBuilderWithA,
+};
+use typestuff::{FieldState, Item, ItemBuilder, Set};
+
+/// Only accepts builders where `a` hasn't been set yet, so nothing gets overwritten.
+fn fill_a(builder: ItemBuilder<impl IsUnset, Set>) -> CompleteItemBuilder {
+    builder.a("a".into())
+}
+
+/// Accepts `a` in any state and hands it back as it was.
+fn fill_b<A: FieldState, B: FieldState>(builder: ItemBuilder<A, B>) -> ItemBuilder<A, Set> {
+    builder.b(vec![])
+}
+
+/// Something that contributes fields, returning builders from trait methods.
+trait Stage {
+    fn apply<A, B>(&self, builder: ItemBuilder<A, B>) -> ItemBuilder<A, Set>
+    where
+        A: FieldState,
+        B: IsUnset;
+}
+
+struct Numbers(Vec<i32>);
+
+impl Stage for Numbers {
+    fn apply<A, B>(&self, builder: ItemBuilder<A, B>) -> ItemBuilder<A, Set>
+    where
+        A: FieldState,
+        B: IsUnset,
+    {
+        builder.b(self.0.clone())
+    }
+}
+
+/// Constructs whatever states it is given, as long as the compiler can prove they are set.
+fn finish<A: ASet, B: BSet>(builder: ItemBuilder<A, B>) -> Item {
+    builder.construct()
+}
+
+/// Builders stored in a struct, with the states named through the aliases.
+struct Pending {
+    fresh: EmptyItemBuilder,
+    named: ItemBuilderWithA,
+}
+
+fn main() {
+    let item = fill_a(fill_b(ItemBuilder::new())).construct();
+    assert_eq!(item.a, "a");
+    println!("{:?}", item);
+
+    let item = finish(Numbers(vec![1]).apply(ItemBuilder::new()).a("staged".into()));
+    assert_eq!(item, Item { a: "staged".into(), b: vec![1] });
+    println!("{:?}", item);
+
+    let pending =
+        Pending { fresh: ItemBuilder::new(), named: ItemBuilder::new().a("named".into()) };
+    println!("{:?}", fill_a(Numbers(vec![2]).apply(pending.fresh)).construct());
+    println!("{:?}", finish(fill_b(pending.named)));
+}
@@ -0,0 +1,354 @@
+//! An ABI-stable handle to a builder, for plugins loaded as `cdylib`s that contribute fields to a
+//! builder owned by the host. The generic `ItemBuilder` is `repr(Rust)` and changes its type with
+//! every setter, neither of which works across that boundary. The handle is a `repr(C)` struct of
+//! function pointers into the host instead, operating on an `ItemPartial` the host keeps:
+//!
+//! ```ignore
+//! // host
+//! let mut builder = HostBuilder::new();
+//! plugin_entry_point(&builder.handle());
+//! let item = builder.try_construct()?;
+//!
+//! // plugin
+//! extern "C" fn plugin_entry_point(handle: &RawBuilderHandle) {
+//!     let mut builder = unsafe { PluginBuilder::new(handle) }.expect("host has other fields");
+//!     builder.b(&[1, 2, 3]);
+//! }
+//! ```
+//!
+//! Host and plugin compiled against different field sets would write into the wrong places, so
+//! the handle carries the number of fields and a hash of their names and types, and the plugin
+//! side refuses handles that don't match its own.
+//!
+//! Nothing `repr(Rust)` crosses the boundary, and neither does ownership of an allocation: the
+//! setters copy out of plugin memory into the host's partial, and constructing through the handle
+//! hands the plugin a [RawItem](struct.RawItem.html) of pointers into an item the host still
+//! owns. The plugin copies what it needs and gives the item back to the host's `free_item`, so
+//! every buffer is freed by the allocator that allocated it.
+
+use std::error::Error;
+use std::fmt;
+use std::os::raw::c_void;
+use std::{ptr, slice, str};
+
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::Item;
+
+/// Index of `a` for `is_set`.
+pub const FIELD_A: u32 = 0;
+/// Index of `b` for `is_set`.
+pub const FIELD_B: u32 = 1;
+
+/// Number of fields behind the handle.
+pub const FIELD_COUNT: u32 = 2;
+/// The fields behind the handle, hashed into [VERSION](constant.VERSION.html).
+pub const FIELD_SET: &str = "a: String, b: Vec<i32>";
+/// Changes whenever a field is added, removed, renamed or changes its type.
+pub const VERSION: u64 = fnv1a(FIELD_SET.as_bytes());
+
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// The handle passed across the boundary. `builder` points at the host's partial, the functions
+/// are the host's and must only ever be called with that pointer.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RawBuilderHandle {
+    pub version: u64,
+    pub field_count: u32,
+    pub builder: *mut c_void,
+    /// Set `a` from UTF-8 bytes, returns `false` and leaves the field alone if they aren't valid.
+    /// The pointer may be null if the length is 0, for both setters.
+    pub set_a: unsafe extern "C" fn(*mut c_void, *const u8, usize) -> bool,
+    pub set_b: unsafe extern "C" fn(*mut c_void, *const i32, usize),
+    pub is_set: unsafe extern "C" fn(*const c_void, u32) -> bool,
+    /// Construct the item on the host and describe it in `*out` if all fields are set and return
+    /// `true`, otherwise leave everything as it is and return `false`.
+    pub try_construct: unsafe extern "C" fn(*mut c_void, *mut RawItem) -> bool,
+    /// Free an item filled in by `try_construct`, once the plugin is done reading it.
+    pub free_item: unsafe extern "C" fn(*mut RawItem),
+}
+
+/// An item constructed by the host, as the plugin sees it: the fields as pointers and lengths
+/// into buffers the host allocated and has to free, through the handle's `free_item`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawItem {
+    /// The UTF-8 bytes of `a`.
+    pub a: *const u8,
+    pub a_len: usize,
+    pub b: *const i32,
+    pub b_len: usize,
+    /// The host's item the fields point into, only `free_item` knows what it is.
+    pub owner: *mut c_void,
+}
+
+impl RawItem {
+    /// An item without fields, for `try_construct` to fill in.
+    pub const fn empty() -> Self {
+        RawItem { a: ptr::null(), a_len: 0, b: ptr::null(), b_len: 0, owner: ptr::null_mut() }
+    }
+}
+
+/// The host side, owning the fields.
+#[derive(Debug, Default)]
+pub struct HostBuilder {
+    // boxed, the handles point into it and moving the builder must not invalidate them
+    partial: Box<ItemPartial>,
+}
+
+impl HostBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A handle to pass to a plugin. It points into this builder, so the builder has to outlive
+    /// every use of the handle and can't be used otherwise while a plugin is working with it.
+    pub fn handle(&mut self) -> RawBuilderHandle {
+        RawBuilderHandle {
+            version: VERSION,
+            field_count: FIELD_COUNT,
+            builder: &mut *self.partial as *mut ItemPartial as *mut c_void,
+            set_a: host_set_a,
+            set_b: host_set_b,
+            is_set: host_is_set,
+            try_construct: host_try_construct,
+            free_item: host_free_item,
+        }
+    }
+
+    /// Construct the item from whatever the plugins have set.
+    pub fn try_construct(self) -> Result<Item, BuildError> {
+        self.partial.try_build()
+    }
+}
+
+/// The `len` elements at `data`. An empty slice may come as a null pointer from C, which
+/// `slice::from_raw_parts` must never see, so no elements means `&[]` whatever the pointer.
+unsafe fn elements<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    match len {
+        0 => &[],
+        _ => slice::from_raw_parts(data, len),
+    }
+}
+
+unsafe extern "C" fn host_set_a(builder: *mut c_void, a: *const u8, len: usize) -> bool {
+    match str::from_utf8(elements(a, len)) {
+        Ok(a) => {
+            (*(builder as *mut ItemPartial)).a = Some(a.into());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+unsafe extern "C" fn host_set_b(builder: *mut c_void, b: *const i32, len: usize) {
+    (*(builder as *mut ItemPartial)).b = Some(elements(b, len).to_vec());
+}
+
+unsafe extern "C" fn host_is_set(builder: *const c_void, field: u32) -> bool {
+    let partial = &*(builder as *const ItemPartial);
+    field < FIELD_COUNT && partial.state_bits() >> field & 1 == 1
+}
+
+unsafe extern "C" fn host_try_construct(builder: *mut c_void, out: *mut RawItem) -> bool {
+    let partial = &mut *(builder as *mut ItemPartial);
+    if partial.a.is_none() || partial.b.is_none() {
+        return false;
+    }
+    let item = match std::mem::take(partial).try_build() {
+        Ok(item) => Box::new(item),
+        Err(_) => return false,
+    };
+    out.write(RawItem {
+        a: item.a.as_ptr(),
+        a_len: item.a.len(),
+        b: item.b.as_ptr(),
+        b_len: item.b.len(),
+        owner: Box::into_raw(item) as *mut c_void,
+    });
+    true
+}
+
+unsafe extern "C" fn host_free_item(item: *mut RawItem) {
+    let item = &mut *item;
+    if !item.owner.is_null() {
+        drop(Box::from_raw(item.owner as *mut Item));
+    }
+    *item = RawItem::empty();
+}
+
+/// A handle that doesn't fit the field set the plugin was compiled against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiError {
+    FieldCount { expected: u32, found: u32 },
+    Version { expected: u64, found: u64 },
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbiError::FieldCount { expected, found } => {
+                write!(f, "builder has {} fields, expected {}", found, expected)
+            }
+            AbiError::Version { expected, found } => {
+                write!(f, "builder version {:#x}, expected {:#x}", found, expected)
+            }
+        }
+    }
+}
+
+impl Error for AbiError {}
+
+/// The plugin side, a safe wrapper around a handle received from the host.
+pub struct PluginBuilder<'h> {
+    handle: &'h RawBuilderHandle,
+}
+
+impl<'h> PluginBuilder<'h> {
+    /// Check that the handle matches the fields this side was compiled with.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to come from [HostBuilder::handle](struct.HostBuilder.html) and the host
+    /// builder has to stay alive and untouched for `'h`.
+    pub unsafe fn new(handle: &'h RawBuilderHandle) -> Result<Self, AbiError> {
+        if handle.field_count != FIELD_COUNT {
+            return Err(AbiError::FieldCount { expected: FIELD_COUNT, found: handle.field_count });
+        }
+        if handle.version != VERSION {
+            return Err(AbiError::Version { expected: VERSION, found: handle.version });
+        }
+        Ok(Self { handle })
+    }
+
+    pub fn a(&mut self, a: &str) {
+        // a `&str` is always valid UTF-8, the host can't reject it
+        unsafe { (self.handle.set_a)(self.handle.builder, a.as_ptr(), a.len()); }
+    }
+
+    pub fn b(&mut self, b: &[i32]) {
+        unsafe { (self.handle.set_b)(self.handle.builder, b.as_ptr(), b.len()); }
+    }
+
+    /// Whether the field with the index `field` has been set, by this plugin or anyone else.
+    pub fn is_set(&self, field: u32) -> bool {
+        unsafe { (self.handle.is_set)(self.handle.builder, field) }
+    }
+
+    /// Construct the item if every field is set, leaving the host's builder empty. The fields
+    /// are copied into buffers of this side's allocator and the host's item is freed by the host.
+    pub fn try_construct(&mut self) -> Option<Item> {
+        let mut raw = RawItem::empty();
+        // `try_construct` only returns `true` after filling in `raw`, whose pointers stay valid
+        // until it is passed to `free_item`
+        unsafe {
+            if !(self.handle.try_construct)(self.handle.builder, &mut raw) {
+                return None;
+            }
+            let a = str::from_utf8_unchecked(elements(raw.a, raw.a_len)).to_owned();
+            let item = Item { a, b: elements(raw.b, raw.b_len).to_vec() };
+            (self.handle.free_item)(&mut raw);
+            Some(item)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut host = HostBuilder::new();
+        let handle = host.handle();
+
+        let mut plugin = unsafe { PluginBuilder::new(&handle) }.unwrap();
+        plugin.b(&[1, 2]);
+        assert!(!plugin.is_set(FIELD_A));
+        assert!(plugin.is_set(FIELD_B));
+        assert!(plugin.try_construct().is_none());
+
+        let mut other = unsafe { PluginBuilder::new(&handle) }.unwrap();
+        other.a("from a plugin");
+
+        let item = host.try_construct().unwrap();
+        assert_eq!(item, Item { a: "from a plugin".into(), b: vec![1, 2] });
+    }
+
+    #[test]
+    fn construct_through_the_handle() {
+        let mut host = HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("a");
+        plugin.b(&[]);
+
+        assert_eq!(plugin.try_construct(), Some(Item { a: "a".into(), b: vec![] }));
+        assert!(!plugin.is_set(FIELD_A));
+        assert!(host.try_construct().is_err());
+    }
+
+    #[test]
+    fn items_stay_the_hosts() {
+        let mut host = HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("a");
+        plugin.b(&[1, 2]);
+
+        let mut raw = RawItem::empty();
+        unsafe {
+            assert!((handle.try_construct)(handle.builder, &mut raw));
+            let b = slice::from_raw_parts(raw.b, raw.b_len);
+            assert_eq!((raw.a_len, b), (1, &[1, 2][..]));
+
+            // freeing forgets the pointers, a second free has nothing left to free
+            (handle.free_item)(&mut raw);
+            assert!(raw.owner.is_null() && raw.a.is_null());
+            (handle.free_item)(&mut raw);
+        }
+    }
+
+    #[test]
+    fn mismatched_versions() {
+        let mut host = HostBuilder::new();
+
+        let handle = RawBuilderHandle { version: fnv1a(b"a: String"), ..host.handle() };
+        let err = unsafe { PluginBuilder::new(&handle) }.err().unwrap();
+        assert_eq!(err, AbiError::Version { expected: VERSION, found: handle.version });
+
+        let handle = RawBuilderHandle { field_count: 3, ..host.handle() };
+        let err = unsafe { PluginBuilder::new(&handle) }.err().unwrap();
+        assert_eq!(err.to_string(), "builder has 3 fields, expected 2");
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut host = HostBuilder::new();
+        let handle = host.handle();
+        let bytes = [0xff, 0xfe];
+        assert!(!unsafe { (handle.set_a)(handle.builder, bytes.as_ptr(), bytes.len()) });
+        assert!(!unsafe { (handle.is_set)(handle.builder, FIELD_A) });
+    }
+
+    #[test]
+    fn null_empty_slices() {
+        let mut host = HostBuilder::new();
+        let handle = host.handle();
+        unsafe {
+            assert!((handle.set_a)(handle.builder, ptr::null(), 0));
+            (handle.set_b)(handle.builder, ptr::null(), 0);
+        }
+        assert_eq!(host.try_construct().unwrap(), Item { a: String::new(), b: vec![] });
+    }
+}
@@ -0,0 +1,125 @@
+//! Building many items from partials at once, sequentially or with rayon. Errors are reported
+//! together with the index of the partial that failed.
+
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::Item;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchPolicy {
+    /// Stop at the first failure and report only that one.
+    FailFast,
+    /// Try every partial and report all failures, ordered by index.
+    CollectAll,
+}
+
+pub type BatchResult = Result<Vec<Item>, Vec<(usize, BuildError)>>;
+
+impl Item {
+    /// Build every partial in order. On success the items are in the same order as the partials.
+    pub fn build_batch(partials: Vec<ItemPartial>, policy: BatchPolicy) -> BatchResult {
+        let results = partials.into_iter().enumerate().map(try_build_indexed);
+
+        match policy {
+            BatchPolicy::FailFast => results.collect::<Result<_, _>>().map_err(|err| vec![err]),
+            BatchPolicy::CollectAll => collect_all(results),
+        }
+    }
+
+    /// Same as [build_batch](#method.build_batch) but on the rayon thread pool. With
+    /// `FailFast` the reported failure is the first one found, which isn't necessarily the one
+    /// with the lowest index.
+    #[cfg(feature = "rayon")]
+    pub fn build_par(partials: Vec<ItemPartial>, policy: BatchPolicy) -> BatchResult {
+        use rayon::prelude::*;
+
+        let results = partials.into_par_iter().enumerate().map(try_build_indexed);
+
+        match policy {
+            BatchPolicy::FailFast => results.collect::<Result<_, _>>().map_err(|err| vec![err]),
+            BatchPolicy::CollectAll => collect_all(results.collect::<Vec<_>>().into_iter()),
+        }
+    }
+}
+
+fn try_build_indexed((index, partial): (usize, ItemPartial)) -> Result<Item, (usize, BuildError)> {
+    partial.try_build().map_err(|err| (index, err))
+}
+
+fn collect_all<I>(results: I) -> BatchResult
+where
+    I: Iterator<Item = Result<Item, (usize, BuildError)>>,
+{
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(item) => items.push(item),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(items)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(a: Option<&str>, b: Option<i32>) -> ItemPartial {
+        ItemPartial {
+            a: a.map(String::from),
+            b: b.map(|b| vec![b]),
+        }
+    }
+
+    fn mixed() -> Vec<ItemPartial> {
+        vec![
+            partial(Some("0"), Some(0)),
+            partial(None, Some(1)),
+            partial(Some("2"), Some(2)),
+            partial(None, None),
+        ]
+    }
+
+    fn valid() -> Vec<ItemPartial> {
+        (0..100).map(|i| partial(Some(&i.to_string()), Some(i))).collect()
+    }
+
+    #[test]
+    fn sequential() {
+        let items = Item::build_batch(valid(), BatchPolicy::CollectAll).unwrap();
+        assert!(items.iter().enumerate().all(|(i, item)| item.b == vec![i as i32]));
+
+        let errors = Item::build_batch(mixed(), BatchPolicy::CollectAll).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                (1, BuildError::MissingFields(vec!["a"])),
+                (3, BuildError::MissingFields(vec!["a", "b"])),
+            ],
+        );
+
+        let errors = Item::build_batch(mixed(), BatchPolicy::FailFast).unwrap_err();
+        assert_eq!(errors, vec![(1, BuildError::MissingFields(vec!["a"]))]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let items = Item::build_par(valid(), BatchPolicy::CollectAll).unwrap();
+        assert!(items.iter().enumerate().all(|(i, item)| item.b == vec![i as i32]));
+
+        let errors = Item::build_par(mixed(), BatchPolicy::CollectAll).unwrap_err();
+        let indices: Vec<_> = errors.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, vec![1, 3]);
+
+        let errors = Item::build_par(mixed(), BatchPolicy::FailFast).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0 == 1 || errors[0].0 == 3);
+    }
+}
@@ -0,0 +1,117 @@
+//! A builder that keeps its fields on the heap. Every setter takes the builder by value, which
+//! for a target of a few hundred bytes is a copy of a few hundred bytes per call, and debug builds
+//! keep each of those copies on the stack. [BoxedItemBuilder](struct.BoxedItemBuilder.html) is a
+//! single pointer instead: the fields live in a box that stays where it is, the setters only move
+//! the pointer along and change the states on the wrapper.
+//!
+//! ```ignore
+//! let item = BoxedItemBuilder::new().a("a".into()).b(vec![]).construct();
+//! // BoxedItemBuilder::new().a("a".into()).construct(); // error: `b` hasn't been set
+//! ```
+//!
+//! The box holds the fields as `Option`s, the states on the wrapper say which of them are `Some`.
+//! `Item` is small, the pattern is meant for the targets that aren't.
+
+use std::marker::PhantomData;
+
+use crate::partial::ItemPartial;
+use crate::slot::Slot;
+use crate::{FieldState, Item, ItemBuilder, Set, Unset};
+
+#[must_use = "builder must be used to construct a value"]
+pub struct BoxedItemBuilder<A: FieldState, B: FieldState> {
+    fields: Box<ItemPartial>,
+    _states: PhantomData<fn() -> (A, B)>,
+}
+
+impl BoxedItemBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        BoxedItemBuilder { fields: Box::new(ItemPartial::default()), _states: PhantomData }
+    }
+}
+
+impl Default for BoxedItemBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: FieldState, B: FieldState> BoxedItemBuilder<A, B> {
+    /// Write `a` into the box, dropping the previous value if there is one.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a(mut self, a: String) -> BoxedItemBuilder<Set, B> {
+        self.fields.a = Some(a);
+        self.into_state()
+    }
+
+    /// Same as [a](#method.a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b(mut self, b: Vec<i32>) -> BoxedItemBuilder<A, Set> {
+        self.fields.b = Some(b);
+        self.into_state()
+    }
+
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn unset_a(mut self) -> BoxedItemBuilder<Unset, B> {
+        self.fields.a = None;
+        self.into_state()
+    }
+
+    /// Same as [unset_a](#method.unset_a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn unset_b(mut self) -> BoxedItemBuilder<A, Unset> {
+        self.fields.b = None;
+        self.into_state()
+    }
+
+    /// The same box with other states, nothing but the pointer moves.
+    fn into_state<A2: FieldState, B2: FieldState>(self) -> BoxedItemBuilder<A2, B2> {
+        BoxedItemBuilder { fields: self.fields, _states: PhantomData }
+    }
+
+    /// The builder with the fields on the stack again.
+    pub fn unboxed(self) -> ItemBuilder<A, B> {
+        let ItemPartial { a, b } = *self.fields;
+        ItemBuilder { a: Slot::from_option(a), b: Slot::from_option(b) }
+    }
+}
+
+impl BoxedItemBuilder<Set, Set> {
+    /// Move the fields out of the box and construct the item.
+    pub fn construct(self) -> Item {
+        match *self.fields {
+            ItemPartial { a: Some(a), b: Some(b) } => Item { a, b },
+            _ => unreachable!("the states say every field is set"),
+        }
+    }
+}
+
+impl<A: FieldState, B: FieldState> From<ItemBuilder<A, B>> for BoxedItemBuilder<A, B> {
+    fn from(builder: ItemBuilder<A, B>) -> Self {
+        BoxedItemBuilder { fields: Box::new(builder.into_partial()), _states: PhantomData }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::*;
+
+    #[test]
+    fn a_pointer_whatever_the_target() {
+        assert_eq!(mem::size_of::<BoxedItemBuilder<Set, Unset>>(), mem::size_of::<usize>());
+
+        let builder = BoxedItemBuilder::new().b(vec![1]).a("a".into()).unset_b();
+        let item = builder.b(vec![2]).construct();
+        assert_eq!(item, Item { a: "a".into(), b: vec![2] });
+    }
+
+    #[test]
+    fn boxed_and_unboxed() {
+        let boxed = BoxedItemBuilder::from(ItemBuilder::new().a("a".into()));
+        let unboxed = boxed.b(vec![]).unset_a().unboxed();
+        assert_eq!(unboxed.state_bits(), 0b10);
+        assert_eq!(unboxed.a("b".into()).construct().a, "b");
+    }
+}
@@ -0,0 +1,75 @@
+//! Preallocating collection fields whose final size is known in advance. `b_with_capacity` sets
+//! `b` to an empty vector with room for that many elements, `push_b` appends to it without
+//! reallocating until the capacity is used up. The same goes for `a` with `a_with_capacity` and
+//! `push_a`.
+//!
+//! A reserved field counts as `Set`, an empty list is a perfectly fine value and the typestate
+//! doesn't need another state for it. Calling the whole-value setter afterwards replaces the
+//! reserved buffer like any other value. Pooled builders don't have these methods, their
+//! buffers come from recycled items and already have whatever capacity those needed.
+//!
+//! Derived builders get the same setter from `#[builder(setter(with_capacity))]` on the field.
+
+use crate::slot::Slot;
+use crate::{FieldState, ItemBuilder, Set};
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Set `a` to an empty string with room for `capacity` bytes.
+    pub fn a_with_capacity(self, capacity: usize) -> ItemBuilder<Set, B> {
+        self.a(String::with_capacity(capacity))
+    }
+
+    /// Set `b` to an empty vector with room for `capacity` elements.
+    pub fn b_with_capacity(self, capacity: usize) -> ItemBuilder<A, Set> {
+        self.b(Vec::with_capacity(capacity))
+    }
+
+    /// Append to `a`, starting with an empty string if it hasn't been set.
+    pub fn push_a(self, a: &str) -> ItemBuilder<Set, B> {
+        let mut buffer = self.a.into_option().unwrap_or_default();
+        buffer.push_str(a);
+
+        ItemBuilder { a: Slot::new(buffer), b: self.b }
+    }
+
+    /// Append to `b`, starting with an empty vector if it hasn't been set.
+    pub fn push_b(self, b: i32) -> ItemBuilder<A, Set> {
+        let mut buffer = self.b.into_option().unwrap_or_default();
+        buffer.push(b);
+
+        ItemBuilder { a: self.a, b: Slot::new(buffer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_reallocation() {
+        let mut builder = ItemBuilder::new().a("a".into()).b_with_capacity(64);
+        for i in 0..64 {
+            builder = builder.push_b(i);
+        }
+        let item = builder.construct();
+        assert_eq!(item.b, (0..64).collect::<Vec<_>>());
+        assert_eq!(item.b.capacity(), 64);
+
+        let builder = ItemBuilder::new().a_with_capacity(11).b(vec![]);
+        let item = builder.push_a("hello").push_a(" world").construct();
+        assert_eq!(item.a, "hello world");
+        assert_eq!(item.a.capacity(), 11);
+    }
+
+    #[test]
+    fn setter_discards_reservation() {
+        let item = ItemBuilder::new().a("a".into()).b_with_capacity(1024).b(vec![1]).construct();
+        assert_eq!(item.b.capacity(), 1);
+    }
+
+    #[test]
+    fn push_without_reservation() {
+        let item = ItemBuilder::new().push_b(1).push_b(2).push_a("a").construct();
+        assert_eq!(item.b, vec![1, 2]);
+    }
+}
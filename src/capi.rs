@@ -0,0 +1,276 @@
+//! A C API for building items incrementally, layered over the
+//! [dynamic builder](../dynamic/index.html). C can't carry the states in its types, so the check
+//! happens at the boundary: `item_builder_construct` refuses to finish while a required field is
+//! missing and reports which ones are, as bits like
+//! [state_bits](../struct.ItemBuilder.html#method.state_bits):
+//!
+//! ```c
+//! ItemBuilderHandle *builder = item_builder_new();
+//! item_builder_set_a(builder, (const uint8_t *)"name", 4);
+//!
+//! Item *item = NULL;
+//! uint64_t missing = 0;
+//! if (item_builder_construct(builder, &item, &missing) == ITEM_STATUS_MISSING_FIELDS) {
+//!     // missing == ITEM_FIELD_B, the builder is untouched and can still be completed
+//! }
+//! ```
+//!
+//! Builders and items are opaque pointers owned by the caller. A successful construct consumes
+//! the builder, otherwise it has to be released with `item_builder_free`. Items are read with
+//! `item_a` and `item_b` and released with `item_free`. The declarations for C:
+//!
+//! ```c
+//! typedef struct ItemBuilderHandle ItemBuilderHandle;
+//! typedef struct Item Item;
+//! typedef enum { ITEM_STATUS_OK, ITEM_STATUS_NULL_POINTER, ITEM_STATUS_INVALID_UTF8,
+//!                ITEM_STATUS_MISSING_FIELDS } ItemStatus;
+//! #define ITEM_FIELD_A 1
+//! #define ITEM_FIELD_B 2
+//!
+//! ItemBuilderHandle *item_builder_new(void);
+//! ItemStatus item_builder_set_a(ItemBuilderHandle *builder, const uint8_t *a, size_t len);
+//! ItemStatus item_builder_set_b(ItemBuilderHandle *builder, const int32_t *b, size_t len);
+//! ItemStatus item_builder_construct(ItemBuilderHandle *builder, Item **out, uint64_t *missing);
+//! void item_builder_free(ItemBuilderHandle *builder);
+//! const uint8_t *item_a(const Item *item, size_t *len);
+//! const int32_t *item_b(const Item *item, size_t *len);
+//! void item_free(Item *item);
+//! ```
+//!
+//! The library has to be built as a `staticlib` or `cdylib` with the `capi` feature for C to link
+//! against it, e.g. with `cargo rustc --features capi --crate-type staticlib`.
+
+use std::{mem, ptr, slice, str};
+
+use crate::dynamic::DynItemBuilder;
+use crate::Item;
+
+/// The bit of `a` in the missing fields.
+pub const ITEM_FIELD_A: u64 = 1 << 0;
+/// The bit of `b` in the missing fields.
+pub const ITEM_FIELD_B: u64 = 1 << 1;
+
+/// What the functions report. Every failure leaves the builder as it was.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemStatus {
+    Ok = 0,
+    /// A pointer that has to point somewhere was null.
+    NullPointer = 1,
+    /// The bytes for `a` aren't valid UTF-8.
+    InvalidUtf8 = 2,
+    /// Required fields haven't been set, the bits of the missing ones are written to `missing`.
+    MissingFields = 3,
+}
+
+/// The builder behind the pointers handed to C, opaque on the C side.
+pub struct ItemBuilderHandle {
+    builder: DynItemBuilder,
+}
+
+/// The `len` elements at `data`. C passes null for empty arrays, only a non-empty null array is
+/// an error.
+unsafe fn elements<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    match len {
+        0 => Some(&[]),
+        _ if data.is_null() => None,
+        _ => Some(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// A new builder with no field set, to be released with `item_builder_free` unless it is
+/// consumed by `item_builder_construct`.
+#[no_mangle]
+pub extern "C" fn item_builder_new() -> *mut ItemBuilderHandle {
+    Box::into_raw(Box::new(ItemBuilderHandle { builder: DynItemBuilder::new() }))
+}
+
+/// Set `a` to a copy of the `len` UTF-8 bytes at `a`.
+///
+/// # Safety
+///
+/// `builder` has to come from `item_builder_new` and not have been released, `a` has to point to
+/// `len` readable bytes unless `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn item_builder_set_a(
+    builder: *mut ItemBuilderHandle,
+    a: *const u8,
+    len: usize,
+) -> ItemStatus {
+    let (builder, a) = match (builder.as_mut(), elements(a, len)) {
+        (Some(builder), Some(a)) => (builder, a),
+        _ => return ItemStatus::NullPointer,
+    };
+    match str::from_utf8(a) {
+        Ok(a) => {
+            builder.builder.set_a(a.into());
+            ItemStatus::Ok
+        }
+        Err(_) => ItemStatus::InvalidUtf8,
+    }
+}
+
+/// Set `b` to a copy of the `len` numbers at `b`.
+///
+/// # Safety
+///
+/// Same as `item_builder_set_a`, `b` has to point to `len` readable numbers.
+#[no_mangle]
+pub unsafe extern "C" fn item_builder_set_b(
+    builder: *mut ItemBuilderHandle,
+    b: *const i32,
+    len: usize,
+) -> ItemStatus {
+    match (builder.as_mut(), elements(b, len)) {
+        (Some(builder), Some(b)) => {
+            builder.builder.set_b(b.to_vec());
+            ItemStatus::Ok
+        }
+        _ => ItemStatus::NullPointer,
+    }
+}
+
+/// Construct the item into `*out` and release the builder. If fields are missing their bits are
+/// written to `*missing`, unless it is null, and the builder is left as it was.
+///
+/// # Safety
+///
+/// `builder` has to come from `item_builder_new` and not have been released, `out` has to be
+/// valid for a write and `missing` either null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn item_builder_construct(
+    builder: *mut ItemBuilderHandle,
+    out: *mut *mut Item,
+    missing: *mut u64,
+) -> ItemStatus {
+    if out.is_null() {
+        return ItemStatus::NullPointer;
+    }
+    let handle = match builder.as_mut() {
+        Some(handle) => handle,
+        None => return ItemStatus::NullPointer,
+    };
+    // the builder only goes back into the handle if it can't be built, whatever the reason
+    match mem::take(&mut handle.builder).build() {
+        Ok(item) => {
+            drop(Box::from_raw(builder));
+            out.write(Box::into_raw(Box::new(item)));
+            ItemStatus::Ok
+        }
+        Err((unfinished, _)) => {
+            if let Some(missing) = missing.as_mut() {
+                *missing = !unfinished.state_bits() & (ITEM_FIELD_A | ITEM_FIELD_B);
+            }
+            handle.builder = unfinished;
+            ItemStatus::MissingFields
+        }
+    }
+}
+
+/// Release a builder that wasn't constructed, null is ignored.
+///
+/// # Safety
+///
+/// `builder` has to be null or come from `item_builder_new` and not have been released.
+#[no_mangle]
+pub unsafe extern "C" fn item_builder_free(builder: *mut ItemBuilderHandle) {
+    if !builder.is_null() {
+        drop(Box::from_raw(builder));
+    }
+}
+
+/// The UTF-8 bytes of `a`, their number is written to `*len`. Valid until the item is released.
+///
+/// # Safety
+///
+/// `item` has to come from `item_builder_construct` and not have been released, `len` has to be
+/// valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn item_a(item: *const Item, len: *mut usize) -> *const u8 {
+    match (item.as_ref(), len.is_null()) {
+        (Some(item), false) => {
+            len.write(item.a.len());
+            item.a.as_ptr()
+        }
+        _ => ptr::null(),
+    }
+}
+
+/// The numbers of `b`, see `item_a`.
+///
+/// # Safety
+///
+/// Same as `item_a`.
+#[no_mangle]
+pub unsafe extern "C" fn item_b(item: *const Item, len: *mut usize) -> *const i32 {
+    match (item.as_ref(), len.is_null()) {
+        (Some(item), false) => {
+            len.write(item.b.len());
+            item.b.as_ptr()
+        }
+        _ => ptr::null(),
+    }
+}
+
+/// Release an item, null is ignored.
+///
+/// # Safety
+///
+/// `item` has to be null or come from `item_builder_construct` and not have been released.
+#[no_mangle]
+pub unsafe extern "C" fn item_free(item: *mut Item) {
+    if !item.is_null() {
+        drop(Box::from_raw(item));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incremental_construction() {
+        let builder = item_builder_new();
+        let mut item = ptr::null_mut();
+        let mut missing = 0;
+        unsafe {
+            assert_eq!(item_builder_set_a(builder, b"name".as_ptr(), 4), ItemStatus::Ok);
+            let status = item_builder_construct(builder, &mut item, &mut missing);
+            assert_eq!((status, missing), (ItemStatus::MissingFields, ITEM_FIELD_B));
+
+            assert_eq!(item_builder_set_b(builder, [1, 2].as_ptr(), 2), ItemStatus::Ok);
+            let status = item_builder_construct(builder, &mut item, ptr::null_mut());
+            assert_eq!(status, ItemStatus::Ok);
+
+            let mut len = 0;
+            let a = slice::from_raw_parts(item_a(item, &mut len), len);
+            assert_eq!(a, b"name");
+            let b = slice::from_raw_parts(item_b(item, &mut len), len);
+            assert_eq!(b, [1, 2]);
+            item_free(item);
+        }
+    }
+
+    #[test]
+    fn rejected_arguments() {
+        let builder = item_builder_new();
+        let mut item = ptr::null_mut();
+        let mut missing = 0;
+        unsafe {
+            assert_eq!(item_builder_set_a(builder, [0xc3].as_ptr(), 1), ItemStatus::InvalidUtf8);
+            assert_eq!(item_builder_set_b(builder, ptr::null(), 1), ItemStatus::NullPointer);
+            let status = item_builder_set_a(ptr::null_mut(), ptr::null(), 0);
+            assert_eq!(status, ItemStatus::NullPointer);
+            let status = item_builder_construct(builder, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(status, ItemStatus::NullPointer);
+
+            // nothing was set, and empty arrays may be null
+            let status = item_builder_construct(builder, &mut item, &mut missing);
+            assert_eq!(status, ItemStatus::MissingFields);
+            assert_eq!(missing, ITEM_FIELD_A | ITEM_FIELD_B);
+            assert_eq!(item_builder_set_b(builder, ptr::null(), 0), ItemStatus::Ok);
+            item_builder_free(builder);
+            item_builder_free(ptr::null_mut());
+        }
+    }
+}
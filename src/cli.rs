@@ -0,0 +1,180 @@
+//! Filling a builder from command-line flags with `clap`. [args](fn.args.html) adds a flag per
+//! field to a `Command`, the matches fill the fields the flags were given for, and fields that
+//! end up set neither in code nor on the command line are reported as a usage error naming their
+//! flags:
+//!
+//! ```ignore
+//! let mut command = cli::args(Command::new("tool"));
+//! let matches = command.try_get_matches_from_mut(["tool", "--b", "1,2"])?;
+//! let builder = ItemBuilder::new().a("default".into());
+//! let item = builder.construct_from_arg_matches(&mut command, &matches)?;
+//! ```
+//!
+//! The values set in code are the defaults, flags that are given override them. The flags are
+//! generated from `ItemBuilder::FIELDS` and named after the fields. A list like `b` takes
+//! comma-separated values and may be given without any for an empty one.
+
+use std::ffi::OsString;
+use std::fmt;
+
+use clap::error::ErrorKind;
+use clap::parser::MatchesError;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use crate::dynamic::{DynItemBuilder, Value};
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::reflection::FieldInfo;
+use crate::{FieldState, Item, ItemBuilder};
+
+/// The flag of `field`, like `--a`.
+fn flag(field: &str) -> String {
+    format!("--{}", field)
+}
+
+/// Whether `field` holds a list, which takes comma-separated values.
+fn is_list(field: &FieldInfo) -> bool {
+    field.type_name == "Vec<i32>"
+}
+
+/// The flag of `field`, taking the type of value the field holds.
+fn arg(field: &FieldInfo) -> Arg {
+    let arg = Arg::new(field.name).long(field.name).value_name(field.name);
+    if is_list(field) {
+        arg.help(format!("The field `{}`, a comma-separated list", field.name))
+            .num_args(0..)
+            .value_delimiter(',')
+            .action(ArgAction::Set)
+            .value_parser(value_parser!(i32))
+    } else {
+        arg.help(format!("The field `{}`", field.name))
+    }
+}
+
+/// The value given for `field`, `None` if its flag wasn't. Matches of a command whose flags
+/// weren't added by [args](fn.args.html) may lack it or hold another type, which is an error.
+fn value(matches: &ArgMatches, field: &FieldInfo) -> Result<Option<Value>, MatchesError> {
+    Ok(if is_list(field) {
+        matches.try_get_many::<i32>(field.name)?.map(|list| Value::List(list.copied().collect()))
+    } else {
+        matches.try_get_one::<String>(field.name)?.cloned().map(Value::Text)
+    })
+}
+
+/// `err` as a `ValueValidation` error, to be given the command with `Error::with_cmd`.
+fn invalid(err: impl fmt::Display) -> clap::Error {
+    clap::Error::raw(ErrorKind::ValueValidation, format!("{}\n", err))
+}
+
+/// Add the flags of `Item`'s fields to `command`. None of them is required by clap, the builder
+/// decides that once it knows what has been set in code.
+pub fn args(command: Command) -> Command {
+    ItemBuilder::FIELDS.iter().fold(command, |command, field| command.arg(arg(field)))
+}
+
+impl ItemPartial {
+    /// The fields whose flags were given. Matches that don't hold the flags the way `cli::args`
+    /// adds them are a `ValueValidation` error.
+    pub fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let mut given = Vec::new();
+        for field in ItemBuilder::FIELDS {
+            if let Some(value) = value(matches, field).map_err(invalid)? {
+                given.push((field.name, value));
+            }
+        }
+        let mut builder = DynItemBuilder::new();
+        builder.set_all(given).map_err(invalid)?;
+        Ok(builder.into())
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Override the fields with the flags that were given and construct the item. Missing fields
+    /// are a `MissingRequiredArgument` error of `command`, with its usage.
+    pub fn construct_from_arg_matches(
+        self,
+        command: &mut Command,
+        matches: &ArgMatches,
+    ) -> Result<Item, clap::Error> {
+        let flags = ItemPartial::from_arg_matches(matches).map_err(|err| err.with_cmd(command))?;
+        let set = self.into_partial();
+        let partial = ItemPartial { a: flags.a.or(set.a), b: flags.b.or(set.b) };
+        partial.try_build().map_err(|err| match err {
+            BuildError::MissingFields(fields) => {
+                let flags: Vec<_> = fields.iter().map(|field| flag(field)).collect();
+                let message = format!("missing required flags: {}", flags.join(", "));
+                command.error(ErrorKind::MissingRequiredArgument, message)
+            }
+            err => command.error(ErrorKind::ValueValidation, err),
+        })
+    }
+}
+
+impl Item {
+    /// Build an item from command-line arguments alone, the first one being the program name.
+    pub fn try_from_args<I, T>(argv: I) -> Result<Item, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = args(Command::new("item"));
+        let matches = command.try_get_matches_from_mut(argv)?;
+        ItemBuilder::new().construct_from_arg_matches(&mut command, &matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_fill_the_fields() {
+        let item = Item::try_from_args(["item", "--b", "1,2", "--a", "a"]).unwrap();
+        assert_eq!(item, Item { a: "a".into(), b: vec![1, 2] });
+        assert!(Item::try_from_args(["item", "--a", "", "--b"]).unwrap().b.is_empty());
+    }
+
+    #[test]
+    fn a_flag_per_field() {
+        let command = args(Command::new("item"));
+        let flags: Vec<_> = command.get_arguments().filter_map(Arg::get_long).collect();
+        let fields: Vec<_> = ItemBuilder::FIELDS.iter().map(|field| field.name).collect();
+        assert_eq!(flags, fields);
+    }
+
+    #[test]
+    fn flags_override_code() {
+        let mut command = args(Command::new("tool"));
+        let matches = command.try_get_matches_from_mut(["tool", "--b", "1,2"]).unwrap();
+        let builder = ItemBuilder::new().a("code".into()).b(vec![0]);
+        let item = builder.construct_from_arg_matches(&mut command, &matches).unwrap();
+        assert_eq!(item, Item { a: "code".into(), b: vec![1, 2] });
+    }
+
+    #[test]
+    fn missing_fields_are_usage_errors() {
+        let err = Item::try_from_args(["item"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let message = err.to_string();
+        assert!(message.contains("missing required flags: --a, --b"), "{}", message);
+        assert!(message.contains("Usage: item"), "{}", message);
+
+        let err = Item::try_from_args(["item", "--b", "x"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn foreign_matches_are_errors() {
+        // `b` as text instead of a list of numbers
+        let mut command =
+            Command::new("tool").arg(Arg::new("a").long("a")).arg(Arg::new("b").long("b"));
+        let matches = command.try_get_matches_from_mut(["tool", "--b", "1"]).unwrap();
+        let err = ItemPartial::from_arg_matches(&matches).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+
+        let builder = ItemBuilder::new().a("code".into());
+        let err = builder.construct_from_arg_matches(&mut command, &matches).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        assert!(err.to_string().contains("Could not downcast"), "{}", err);
+    }
+}
@@ -0,0 +1,26 @@
+//! Where builders get the current time from. Defaults depending on the time go through a `Clock`,
+//! so tests can swap `SystemTime::now` for a fixed time.
+
+use std::time::SystemTime;
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+/// The real clock. It is zero-sized, builders using it are no bigger than without a clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Any closure returning a time is a clock. It is stored inline in the builder, injecting one
+/// doesn't allocate.
+impl<F: Fn() -> SystemTime> Clock for F {
+    fn now(&self) -> SystemTime {
+        self()
+    }
+}
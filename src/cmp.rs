@@ -0,0 +1,100 @@
+//! Comparing and hashing complete builders, e.g. to use them as the key of a cache of items that
+//! have already been constructed. Only complete builders get these impls: a partial builder would
+//! have to skip its unset fields, which is easy to get wrong and rarely what anyone wants. A
+//! complete builder hashes and compares exactly like the `Item` it would construct.
+
+use std::hash::{Hash, Hasher};
+
+use crate::slot::Slot;
+use crate::{Item, ItemBuilder, Set};
+
+impl<T: PartialEq> PartialEq for Slot<T, Set> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq> Eq for Slot<T, Set> {}
+
+impl<T: Hash> Hash for Slot<T, Set> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl PartialEq for ItemBuilder<Set, Set> {
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a && self.b == other.b
+    }
+}
+
+impl Eq for ItemBuilder<Set, Set> {}
+
+/// Hashes the fields in declaration order, the same as the derived `Hash` of `Item`.
+impl Hash for ItemBuilder<Set, Set> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.a.hash(state);
+        self.b.hash(state);
+    }
+}
+
+impl PartialEq<Item> for ItemBuilder<Set, Set> {
+    fn eq(&self, other: &Item) -> bool {
+        *self.a.get() == other.a && *self.b.get() == other.b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::marker::PhantomData;
+
+    use super::*;
+
+    fn builder(a: &str, b: i32) -> ItemBuilder<Set, Set> {
+        ItemBuilder::new().a(a.into()).b(vec![b])
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn cache_keys() {
+        let mut cache = HashSet::new();
+        cache.insert(builder("a", 1));
+        cache.insert(builder("a", 1));
+        cache.insert(builder("a", 2));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn consistent_with_item() {
+        let item = Item { a: "a".into(), b: vec![1] };
+        assert!(builder("a", 1) == item);
+        assert!(builder("b", 1) != item);
+        assert_eq!(hash_of(&builder("a", 1)), hash_of(&item));
+    }
+
+    // Checks whether a type implements `Hash` without requiring it to: the inherent constant only
+    // exists if the bound holds, otherwise the one from the blanket trait impl is used.
+    trait NotHash {
+        const HASH: bool = false;
+    }
+
+    impl<T> NotHash for T {}
+
+    struct Probe<T>(PhantomData<T>);
+
+    impl<T: Hash> Probe<T> {
+        const HASH: bool = true;
+    }
+
+    // only slots of hashable types are hashable, and only once they are set
+    const _: () = assert!(Probe::<Slot<String, Set>>::HASH);
+    const _: () = assert!(!Probe::<Slot<f64, Set>>::HASH);
+    const _: () = assert!(!Probe::<Slot<String, crate::Unset>>::HASH);
+}
@@ -0,0 +1,99 @@
+//! The `std::process::Command` pattern: arguments are appended one at a time or in batches instead
+//! of being passed as a finished list. The first `arg`/`args` call creates the list and moves the
+//! field to `Set`, later calls push onto the list that is already there. Calling `args` with an
+//! empty iterator counts as setting an empty list.
+
+use std::ffi::{OsStr, OsString};
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug)]
+pub struct Command {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct CommandBuilder<P: FieldState, A: FieldState> {
+    program: Slot<OsString, P>,
+    args: Slot<Vec<OsString>, A>,
+}
+
+impl CommandBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            program: Slot::empty(),
+            args: Slot::empty(),
+        }
+    }
+}
+
+impl Default for CommandBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: FieldState, A: FieldState> CommandBuilder<P, A> {
+    pub fn program<S: AsRef<OsStr>>(self, program: S) -> CommandBuilder<Set, A> {
+        CommandBuilder {
+            program: self.program.write(program.as_ref().to_owned()),
+            args: self.args,
+        }
+    }
+
+    /// Append a single argument.
+    pub fn arg<S: AsRef<OsStr>>(self, arg: S) -> CommandBuilder<P, Set> {
+        self.args(Some(arg))
+    }
+
+    /// Append all arguments of an iterator.
+    pub fn args<I, S>(self, args: I) -> CommandBuilder<P, Set>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        // the first call has to create the list, every later call appends to the one in place
+        let mut list = self.args.into_option().unwrap_or_default();
+        list.extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+
+        CommandBuilder {
+            program: self.program,
+            args: Slot::new(list),
+        }
+    }
+}
+
+impl CommandBuilder<Set, Set> {
+    pub fn construct(self) -> Command {
+        Command {
+            program: self.program.into_inner(),
+            args: self.args.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append() {
+        let command = CommandBuilder::new()
+            .program("ls")
+            .arg("-l")
+            .args(["-a", "-h"])
+            .arg(OsString::from("/tmp"))
+            .construct();
+
+        assert_eq!(command.program, "ls");
+        assert_eq!(command.args, vec!["-l", "-a", "-h", "/tmp"]);
+    }
+
+    #[test]
+    fn empty_args_set_the_field() {
+        let command = CommandBuilder::new().args(Vec::<String>::new()).program("true").construct();
+        assert!(command.args.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! A builder that works in const contexts. All fields of `Config` are `Copy`, so overwriting or
+//! abandoning a value never has to run a destructor. That means the builder needs no `Drop` impl
+//! and no runtime check of the type-parameters, and everything can be a `const fn`:
+//!
+//! ```ignore
+//! const DEFAULT_CONFIG: Config = Config::builder().timeout(30).retries(3).construct();
+//! ```
+
+use core::marker::PhantomData;
+#[cfg(not(feature = "forbid-unsafe"))]
+use core::mem::MaybeUninit;
+
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub timeout: u32,
+    pub retries: u8,
+}
+
+impl Config {
+    pub const fn builder() -> ConfigBuilder<Unset, Unset> {
+        ConfigBuilder::new()
+    }
+}
+
+/// The storage of a field, uninitialized until it is set. With the `forbid-unsafe` feature it is
+/// an `Option` instead, which `construct` unwraps.
+#[cfg(not(feature = "forbid-unsafe"))]
+type Field<T> = MaybeUninit<T>;
+#[cfg(feature = "forbid-unsafe")]
+type Field<T> = Option<T>;
+
+#[cfg(not(feature = "forbid-unsafe"))]
+const fn unset<T: Copy>() -> Field<T> {
+    MaybeUninit::uninit()
+}
+
+#[cfg(not(feature = "forbid-unsafe"))]
+const fn set<T: Copy>(value: T) -> Field<T> {
+    MaybeUninit::new(value)
+}
+
+#[cfg(feature = "forbid-unsafe")]
+const fn unset<T: Copy>() -> Field<T> {
+    None
+}
+
+#[cfg(feature = "forbid-unsafe")]
+const fn set<T: Copy>(value: T) -> Field<T> {
+    Some(value)
+}
+
+/// Like `ItemBuilder` one state per field, but the fields are stored in `MaybeUninit`s (`Option`s
+/// with `forbid-unsafe`) instead of slots. Slots drop what they hold, which no `const fn` can do;
+/// `Copy`-types have nothing to drop, so the setters can move the fields into a new builder by
+/// value.
+#[must_use = "builder must be used to construct a value"]
+pub struct ConfigBuilder<T: FieldState, R: FieldState> {
+    timeout: Field<u32>,
+    retries: Field<u8>,
+    _timeout: PhantomData<T>,
+    _retries: PhantomData<R>,
+}
+
+// by hand, a derive would require the states to be `Copy`, which `Set` and `Unset` are not
+impl<T: FieldState, R: FieldState> Clone for ConfigBuilder<T, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: FieldState, R: FieldState> Copy for ConfigBuilder<T, R> {}
+
+impl ConfigBuilder<Unset, Unset> {
+    pub const fn new() -> Self {
+        Self {
+            timeout: unset(),
+            retries: unset(),
+            _timeout: PhantomData,
+            _retries: PhantomData,
+        }
+    }
+}
+
+impl Default for ConfigBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FieldState, R: FieldState> ConfigBuilder<T, R> {
+    pub const fn timeout(self, timeout: u32) -> ConfigBuilder<Set, R> {
+        ConfigBuilder {
+            timeout: set(timeout),
+            retries: self.retries,
+            _timeout: PhantomData,
+            _retries: PhantomData,
+        }
+    }
+
+    pub const fn retries(self, retries: u8) -> ConfigBuilder<T, Set> {
+        ConfigBuilder {
+            timeout: self.timeout,
+            retries: set(retries),
+            _timeout: PhantomData,
+            _retries: PhantomData,
+        }
+    }
+}
+
+impl ConfigBuilder<Set, Set> {
+    #[cfg(not(feature = "forbid-unsafe"))]
+    pub const fn construct(self) -> Config {
+        // both fields are initialized thanks to the type-information
+        unsafe {
+            Config {
+                timeout: self.timeout.assume_init(),
+                retries: self.retries.assume_init(),
+            }
+        }
+    }
+
+    #[cfg(feature = "forbid-unsafe")]
+    pub const fn construct(self) -> Config {
+        match (self.timeout, self.retries) {
+            (Some(timeout), Some(retries)) => Config { timeout, retries },
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Built entirely at compile-time.
+pub const DEFAULT_CONFIG: Config = Config::builder().timeout(30).retries(3).construct();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_builder() {
+        let builder = Config::builder().timeout(30);
+        // both copies stay usable, in any state
+        let (first, second) = (builder, builder);
+        assert_eq!(first.retries(1).construct(), Config { timeout: 30, retries: 1 });
+        assert_eq!(second.retries(2).construct(), Config { timeout: 30, retries: 2 });
+        let complete = builder.retries(3);
+        let copied = complete;
+        assert_eq!(copied.construct(), complete.construct());
+    }
+}
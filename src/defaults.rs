@@ -0,0 +1,38 @@
+//! Builder-level defaults. Every field of `Item` has one, so a builder in any state can be
+//! finished by filling in whatever hasn't been set. `Item`'s own `Default` goes through the same
+//! path, that way the two can't drift apart.
+
+use crate::{FieldState, Item, ItemBuilder};
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Construct the item, using the default for every field that hasn't been set.
+    pub fn finish_with_defaults(self) -> Item {
+        Item {
+            a: self.a.into_option().unwrap_or_default(),
+            b: self.b.into_option().unwrap_or_default(),
+        }
+    }
+}
+
+/// The item an empty builder finishes with.
+impl Default for Item {
+    fn default() -> Self {
+        ItemBuilder::new().finish_with_defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_builder() {
+        assert_eq!(Item::default(), ItemBuilder::new().finish_with_defaults());
+    }
+
+    #[test]
+    fn set_fields_are_kept() {
+        let item = ItemBuilder::new().b(vec![1]).finish_with_defaults();
+        assert_eq!(item, Item { a: String::new(), b: vec![1] });
+    }
+}
@@ -0,0 +1,143 @@
+//! Field-by-field comparison of two values, e.g. to show operators what a configuration change
+//! is going to do before applying it. Only fields that differ show up in the diff, rendered with
+//! `Debug`. Fields marked as sensitive in the builder's `FIELDS` table are reported as changed
+//! without showing either value.
+//!
+//! Derived types get the same `diff` on the value and on its complete builder with
+//! `#[builder(diff)]`, fields marked `#[builder(sensitive)]` are redacted.
+
+use std::fmt::{self, Debug};
+
+use crate::guard::{Export, ExportBuilder};
+use crate::reflection::FieldInfo;
+use crate::{Item, ItemBuilder, Set};
+
+/// Shown instead of the values of sensitive fields.
+pub const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// The fields that differ, in declaration order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub fields: Vec<FieldDiff>,
+}
+
+impl Diff {
+    /// Whether both sides are equal.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Add the field described by `info` if the values differ, redacted if it is sensitive.
+    fn field<T: PartialEq + Debug>(self, info: &FieldInfo, old: &T, new: &T) -> Self {
+        self.__field(info.name, info.sensitive, old, new)
+    }
+
+    /// `field` for derived types, which have no `FIELDS` table without the `reflection` feature.
+    #[doc(hidden)]
+    pub fn __field<T>(mut self, field: &'static str, sensitive: bool, old: &T, new: &T) -> Self
+    where
+        T: PartialEq + Debug,
+    {
+        if old != new {
+            let render = |value| {
+                if sensitive {
+                    REDACTED.into()
+                } else {
+                    format!("{:?}", value)
+                }
+            };
+            self.fields.push(FieldDiff { field, old: render(old), new: render(new) });
+        }
+        self
+    }
+}
+
+/// One line per differing field, like `a: "old" -> "new"`.
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for diff in &self.fields {
+            writeln!(f, "{}: {} -> {}", diff.field, diff.old, diff.new)?;
+        }
+        Ok(())
+    }
+}
+
+impl Item {
+    /// The fields that differ between `self` (old) and `other` (new).
+    pub fn diff(&self, other: &Self) -> Diff {
+        let fields = ItemBuilder::FIELDS;
+        Diff::default().field(&fields[0], &self.a, &other.a).field(&fields[1], &self.b, &other.b)
+    }
+}
+
+impl ItemBuilder<Set, Set> {
+    /// The fields that differ between the items `self` (old) and `other` (new) would construct.
+    pub fn diff(&self, other: &Self) -> Diff {
+        let fields = ItemBuilder::FIELDS;
+        Diff::default()
+            .field(&fields[0], self.a.get(), other.a.get())
+            .field(&fields[1], self.b.get(), other.b.get())
+    }
+}
+
+impl Export {
+    /// The fields that differ between `self` (old) and `other` (new), the key is sensitive.
+    pub fn diff(&self, other: &Self) -> Diff {
+        let fields = ExportBuilder::FIELDS;
+        Diff::default()
+            .field(&fields[0], &self.path, &other.path)
+            .field(&fields[1], &self.compression, &other.compression)
+            .field(&fields[2], &self.key, &other.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder(a: &str, b: Vec<i32>) -> ItemBuilder<Set, Set> {
+        ItemBuilder::new().a(a.into()).b(b)
+    }
+
+    #[test]
+    fn identical() {
+        assert!(builder("a", vec![1]).diff(&builder("a", vec![1])).is_empty());
+    }
+
+    #[test]
+    fn one_field() {
+        let diff = builder("a", vec![1]).diff(&builder("a", vec![1, 2]));
+        assert_eq!(
+            diff.fields,
+            [FieldDiff { field: "b", old: "[1]".into(), new: "[1, 2]".into() }],
+        );
+        let (old, new) = (builder("a", vec![1]).construct(), builder("a", vec![1, 2]).construct());
+        assert_eq!(diff, old.diff(&new));
+    }
+
+    #[test]
+    fn all_fields() {
+        let diff = builder("old", vec![]).diff(&builder("new", vec![1]));
+        assert_eq!(diff.to_string(), "a: \"old\" -> \"new\"\nb: [] -> [1]\n");
+    }
+
+    #[test]
+    fn sensitive_fields_are_redacted() {
+        let export = |compression, key: &str| Export {
+            path: "out".into(),
+            compression,
+            key: key.into(),
+        };
+        assert!(export(1, "secret").diff(&export(1, "secret")).is_empty());
+
+        let diff = export(1, "old secret").diff(&export(9, "new secret"));
+        assert_eq!(diff.to_string(), "compression: 1 -> 9\nkey: <redacted> -> <redacted>\n");
+    }
+}
@@ -0,0 +1,74 @@
+//! Formatting of builders.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{FieldState, ItemBuilder};
+
+/// Prints the [state_bits](struct.ItemBuilder.html#method.state_bits) in hexadecimal, so
+/// `{:#010x}` gives a fixed-width representation of which fields are set.
+impl<A: FieldState, B: FieldState> fmt::LowerHex for ItemBuilder<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.state_bits(), f)
+    }
+}
+
+/// Shows the values of the fields that are set, like `ItemBuilder { a: "a", b: <unset> }`.
+impl<A: FieldState, B: FieldState> fmt::Debug for ItemBuilder<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ItemBuilder")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .finish()
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// The set fields formatted for humans, keyed and sorted by field name. Unset fields are left
+    /// out. Fields are formatted with `Display` where the type has it and with `Debug` otherwise,
+    /// so `a` shows up as `text` and `b` as `[1, 2]`.
+    pub fn into_display_map(self) -> BTreeMap<&'static str, String> {
+        let mut map = BTreeMap::new();
+        if let Some(a) = self.a.as_option() {
+            map.insert("a", a.to_string());
+        }
+        if let Some(b) = self.b.as_option() {
+            map.insert("b", format!("{:?}", b));
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ItemBuilder;
+
+    #[test]
+    fn lower_hex() {
+        assert_eq!(format!("{:x}", ItemBuilder::new()), "0");
+        assert_eq!(format!("{:x}", ItemBuilder::new().a("a".into())), "1");
+        assert_eq!(format!("{:x}", ItemBuilder::new().b(vec![])), "2");
+        assert_eq!(format!("{:#010x}", ItemBuilder::new().a("a".into()).b(vec![])), "0x00000003");
+    }
+
+    #[test]
+    fn debug() {
+        let builder = ItemBuilder::new().a("a".into());
+        assert_eq!(format!("{:?}", builder), "ItemBuilder { a: \"a\", b: <unset> }");
+        let builder = builder.b(vec![1]).unset_a();
+        assert_eq!(format!("{:?}", builder), "ItemBuilder { a: <unset>, b: [1] }");
+    }
+
+    #[test]
+    fn display_map() {
+        assert!(ItemBuilder::new().into_display_map().is_empty());
+
+        let map = ItemBuilder::new().b(vec![1, 2]).into_display_map();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["b"], "[1, 2]");
+
+        let map = ItemBuilder::new().b(vec![]).a("text".into()).into_display_map();
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        assert_eq!(entries, [("a", "text"), ("b", "[]")]);
+    }
+}
@@ -0,0 +1,154 @@
+//! The order in which an abandoned builder drops its fields. Builders have no `Drop` impl, their
+//! slots are dropped by the language in the order they are declared in. That is a stable contract:
+//! every builder declares its slots in the same order as the target's fields, so the set fields
+//! of a dropped builder go in declaration order, like the fields of the target would. `ItemBuilder`
+//! drops `a` before `b`.
+//!
+//! Targets whose fields depend on each other sometimes need the opposite, e.g. a guard that has to
+//! outlive the resource it protects. Their builders declare the slots in reverse, like
+//! `ScopedBuilder` below. `construct()` moves the values into the target, from then on the
+//! target's own declaration order applies.
+//!
+//! Derived builders follow the same contract, `#[builder(drop_order = "reverse")]` declares their
+//! slots in reverse.
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+/// A pair of arbitrary values, with a builder using the default drop order.
+#[derive(Debug)]
+pub struct Pair<L, R> {
+    pub left: L,
+    pub right: R,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct PairBuilder<L, R, LS: FieldState, RS: FieldState> {
+    left: Slot<L, LS>,
+    right: Slot<R, RS>,
+}
+
+impl<L, R> PairBuilder<L, R, Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            left: Slot::empty(),
+            right: Slot::empty(),
+        }
+    }
+}
+
+impl<L, R> Default for PairBuilder<L, R, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L, R, LS: FieldState, RS: FieldState> PairBuilder<L, R, LS, RS> {
+    pub fn left(self, left: L) -> PairBuilder<L, R, Set, RS> {
+        PairBuilder { left: self.left.write(left), right: self.right }
+    }
+
+    pub fn right(self, right: R) -> PairBuilder<L, R, LS, Set> {
+        PairBuilder { left: self.left, right: self.right.write(right) }
+    }
+}
+
+impl<L, R> PairBuilder<L, R, Set, Set> {
+    pub fn construct(self) -> Pair<L, R> {
+        Pair { left: self.left.into_inner(), right: self.right.into_inner() }
+    }
+}
+
+/// A resource together with the guard protecting it. The builder drops in reverse, so an
+/// abandoned builder releases the resource before the guard.
+#[derive(Debug)]
+pub struct Scoped<G, R> {
+    pub guard: G,
+    pub resource: R,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct ScopedBuilder<G, R, GS: FieldState, RS: FieldState> {
+    // declared in reverse on purpose, see the module documentation
+    resource: Slot<R, RS>,
+    guard: Slot<G, GS>,
+}
+
+impl<G, R> ScopedBuilder<G, R, Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            resource: Slot::empty(),
+            guard: Slot::empty(),
+        }
+    }
+}
+
+impl<G, R> Default for ScopedBuilder<G, R, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G, R, GS: FieldState, RS: FieldState> ScopedBuilder<G, R, GS, RS> {
+    pub fn guard(self, guard: G) -> ScopedBuilder<G, R, Set, RS> {
+        ScopedBuilder { resource: self.resource, guard: self.guard.write(guard) }
+    }
+
+    pub fn resource(self, resource: R) -> ScopedBuilder<G, R, GS, Set> {
+        ScopedBuilder { resource: self.resource.write(resource), guard: self.guard }
+    }
+}
+
+impl<G, R> ScopedBuilder<G, R, Set, Set> {
+    pub fn construct(self) -> Scoped<G, R> {
+        Scoped { guard: self.guard.into_inner(), resource: self.resource.into_inner() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Records its name in the log when dropped.
+    struct Recorder<'l>(&'static str, &'l RefCell<Vec<&'static str>>);
+
+    impl Drop for Recorder<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[test]
+    fn declaration_order() {
+        let log = RefCell::new(Vec::new());
+        // set in the opposite order to show that only the declaration counts
+        let builder = PairBuilder::new().right(Recorder("right", &log));
+        drop(builder.left(Recorder("left", &log)));
+        assert_eq!(*log.borrow(), ["left", "right"]);
+    }
+
+    #[test]
+    fn reverse_order() {
+        let log = RefCell::new(Vec::new());
+        let builder = ScopedBuilder::new().guard(Recorder("guard", &log));
+        drop(builder.resource(Recorder("resource", &log)));
+        assert_eq!(*log.borrow(), ["resource", "guard"]);
+    }
+
+    #[test]
+    fn partially_set() {
+        let log = RefCell::new(Vec::new());
+        drop(ScopedBuilder::<_, Recorder, _, _>::new().guard(Recorder("guard", &log)));
+        assert_eq!(*log.borrow(), ["guard"]);
+    }
+
+    #[test]
+    fn target_keeps_its_own_order() {
+        let log = RefCell::new(Vec::new());
+        let builder = ScopedBuilder::new().resource(Recorder("resource", &log));
+        drop(builder.guard(Recorder("guard", &log)).construct());
+        assert_eq!(*log.borrow(), ["guard", "resource"]);
+    }
+}
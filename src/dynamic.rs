@@ -0,0 +1,262 @@
+//! `Item`'s builder with the states tracked at runtime, for fields that are decided at runtime:
+//! plugins, scripting bridges, anything setting fields by name. The fields are `Option`s, setters
+//! take `&mut self` and `build()` reports the missing fields instead of failing to compile.
+//!
+//! Both kinds of builders convert into each other without losing or dropping a value. A typestate
+//! builder always fits, the other way round only works for the states the fields are actually in:
+//!
+//! ```ignore
+//! let mut dynamic = DynItemBuilder::from(ItemBuilder::new().a("a".into()));
+//! dynamic.set("b", Value::List(vec![1]))?;
+//! let item = dynamic.into_typed::<Set, Set>().unwrap().construct();
+//! ```
+//!
+//! Data that arrives as a map, from a form submission or a message payload, fills the fields with
+//! [set_all](struct.DynItemBuilder.html#method.set_all) or converts with `TryFrom`. With the
+//! `serde` feature a [Value](enum.Value.html) deserializes from a JSON string or list of numbers,
+//! so a `HashMap<String, Value>` comes straight out of `serde_json`:
+//!
+//! ```ignore
+//! let fields: HashMap<String, Value> = serde_json::from_str(r#"{ "a": "a", "b": [1] }"#)?;
+//! let item = Item::try_from(fields)?; // `UnknownField`, `TypeMismatch` or `MissingFields`
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::BuildHasher;
+
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::reflection::{missing_fields, state_bits};
+use crate::slot::Slot;
+use crate::{FieldState, Item, ItemBuilder};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DynItemBuilder {
+    a: Option<String>,
+    b: Option<Vec<i32>>,
+}
+
+/// A value for [set](struct.DynItemBuilder.html#method.set), one variant per type of field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(untagged))]
+pub enum Value {
+    Text(String),
+    List(Vec<i32>),
+}
+
+impl DynItemBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_a(&mut self, a: String) -> &mut Self {
+        self.a = Some(a);
+        self
+    }
+
+    pub fn set_b(&mut self, b: Vec<i32>) -> &mut Self {
+        self.b = Some(b);
+        self
+    }
+
+    /// Set the field called `field`. Unknown fields are a `BuildError::UnknownField`, values of
+    /// the wrong type a `BuildError::TypeMismatch`, and either leaves the builder as it was.
+    pub fn set(&mut self, field: &str, value: Value) -> Result<&mut Self, BuildError> {
+        match (index(field)?, value) {
+            (FIELD_A, Value::Text(a)) => Ok(self.set_a(a)),
+            (FIELD_B, Value::List(b)) => Ok(self.set_b(b)),
+            (_, value) => Err(BuildError::TypeMismatch { field: field.into(), value }),
+        }
+    }
+
+    /// Set every field in `fields` by name. The first unknown field or value of the wrong type is
+    /// an error and leaves the builder as it was, none of the fields are set.
+    pub fn set_all<I, K>(&mut self, fields: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (K, Value)>,
+        K: AsRef<str>,
+    {
+        let mut next = self.clone();
+        for (field, value) in fields {
+            next.set(field.as_ref(), value)?;
+        }
+        *self = next;
+        Ok(self)
+    }
+
+    /// Drop the value of the field called `field`, if it is set.
+    pub fn unset(&mut self, field: &str) -> Result<&mut Self, BuildError> {
+        match index(field)? {
+            FIELD_A => self.a = None,
+            _ => self.b = None,
+        }
+        Ok(self)
+    }
+
+    /// The fields that are set, with the same bits as
+    /// [ItemBuilder::state_bits](../struct.ItemBuilder.html#method.state_bits).
+    pub fn state_bits(&self) -> u64 {
+        state_bits(&self.states())
+    }
+
+    /// The fields that haven't been set, in declaration order.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        missing_fields(ItemBuilder::FIELDS, &self.states())
+    }
+
+    fn states(&self) -> [bool; 2] {
+        [self.a.is_some(), self.b.is_some()]
+    }
+
+    /// Build the item, or hand back the builder with the missing fields.
+    pub fn build(self) -> Result<Item, (Self, BuildError)> {
+        match self {
+            DynItemBuilder { a: Some(a), b: Some(b) } => Ok(Item { a, b }),
+            builder => {
+                let missing = builder.missing_fields();
+                Err((builder, BuildError::MissingFields(missing)))
+            }
+        }
+    }
+
+    /// The typestate builder in the states `A` and `B`, if those are the states the fields are
+    /// in. Otherwise the builder is handed back: a field that is set can't go into an `Unset`
+    /// slot without dropping its value.
+    pub fn into_typed<A: FieldState, B: FieldState>(self) -> Result<ItemBuilder<A, B>, Self> {
+        if self.state_bits() != ItemBuilder::<A, B>::STATE_BITS {
+            return Err(self);
+        }
+        Ok(ItemBuilder { a: Slot::from_option(self.a), b: Slot::from_option(self.b) })
+    }
+}
+
+/// The positions of the fields in `ItemBuilder::FIELDS`.
+const FIELD_A: usize = 0;
+const FIELD_B: usize = 1;
+
+/// The position of the field called `field` in `ItemBuilder::FIELDS`, which has the names.
+fn index(field: &str) -> Result<usize, BuildError> {
+    let index = ItemBuilder::FIELDS.iter().position(|info| info.name == field);
+    index.ok_or_else(|| BuildError::UnknownField(field.into()))
+}
+
+impl<A: FieldState, B: FieldState> From<ItemBuilder<A, B>> for DynItemBuilder {
+    fn from(builder: ItemBuilder<A, B>) -> Self {
+        builder.into_partial().into()
+    }
+}
+
+impl From<ItemPartial> for DynItemBuilder {
+    fn from(partial: ItemPartial) -> Self {
+        DynItemBuilder { a: partial.a, b: partial.b }
+    }
+}
+
+impl From<DynItemBuilder> for ItemPartial {
+    fn from(builder: DynItemBuilder) -> Self {
+        ItemPartial { a: builder.a, b: builder.b }
+    }
+}
+
+/// The fields in the map, which may leave some of them unset.
+impl<S: BuildHasher> TryFrom<HashMap<String, Value, S>> for DynItemBuilder {
+    type Error = BuildError;
+
+    fn try_from(fields: HashMap<String, Value, S>) -> Result<Self, BuildError> {
+        let mut builder = DynItemBuilder::new();
+        builder.set_all(fields)?;
+        Ok(builder)
+    }
+}
+
+/// An item from a map that has every field, and nothing else.
+impl<S: BuildHasher> TryFrom<HashMap<String, Value, S>> for Item {
+    type Error = BuildError;
+
+    fn try_from(fields: HashMap<String, Value, S>) -> Result<Self, BuildError> {
+        let builder = DynItemBuilder::try_from(fields)?;
+        builder.build().map_err(|(_, err)| err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::{Set, Unset};
+
+    #[test]
+    fn set_by_name() {
+        let mut builder = DynItemBuilder::new();
+        builder.set("b", Value::List(vec![1])).unwrap();
+        assert_eq!(builder.missing_fields(), ["a"]);
+
+        let err = builder.set("a", Value::List(vec![])).unwrap_err();
+        assert_eq!(err.to_string(), "`a` can't be set to List([])");
+        let err = builder.set("c", Value::List(vec![])).unwrap_err();
+        assert_eq!(err, BuildError::UnknownField("c".into()));
+        assert_eq!(builder.unset("c").unwrap_err(), err);
+
+        builder.set("a", Value::Text("a".into())).unwrap().unset("b").unwrap();
+        let (builder, err) = builder.build().unwrap_err();
+        assert_eq!(err, BuildError::MissingFields(vec!["b"]));
+        assert_eq!(builder.state_bits(), 0b01);
+    }
+
+    #[test]
+    fn round_trip() {
+        let builder = DynItemBuilder::from(ItemBuilder::new().b(vec![2]));
+        assert_eq!(builder.state_bits(), ItemBuilder::<Unset, Set>::STATE_BITS);
+
+        // only the states the fields are in fit
+        let builder = builder.into_typed::<Set, Set>().unwrap_err();
+        let builder = builder.into_typed::<Unset, Unset>().unwrap_err();
+        let typed = builder.into_typed::<Unset, Set>().unwrap();
+        assert_eq!(typed.a("a".into()).construct(), Item { a: "a".into(), b: vec![2] });
+
+        let mut builder = DynItemBuilder::new();
+        builder.set_a("a".into()).set_b(vec![]);
+        assert_eq!(builder.clone().build().unwrap(), Item { a: "a".into(), b: vec![] });
+        assert_eq!(ItemPartial::from(builder).try_build().unwrap().a, "a");
+    }
+
+    #[test]
+    fn from_maps() {
+        let text = |a: &str| Value::Text(a.into());
+        let list = || Value::List(vec![]);
+        let fields = HashMap::from([("a".to_string(), text("a")), ("b".into(), list())]);
+        assert_eq!(Item::try_from(fields).unwrap(), Item { a: "a".into(), b: vec![] });
+
+        let fields = HashMap::from([("a".to_string(), text("a"))]);
+        let err = Item::try_from(fields).unwrap_err();
+        assert_eq!((err.kind(), err.fields()), (ErrorKind::MissingField, vec!["b"]));
+
+        let fields = HashMap::from([("a".to_string(), text("a")), ("c".into(), text("c"))]);
+        let err = Item::try_from(fields).unwrap_err();
+        assert_eq!((err.kind(), err.fields()), (ErrorKind::UnknownField, vec!["c"]));
+
+        let fields = HashMap::from([("b".to_string(), text("b"))]);
+        let err = Item::try_from(fields).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeMismatch);
+        assert_eq!(err.to_string(), "`b` can't be set to Text(\"b\")");
+
+        // nothing is set if one of the fields is rejected
+        let mut builder = DynItemBuilder::new();
+        let err = builder.set_all([("a", text("a")), ("c", text("c"))]).unwrap_err();
+        assert_eq!((err, builder.state_bits()), (BuildError::UnknownField("c".into()), 0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_json() {
+        let fields: HashMap<String, Value> =
+            serde_json::from_str(r#"{ "b": [1, 2], "a": "json" }"#).unwrap();
+        assert_eq!(Item::try_from(fields).unwrap(), Item { a: "json".into(), b: vec![1, 2] });
+
+        let fields: HashMap<String, Value> = serde_json::from_str(r#"{ "a": [] }"#).unwrap();
+        let builder = DynItemBuilder::try_from(fields).unwrap_err();
+        let mismatch = BuildError::TypeMismatch { field: "a".into(), value: Value::List(vec![]) };
+        assert_eq!(builder, mismatch);
+    }
+}
@@ -0,0 +1,215 @@
+//! Defaults that can be overridden through the environment. A field marked like this falls back to
+//! the environment variable `BUILDER_<FIELD>` when it hasn't been set, parsed via `FromStr`, and to
+//! `Default::default()` if the variable doesn't exist either.
+//!
+//! An unparsable variable panics, unless the `try_` variants are used. With the `no-panic` feature
+//! only those are available.
+//!
+//! Derived builders read them for the fields marked `#[builder(from_str_default)]`, in
+//! `construct()` and `try_construct()`.
+//!
+//! [ItemPartial::from_env](../partial/struct.ItemPartial.html#method.from_env) reads every field
+//! under a prefix of your own instead, `APP_A` and `APP_B` for the prefix `app`, leaving the
+//! fields without a variable unset. It is the environment layer of
+//! [layered](../layered/index.html).
+
+use std::env;
+use std::error::Error;
+#[cfg(not(feature = "no-panic"))]
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::dynamic::DynItemBuilder;
+use crate::error::{BuildError, SourceError};
+use crate::partial::ItemPartial;
+use crate::{FieldState, Item, ItemBuilder, Set};
+
+/// The name of the variable overriding the default of `field`.
+pub fn env_var_name(field: &str) -> String {
+    prefixed_var_name("builder", field)
+}
+
+/// The name of the variable for `field` under `prefix`, like `APP_RETRIES`.
+pub fn prefixed_var_name(prefix: &str, field: &str) -> String {
+    format!("{}_{}", prefix.to_uppercase(), field.to_uppercase())
+}
+
+/// Parse the default for `field` from the environment, or use `Default::default()` if the
+/// variable isn't set. A variable that is set but can't be parsed is a configuration error and
+/// panics, silently ignoring it would hide the mistake.
+#[cfg(not(feature = "no-panic"))]
+pub fn from_str_default<T>(field: &str) -> T
+where
+    T: FromStr + Default,
+    T::Err: Debug,
+{
+    let name = env_var_name(field);
+    match env::var(&name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|err| panic!("can't parse {}={:?}: {:?}", name, value, err)),
+        Err(_) => T::default(),
+    }
+}
+
+/// [from_str_default](fn.from_str_default.html) reporting an unparsable variable as an error.
+pub fn try_from_str_default<T>(field: &'static str) -> Result<T, BuildError>
+where
+    T: FromStr + Default,
+    T::Err: Error + Send + Sync + 'static,
+{
+    let var = env_var_name(field);
+    match env::var(&var) {
+        Ok(value) => value.parse().map_err(|err| BuildError::InvalidEnv {
+            field,
+            var,
+            value,
+            source: SourceError::new(err),
+        }),
+        Err(_) => Ok(T::default()),
+    }
+}
+
+/// Parse the variable for `field` under `prefix`, `None` if it isn't set.
+fn parse_var<T>(prefix: &str, field: &'static str) -> Result<Option<T>, BuildError>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    let var = prefixed_var_name(prefix, field);
+    match env::var(&var) {
+        Ok(value) => match value.parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(err) => Err(BuildError::InvalidEnv {
+                field,
+                var,
+                value,
+                source: SourceError::new(err),
+            }),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// A comma-separated list, empty for an empty string.
+struct List(Vec<i32>);
+
+impl FromStr for List {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let items = s.split(',').filter(|item| !item.trim().is_empty());
+        items.map(|item| item.trim().parse()).collect::<Result<_, _>>().map(List)
+    }
+}
+
+impl ItemPartial {
+    /// The fields whose variables under `prefix` are set: `a` as it is, `b` as a comma-separated
+    /// list. A variable that can't be parsed is a `BuildError::InvalidEnv`.
+    pub fn from_env(prefix: &str) -> Result<Self, BuildError> {
+        Ok(ItemPartial {
+            a: parse_var(prefix, "a")?,
+            b: parse_var::<List>(prefix, "b")?.map(|list| list.0),
+        })
+    }
+}
+
+impl DynItemBuilder {
+    /// [ItemPartial::from_env](../partial/struct.ItemPartial.html#method.from_env) as a builder,
+    /// to set the rest by name.
+    pub fn from_env(prefix: &str) -> Result<Self, BuildError> {
+        ItemPartial::from_env(prefix).map(Self::from)
+    }
+}
+
+/// `a` is the field with an environment default here, `b` has to be set as usual.
+impl<A: FieldState> ItemBuilder<A, Set> {
+    /// Construct the item, taking `a` from `BUILDER_A` (or its default) if it hasn't been set.
+    #[cfg(not(feature = "no-panic"))]
+    pub fn construct_with_env_defaults(self) -> Item {
+        Item {
+            a: self.a.into_option().unwrap_or_else(|| from_str_default("a")),
+            b: self.b.into_inner(),
+        }
+    }
+
+    /// [construct_with_env_defaults](#method.construct_with_env_defaults) reporting an
+    /// unparsable variable as an error.
+    pub fn try_construct_with_env_defaults(self) -> Result<Item, BuildError> {
+        let a = match self.a.into_option() {
+            Some(a) => a,
+            None => try_from_str_default("a")?,
+        };
+        Ok(Item { a, b: self.b.into_inner() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    // the environment is shared between the test threads, so `BUILDER_A` is only set here and
+    // every other test uses variables of its own
+    #[cfg(not(feature = "no-panic"))]
+    #[test]
+    fn env_defaults() {
+        env::remove_var("BUILDER_A");
+        let item = ItemBuilder::new().b(vec![]).construct_with_env_defaults();
+        assert_eq!(item.a, "");
+
+        env::set_var("BUILDER_A", "from env");
+        let item = ItemBuilder::new().b(vec![]).construct_with_env_defaults();
+        assert_eq!(item.a, "from env");
+
+        let item = ItemBuilder::new().a("explicit".into()).b(vec![]).construct_with_env_defaults();
+        assert_eq!(item.a, "explicit");
+        env::remove_var("BUILDER_A");
+    }
+
+    #[test]
+    fn var_names() {
+        assert_eq!(env_var_name("retries"), "BUILDER_RETRIES");
+        assert_eq!(prefixed_var_name("app", "retries"), "APP_RETRIES");
+    }
+
+    #[test]
+    fn every_field_under_a_prefix() {
+        env::set_var("ENV_TEST_PREFIX_B", "1, 2,3");
+        let partial = ItemPartial::from_env("env_test_prefix").unwrap();
+        assert_eq!(partial, ItemPartial { a: None, b: Some(vec![1, 2, 3]) });
+
+        env::set_var("ENV_TEST_PREFIX_B", "");
+        let builder = DynItemBuilder::from_env("env_test_prefix").unwrap();
+        assert_eq!(builder.missing_fields(), ["a"]);
+
+        env::set_var("ENV_TEST_PREFIX_B", "1,x");
+        let err = ItemPartial::from_env("env_test_prefix").unwrap_err();
+        assert_eq!(err.to_string(), "can't parse ENV_TEST_PREFIX_B=\"1,x\"");
+        assert_eq!((err.kind(), err.fields()), (ErrorKind::ParseFailed, vec!["b"]));
+        env::remove_var("ENV_TEST_PREFIX_B");
+    }
+
+    #[cfg(not(feature = "no-panic"))]
+    #[test]
+    #[should_panic(expected = "can't parse BUILDER_ENV_TEST_RETRIES")]
+    fn unparsable_values_panic() {
+        env::set_var("BUILDER_ENV_TEST_RETRIES", "many");
+        let _: u8 = from_str_default("env_test_retries");
+    }
+
+    #[test]
+    fn unparsable_values_are_errors() {
+        env::set_var("BUILDER_ENV_TEST_TIMEOUT", "soon");
+        assert_eq!(
+            try_from_str_default::<u32>("env_test_timeout"),
+            Err(BuildError::InvalidEnv {
+                field: "env_test_timeout",
+                var: "BUILDER_ENV_TEST_TIMEOUT".into(),
+                value: "soon".into(),
+                source: SourceError::new("soon".parse::<u32>().unwrap_err()),
+            }),
+        );
+        assert_eq!(try_from_str_default::<u32>("env_test_unset"), Ok(0));
+    }
+}
@@ -0,0 +1,213 @@
+//! The error shared by everything that checks at runtime what the typestate checks at
+//! compile-time. Code that reports errors to metrics or alerting should match on
+//! [kind](enum.BuildError.html#method.kind) and [fields](enum.BuildError.html#method.fields)
+//! instead of the `Display` output, which is meant for humans and may change.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::dynamic::Value;
+use crate::guard::GuardError;
+use crate::validate::ValidationError;
+
+/// What went wrong, stable enough to key metrics and alert routing on. New kinds are added as
+/// new runtime checks come up, so matches on this need a catch-all arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    MissingField,
+    ValidationFailed,
+    ParseFailed,
+    GuardDenied,
+    TypeMismatch,
+    Expired,
+    UnknownField,
+}
+
+impl ErrorKind {
+    /// A stable code for telemetry, like `missing_field`.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::MissingField => "missing_field",
+            ErrorKind::ValidationFailed => "validation_failed",
+            ErrorKind::ParseFailed => "parse_failed",
+            ErrorKind::GuardDenied => "guard_denied",
+            ErrorKind::TypeMismatch => "type_mismatch",
+            ErrorKind::Expired => "expired",
+            ErrorKind::UnknownField => "unknown_field",
+        }
+    }
+}
+
+/// The error that caused a `BuildError`. Sources are compared by their message, that is enough
+/// to compare `BuildError`s in tests. The box makes the `Arc` a thin pointer, keeping
+/// `BuildError` small enough to return next to a builder.
+#[derive(Clone)]
+pub struct SourceError(Arc<Box<dyn Error + Send + Sync>>);
+
+impl SourceError {
+    pub fn new<E: Error + Send + Sync + 'static>(err: E) -> Self {
+        SourceError(Arc::new(Box::new(err)))
+    }
+
+    /// The error itself, for `Error::source` of the errors carrying it.
+    pub fn as_error(&self) -> &(dyn Error + 'static) {
+        &**self.0
+    }
+}
+
+impl fmt::Debug for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl PartialEq for SourceError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Eq for SourceError {}
+
+/// Like [ErrorKind](enum.ErrorKind.html) this grows with the runtime checks, matches on it need a
+/// catch-all arm as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// Required fields that haven't been set, in declaration order.
+    MissingFields(Vec<&'static str>),
+    /// The environment variable `var`, the default of `field` or its value under a prefix, holds
+    /// a value that can't be parsed.
+    InvalidEnv { field: &'static str, var: String, value: String, source: SourceError },
+    /// A setter was rejected by the guard of its field.
+    GuardDenied { field: &'static str },
+    /// A setter's validator rejected the value of `field`.
+    Invalid { field: &'static str, reason: String },
+    /// The builder wasn't finished before its deadline.
+    Expired { deadline: SystemTime },
+    /// A field was set by name, but there is no field called `0`.
+    UnknownField(String),
+    /// A field was set by name to a value of another type.
+    TypeMismatch { field: String, value: Value },
+}
+
+impl BuildError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            BuildError::MissingFields(_) => ErrorKind::MissingField,
+            BuildError::InvalidEnv { .. } => ErrorKind::ParseFailed,
+            BuildError::GuardDenied { .. } => ErrorKind::GuardDenied,
+            BuildError::Invalid { .. } => ErrorKind::ValidationFailed,
+            BuildError::Expired { .. } => ErrorKind::Expired,
+            BuildError::UnknownField(_) => ErrorKind::UnknownField,
+            BuildError::TypeMismatch { .. } => ErrorKind::TypeMismatch,
+        }
+    }
+
+    /// The fields the error is about, in declaration order.
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            BuildError::MissingFields(fields) => fields.clone(),
+            BuildError::InvalidEnv { field, .. } => vec![field],
+            BuildError::GuardDenied { field } => vec![field],
+            BuildError::Invalid { field, .. } => vec![field],
+            BuildError::Expired { .. } => vec![],
+            BuildError::UnknownField(field) => vec![field],
+            BuildError::TypeMismatch { field, .. } => vec![field],
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::MissingFields(fields) => {
+                write!(f, "missing fields: {}", fields.join(", "))
+            }
+            BuildError::InvalidEnv { var, value, .. } => {
+                write!(f, "can't parse {}={:?}", var, value)
+            }
+            BuildError::GuardDenied { field } => write!(f, "the field `{}` is disabled", field),
+            BuildError::Invalid { field, reason } => write!(f, "invalid `{}`: {}", field, reason),
+            BuildError::Expired { .. } => write!(f, "the builder has expired"),
+            BuildError::UnknownField(field) => write!(f, "no field `{}`", field),
+            BuildError::TypeMismatch { field, value } => {
+                write!(f, "`{}` can't be set to {:?}", field, value)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::InvalidEnv { source, .. } => Some(source.as_error()),
+            _ => None,
+        }
+    }
+}
+
+/// Drops the builder the guard handed back, for callers that won't retry.
+impl<Bld> From<GuardError<Bld>> for BuildError {
+    fn from(err: GuardError<Bld>) -> Self {
+        BuildError::GuardDenied { field: err.field }
+    }
+}
+
+/// Drops the builder the validator handed back, for callers that won't retry.
+impl<Bld> From<ValidationError<Bld>> for BuildError {
+    fn from(err: ValidationError<Bld>) -> Self {
+        BuildError::Invalid { field: err.field, reason: err.reason }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::env::try_from_str_default;
+    use crate::guard::ExportBuilder;
+    use crate::validate::rules;
+    use crate::ItemBuilder;
+
+    #[test]
+    fn missing_field() {
+        let (_, err) = ItemBuilder::new().a("a".into()).try_construct().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingField);
+        assert_eq!(err.kind().code(), "missing_field");
+        assert_eq!(err.fields(), ["b"]);
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn parse_failed() {
+        env::set_var("BUILDER_ERROR_TEST_RETRIES", "-1");
+        let err = try_from_str_default::<u8>("error_test_retries").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ParseFailed);
+        assert_eq!(err.fields(), ["error_test_retries"]);
+        assert_eq!(err.source().unwrap().to_string(), "invalid digit found in string");
+        assert_eq!(err.to_string(), "can't parse BUILDER_ERROR_TEST_RETRIES=\"-1\"");
+    }
+
+    #[test]
+    fn guard_denied() {
+        // nothing turns the flag on for an empty key, this is denied no matter what the other
+        // tests do with the flags
+        let err: BuildError = ExportBuilder::new().key(String::new()).unwrap_err().into();
+        assert_eq!(err.kind(), ErrorKind::GuardDenied);
+        assert_eq!(err.fields(), ["key"]);
+    }
+
+    #[test]
+    fn validation_failed() {
+        let err = ItemBuilder::new().validated_a(String::new(), rules::non_empty).unwrap_err();
+        let err = BuildError::from(err);
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        assert_eq!(err.fields(), ["a"]);
+        assert_eq!(err.to_string(), "invalid `a`: must not be empty");
+    }
+}
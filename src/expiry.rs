@@ -0,0 +1,156 @@
+//! Builders that can only be finished for a limited time, for builders holding credentials or
+//! signed URLs that must not stay usable indefinitely. `expires_in` wraps a builder together with
+//! a deadline, once it has passed `construct()` and `try_construct()` fail with
+//! `BuildError::Expired` instead of producing an item. `construct()` is fallible for that reason,
+//! even with every field set.
+//!
+//! The deadline is taken from a [Clock](../clock/trait.Clock.html), tests can inject one with
+//! [expires_in_with_clock](../struct.ItemBuilder.html#method.expires_in_with_clock) instead of
+//! sleeping.
+//!
+//! Derived builders expire with `#[builder(expires_in = ttl)]` on the type, which makes their
+//! `construct()` return a `Result` and adds `construct_with_clock`.
+
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::BuildError;
+use crate::{FieldState, Item, ItemBuilder, Set};
+
+/// A builder with a deadline, the default clock takes up no space.
+#[must_use = "builder must be used to construct a value"]
+pub struct Expiring<Bld, C: Clock = SystemClock> {
+    builder: Bld,
+    /// `None` if the deadline is too far in the future to be represented, it never expires.
+    deadline: Option<SystemTime>,
+    clock: C,
+}
+
+/// The deadline of a builder started now, `None` if it is too far in the future to be
+/// represented and never comes.
+pub fn deadline(clock: &impl Clock, ttl: Duration) -> Option<SystemTime> {
+    clock.now().checked_add(ttl)
+}
+
+/// `BuildError::Expired` once `deadline` has passed.
+pub fn check(clock: &impl Clock, deadline: Option<SystemTime>) -> Result<(), BuildError> {
+    match deadline {
+        Some(deadline) if clock.now() >= deadline => Err(BuildError::Expired { deadline }),
+        _ => Ok(()),
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Refuse to construct the item once `ttl` has passed.
+    pub fn expires_in(self, ttl: Duration) -> Expiring<Self> {
+        self.expires_in_with_clock(ttl, SystemClock)
+    }
+
+    /// [expires_in](#method.expires_in), measured with `clock`.
+    pub fn expires_in_with_clock<C: Clock>(self, ttl: Duration, clock: C) -> Expiring<Self, C> {
+        Expiring { builder: self, deadline: deadline(&clock, ttl), clock }
+    }
+}
+
+impl<Bld, C: Clock> Expiring<Bld, C> {
+    /// When the builder expires, `None` if it never does.
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.check().is_err()
+    }
+
+    fn check(&self) -> Result<(), BuildError> {
+        check(&self.clock, self.deadline)
+    }
+
+    /// The wrapped builder, as long as it hasn't expired. An expired builder is dropped.
+    pub fn into_inner(self) -> Result<Bld, BuildError> {
+        self.check()?;
+        Ok(self.builder)
+    }
+}
+
+impl<A: FieldState, B: FieldState, C: Clock> Expiring<ItemBuilder<A, B>, C> {
+    pub fn a(self, a: String) -> Expiring<ItemBuilder<Set, B>, C> {
+        Expiring { builder: self.builder.a(a), deadline: self.deadline, clock: self.clock }
+    }
+
+    pub fn b(self, b: Vec<i32>) -> Expiring<ItemBuilder<A, Set>, C> {
+        Expiring { builder: self.builder.b(b), deadline: self.deadline, clock: self.clock }
+    }
+
+    /// Construct the item if it hasn't expired and every field has been set. Unlike
+    /// `ItemBuilder::try_construct` the builder isn't handed back, use
+    /// [into_inner](#method.into_inner) to get at it while it is still valid.
+    pub fn try_construct(self) -> Result<Item, BuildError> {
+        self.check()?;
+        self.builder.try_construct().map_err(|(_, err)| err)
+    }
+}
+
+impl<C: Clock> Expiring<ItemBuilder<Set, Set>, C> {
+    /// Construct the item if it hasn't expired.
+    pub fn construct(self) -> Result<Item, BuildError> {
+        self.check()?;
+        Ok(self.builder.construct())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn not_expired() {
+        let builder = ItemBuilder::new().expires_in(TTL).a("a".into()).b(vec![1]);
+        assert!(!builder.is_expired());
+        assert_eq!(builder.construct().unwrap(), Item { a: "a".into(), b: vec![1] });
+    }
+
+    #[test]
+    fn expired() {
+        let now = Cell::new(SystemTime::UNIX_EPOCH);
+        let builder = ItemBuilder::new().a("a".into()).b(vec![]);
+        let builder = builder.expires_in_with_clock(TTL, || now.get());
+        assert_eq!(builder.deadline(), Some(SystemTime::UNIX_EPOCH + TTL));
+
+        now.set(SystemTime::UNIX_EPOCH + TTL);
+        assert!(builder.is_expired());
+        let err = builder.construct().unwrap_err();
+        assert_eq!(err, BuildError::Expired { deadline: SystemTime::UNIX_EPOCH + TTL });
+        assert_eq!(err.kind(), ErrorKind::Expired);
+    }
+
+    #[test]
+    fn injected_clock_is_deterministic() {
+        let now = Cell::new(SystemTime::UNIX_EPOCH);
+        let clock = || now.get();
+
+        let builder = ItemBuilder::new().b(vec![]).expires_in_with_clock(TTL, clock);
+        now.set(SystemTime::UNIX_EPOCH + TTL - Duration::from_nanos(1));
+        let err = builder.try_construct().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingField);
+
+        let builder = ItemBuilder::new().expires_in_with_clock(TTL, clock).a("a".into());
+        now.set(SystemTime::UNIX_EPOCH + 2 * TTL);
+        assert_eq!(builder.try_construct().unwrap_err().kind(), ErrorKind::Expired);
+        now.set(SystemTime::UNIX_EPOCH);
+        let builder = ItemBuilder::new().expires_in_with_clock(TTL, clock);
+        assert!(builder.into_inner().is_ok());
+    }
+
+    #[test]
+    fn unrepresentable_deadline_never_expires() {
+        let builder = ItemBuilder::new().a("a".into()).b(vec![]).expires_in(Duration::MAX);
+        assert_eq!(builder.deadline(), None);
+        assert!(builder.construct().is_ok());
+    }
+}
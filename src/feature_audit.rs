@@ -0,0 +1,77 @@
+//! Checks the feature policy in `Cargo.toml`: the builders themselves must not depend on any
+//! other crate, so every dependency is optional and only enabled by a feature of its own, and the
+//! default features don't enable any of them. Together with building and testing with
+//! `--no-default-features` this keeps integrations from creeping into the core.
+//!
+//! `no-panic` and `forbid-unsafe` are the features that take something away: the first removes
+//! the panicking env APIs for crates that must not link them, the second the APIs that can't do
+//! without unsafe code. They pull in nothing, so they don't weigh on the core.
+
+use std::collections::BTreeMap;
+
+const MANIFEST: &str = include_str!("../Cargo.toml");
+
+/// The entries of a `[section]`, as the raw text after the `=`.
+fn section(name: &str) -> BTreeMap<&'static str, &'static str> {
+    let header = format!("[{}]", name);
+    MANIFEST
+        .lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// What a feature enables, like `["std", "dep:rayon"]`.
+fn enables(value: &str) -> Vec<&str> {
+    value
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|entry| entry.trim().trim_matches('"'))
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[test]
+fn dependencies_are_optional() {
+    let dependencies = section("dependencies");
+    for (name, spec) in &dependencies {
+        assert!(spec.contains("optional = true"), "the dependency `{}` isn't optional", name);
+    }
+
+    let features = section("features");
+    for name in dependencies.keys() {
+        let dep = format!("dep:{}", name);
+        assert!(
+            features.values().any(|value| enables(value).contains(&dep.as_str())),
+            "no feature enables the dependency `{}`",
+            name,
+        );
+    }
+}
+
+#[test]
+fn default_features_pull_in_no_crates() {
+    let features = section("features");
+    let mut pending = vec!["default"];
+    let mut seen = Vec::new();
+    while let Some(feature) = pending.pop() {
+        if seen.contains(&feature) {
+            continue;
+        }
+        seen.push(feature);
+
+        let value = features.get(feature).unwrap_or_else(|| panic!("unknown feature {}", feature));
+        for entry in enables(value) {
+            assert!(
+                !entry.starts_with("dep:") && !entry.contains('/'),
+                "the default features pull in a crate through `{}`",
+                feature,
+            );
+            pending.push(entry);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Reading back the fields that have been set, to log or check a value before passing the builder
+//! on. The getters only exist in the states where their field is `Set`, so reading a field that
+//! was never assigned is a compile error rather than a read of an empty slot:
+//!
+//! ```ignore
+//! let builder = ItemBuilder::new().a("a".into());
+//! assert_eq!(builder.get_a(), "a");
+//! // builder.get_b(); // error: `b` hasn't been set
+//! ```
+//!
+//! The setters already take the fields' names, so the getters are prefixed with `get_`.
+
+use crate::{FieldState, ItemBuilder, Set};
+
+impl<B: FieldState> ItemBuilder<Set, B> {
+    /// The value of `a`, without consuming the builder.
+    pub fn get_a(&self) -> &String {
+        self.a.get()
+    }
+
+    /// Change `a` in place, like pushing onto the string set earlier.
+    pub fn get_a_mut(&mut self) -> &mut String {
+        self.a.get_mut()
+    }
+}
+
+impl<A: FieldState> ItemBuilder<A, Set> {
+    /// Same as [get_a](#method.get_a)
+    pub fn get_b(&self) -> &Vec<i32> {
+        self.b.get()
+    }
+
+    /// Same as [get_a_mut](#method.get_a_mut)
+    pub fn get_b_mut(&mut self) -> &mut Vec<i32> {
+        self.b.get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Item;
+
+    use super::*;
+
+    #[test]
+    fn read_what_has_been_set() {
+        let mut builder = ItemBuilder::new().b(vec![1]);
+        builder.get_b_mut().push(2);
+        assert_eq!(builder.get_b(), &[1, 2]);
+
+        let mut builder = builder.a("a".into());
+        builder.get_a_mut().push('b');
+        assert_eq!(builder.get_a(), "ab");
+        assert_eq!(builder.construct(), Item { a: "ab".into(), b: vec![1, 2] });
+    }
+}
@@ -0,0 +1,255 @@
+//! Constraints between fields. A connection authenticates either with a token or with a username
+//! and a password, never both: the fields of such an exclusive group share one state parameter
+//! instead of having one each. [ConnectionBuilder](struct.ConnectionBuilder.html)'s second
+//! parameter is [NoAuth](enum.NoAuth.html), [TokenAuth](enum.TokenAuth.html) or
+//! [PasswordAuth](struct.PasswordAuth.html), and [AuthState](trait.AuthState.html) maps it to the
+//! states of the three slots. Setting a field of one method clears the fields of the other, and
+//! `construct()` only exists once a method is complete:
+//!
+//! ```ignore
+//! let conn = ConnectionBuilder::new().host(h).username(u).token(t).construct(); // token wins
+//! // ConnectionBuilder::new().host(h).username(u).construct(); // error: no password
+//! ```
+//!
+//! "At least one of" is a bound on the states instead: [AlertBuilder](struct.AlertBuilder.html)
+//! can construct once its pair of states implements [AtLeastOne](trait.AtLeastOne.html), which
+//! every pair but `(Unset, Unset)` does.
+
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    Token(String),
+    Password { username: String, password: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub host: String,
+    pub auth: Auth,
+}
+
+/// No field of the authentication group is set.
+pub enum NoAuth {}
+/// The token is set, username and password aren't.
+pub enum TokenAuth {}
+/// The token isn't set, username and password are in the states `U` and `P`. Can't be
+/// constructed, like the other markers.
+pub struct PasswordAuth<U, P>(Infallible, PhantomData<(U, P)>);
+
+/// The states of the fields of the authentication group, for a state of the group.
+pub trait AuthState {
+    type Token: FieldState;
+    type Username: FieldState;
+    type Password: FieldState;
+}
+
+impl AuthState for NoAuth {
+    type Token = Unset;
+    type Username = Unset;
+    type Password = Unset;
+}
+
+impl AuthState for TokenAuth {
+    type Token = Set;
+    type Username = Unset;
+    type Password = Unset;
+}
+
+impl<U: FieldState, P: FieldState> AuthState for PasswordAuth<U, P> {
+    type Token = Unset;
+    type Username = U;
+    type Password = P;
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct ConnectionBuilder<H: FieldState, G: AuthState> {
+    host: Slot<String, H>,
+    token: Slot<String, G::Token>,
+    username: Slot<String, G::Username>,
+    password: Slot<String, G::Password>,
+}
+
+impl ConnectionBuilder<Unset, NoAuth> {
+    pub fn new() -> Self {
+        ConnectionBuilder {
+            host: Slot::empty(),
+            token: Slot::empty(),
+            username: Slot::empty(),
+            password: Slot::empty(),
+        }
+    }
+}
+
+impl Default for ConnectionBuilder<Unset, NoAuth> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: FieldState, G: AuthState> ConnectionBuilder<H, G> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn host(self, host: String) -> ConnectionBuilder<Set, G> {
+        ConnectionBuilder {
+            host: self.host.write(host),
+            token: self.token,
+            username: self.username,
+            password: self.password,
+        }
+    }
+
+    /// Authenticate with a token, dropping username and password if they were set.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn token(self, token: String) -> ConnectionBuilder<H, TokenAuth> {
+        ConnectionBuilder {
+            host: self.host,
+            token: self.token.write(token),
+            username: self.username.clear(),
+            password: self.password.clear(),
+        }
+    }
+
+    /// Authenticate with username and password, dropping the token if it was set. The password
+    /// keeps its state.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn username(
+        self,
+        username: String,
+    ) -> ConnectionBuilder<H, PasswordAuth<Set, G::Password>> {
+        ConnectionBuilder {
+            host: self.host,
+            token: self.token.clear(),
+            username: self.username.write(username),
+            password: self.password,
+        }
+    }
+
+    /// Same as [username](#method.username)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn password(
+        self,
+        password: String,
+    ) -> ConnectionBuilder<H, PasswordAuth<G::Username, Set>> {
+        ConnectionBuilder {
+            host: self.host,
+            token: self.token.clear(),
+            username: self.username,
+            password: self.password.write(password),
+        }
+    }
+}
+
+impl ConnectionBuilder<Set, TokenAuth> {
+    pub fn construct(self) -> Connection {
+        Connection { host: self.host.into_inner(), auth: Auth::Token(self.token.into_inner()) }
+    }
+}
+
+impl ConnectionBuilder<Set, PasswordAuth<Set, Set>> {
+    pub fn construct(self) -> Connection {
+        let auth = Auth::Password {
+            username: self.username.into_inner(),
+            password: self.password.into_inner(),
+        };
+        Connection { host: self.host.into_inner(), auth }
+    }
+}
+
+/// Implemented by the pairs of states with at least one `Set`.
+pub trait AtLeastOne {}
+
+impl AtLeastOne for (Set, Unset) {}
+impl AtLeastOne for (Unset, Set) {}
+impl AtLeastOne for (Set, Set) {}
+
+/// Where to send an alert, at least one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub email: Option<String>,
+    pub pager: Option<String>,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct AlertBuilder<E: FieldState, P: FieldState> {
+    email: Slot<String, E>,
+    pager: Slot<String, P>,
+}
+
+impl AlertBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        AlertBuilder { email: Slot::empty(), pager: Slot::empty() }
+    }
+}
+
+impl Default for AlertBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: FieldState, P: FieldState> AlertBuilder<E, P> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn email(self, email: String) -> AlertBuilder<Set, P> {
+        AlertBuilder { email: self.email.write(email), pager: self.pager }
+    }
+
+    /// Same as [email](#method.email)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn pager(self, pager: String) -> AlertBuilder<E, Set> {
+        AlertBuilder { email: self.email, pager: self.pager.write(pager) }
+    }
+}
+
+impl<E: FieldState, P: FieldState> AlertBuilder<E, P>
+where
+    (E, P): AtLeastOne,
+{
+    /// The fields that weren't set are `None`.
+    pub fn construct(self) -> Alert {
+        Alert { email: self.email.into_option(), pager: self.pager.into_option() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password(username: &str, password: &str) -> Auth {
+        Auth::Password { username: username.into(), password: password.into() }
+    }
+
+    #[test]
+    fn either_method() {
+        let conn = ConnectionBuilder::new().token("t".into()).host("h".into()).construct();
+        assert_eq!(conn, Connection { host: "h".into(), auth: Auth::Token("t".into()) });
+
+        let builder = ConnectionBuilder::new().password("p".into()).host("h".into());
+        let conn = builder.username("u".into()).construct();
+        assert_eq!(conn.auth, password("u", "p"));
+    }
+
+    #[test]
+    fn switching_methods_clears_the_other() {
+        let builder = ConnectionBuilder::new().host("h".into()).username("u".into());
+        let conn = builder.password("p".into()).token("t".into()).construct();
+        assert_eq!(conn.auth, Auth::Token("t".into()));
+
+        // the password starts over, the token is gone
+        let builder = ConnectionBuilder::new().host("h".into()).token("t".into());
+        let conn = builder.password("p".into()).username("u".into()).construct();
+        assert_eq!(conn.auth, password("u", "p"));
+    }
+
+    #[test]
+    fn at_least_one() {
+        let alert = AlertBuilder::new().pager("p".into()).construct();
+        assert_eq!(alert, Alert { email: None, pager: Some("p".into()) });
+
+        let alert = AlertBuilder::new().email("e".into()).pager("p".into()).construct();
+        assert_eq!((alert.email.as_deref(), alert.pager.as_deref()), (Some("e"), Some("p")));
+    }
+}
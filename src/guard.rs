@@ -0,0 +1,215 @@
+//! Fields behind runtime feature flags. Setting a guarded field while its flag is off is rejected
+//! right away by the setter, instead of at `construct()` far away from the mistake. The typestate
+//! still tracks which fields are set, a guard only decides whether a setter may run at all.
+//!
+//! A guard is a plain function, either without arguments like
+//! [compression_enabled](flags/fn.compression_enabled.html) or taking the value about to be set
+//! by reference like [encryption_allowed](flags/fn.encryption_allowed.html).
+//!
+//! Derived builders guard a field with `#[builder(guard = "flags::compression_enabled")]`, the
+//! setter asks the function through [Guard](trait.Guard.html) and returns a `GuardError` like
+//! the ones here.
+
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt;
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+/// The runtime flags and the guards reading them.
+pub mod flags {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    pub static COMPRESSION: AtomicBool = AtomicBool::new(false);
+    pub static ENCRYPTION: AtomicBool = AtomicBool::new(false);
+
+    pub fn compression_enabled() -> bool {
+        COMPRESSION.load(Ordering::Relaxed)
+    }
+
+    /// Keys can only be set with encryption on, and an empty key is never allowed.
+    pub fn encryption_allowed(key: &str) -> bool {
+        ENCRYPTION.load(Ordering::Relaxed) && !key.is_empty()
+    }
+}
+
+/// The functions a derived setter can be guarded by: one without arguments, or one taking the
+/// value by reference, or anything the value borrows as, like `&str` for a `String`. `M` tells
+/// the two kinds apart, it is inferred from the function.
+pub trait Guard<V, M> {
+    /// Whether `value` may be set.
+    fn allows(&self, value: &V) -> bool;
+}
+
+impl<F: Fn() -> bool, V> Guard<V, ()> for F {
+    fn allows(&self, _: &V) -> bool {
+        self()
+    }
+}
+
+impl<F, V, B> Guard<V, fn(&B)> for F
+where
+    F: Fn(&B) -> bool,
+    V: Borrow<B>,
+    B: ?Sized,
+{
+    fn allows(&self, value: &V) -> bool {
+        self(value.borrow())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub path: String,
+    pub compression: u8,
+    pub key: String,
+}
+
+/// A setter was rejected by the guard of its field. The builder is handed back unchanged, so the
+/// caller can try again once the flag is on or continue without the field.
+pub struct GuardError<Bld> {
+    pub field: &'static str,
+    builder: Bld,
+}
+
+impl<Bld> GuardError<Bld> {
+    /// The error a derived setter returns, the derive can't name the private fields.
+    #[doc(hidden)]
+    pub fn __derived(field: &'static str, builder: Bld) -> Self {
+        GuardError { field, builder }
+    }
+
+    /// The builder the setter was called on.
+    pub fn into_builder(self) -> Bld {
+        self.builder
+    }
+}
+
+impl<Bld> fmt::Debug for GuardError<Bld> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GuardError").field("field", &self.field).finish_non_exhaustive()
+    }
+}
+
+impl<Bld> fmt::Display for GuardError<Bld> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the field `{}` is disabled", self.field)
+    }
+}
+
+impl<Bld> Error for GuardError<Bld> {}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct ExportBuilder<P: FieldState, C: FieldState, K: FieldState> {
+    path: Slot<String, P>,
+    compression: Slot<u8, C>,
+    key: Slot<String, K>,
+}
+
+/// Shows the fields that are set, so results of the guarded setters can be unwrapped.
+impl<P: FieldState, C: FieldState, K: FieldState> fmt::Debug for ExportBuilder<P, C, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExportBuilder")
+            .field("path", &self.path.as_option())
+            .field("compression", &self.compression.as_option())
+            .field("key", &self.key.as_option())
+            .finish()
+    }
+}
+
+impl ExportBuilder<Unset, Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            path: Slot::empty(),
+            compression: Slot::empty(),
+            key: Slot::empty(),
+        }
+    }
+}
+
+impl Default for ExportBuilder<Unset, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: FieldState, C: FieldState, K: FieldState> ExportBuilder<P, C, K> {
+    pub fn path(self, path: String) -> ExportBuilder<Set, C, K> {
+        ExportBuilder {
+            path: self.path.write(path),
+            compression: self.compression,
+            key: self.key,
+        }
+    }
+
+    /// Guarded by [compression_enabled](flags/fn.compression_enabled.html).
+    pub fn compression(
+        self,
+        compression: u8,
+    ) -> Result<ExportBuilder<P, Set, K>, GuardError<Self>> {
+        if !flags::compression_enabled() {
+            return Err(GuardError { field: "compression", builder: self });
+        }
+        Ok(ExportBuilder {
+            path: self.path,
+            compression: self.compression.write(compression),
+            key: self.key,
+        })
+    }
+
+    /// Guarded by [encryption_allowed](flags/fn.encryption_allowed.html).
+    pub fn key(self, key: String) -> Result<ExportBuilder<P, C, Set>, GuardError<Self>> {
+        if !flags::encryption_allowed(&key) {
+            return Err(GuardError { field: "key", builder: self });
+        }
+        Ok(ExportBuilder {
+            path: self.path,
+            compression: self.compression,
+            key: self.key.write(key),
+        })
+    }
+}
+
+impl ExportBuilder<Set, Set, Set> {
+    pub fn construct(self) -> Export {
+        Export {
+            path: self.path.into_inner(),
+            compression: self.compression.into_inner(),
+            key: self.key.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    // a single test, the flags are shared between the test threads
+    #[test]
+    fn guarded_setters() {
+        flags::COMPRESSION.store(false, Ordering::Relaxed);
+        flags::ENCRYPTION.store(true, Ordering::Relaxed);
+
+        // denied, the error hands back the builder with the path still set
+        let err = ExportBuilder::new().path("out".into()).compression(9).unwrap_err();
+        assert_eq!(err.field, "compression");
+        assert_eq!(err.to_string(), "the field `compression` is disabled");
+        let builder = err.into_builder();
+
+        // denied by the value, even though the flag is on
+        let builder = builder.key(String::new()).unwrap_err().into_builder();
+
+        // allowed once the flag is turned on
+        flags::COMPRESSION.store(true, Ordering::Relaxed);
+        let export = builder.compression(9).unwrap().key("secret".into()).unwrap().construct();
+        assert_eq!(export.path, "out");
+        assert_eq!(export.compression, 9);
+        assert_eq!(export.key, "secret");
+
+        flags::COMPRESSION.store(false, Ordering::Relaxed);
+        flags::ENCRYPTION.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,149 @@
+//! A target built in two phases. The spec holds the plain data and can be prepared on any thread,
+//! the build phase adds resources that have to stay on the thread they were created on. Each phase
+//! has its own typestate: the build phase can only be entered with a complete spec, and the
+//! resources can only be set there, `JobSpecBuilder` simply has no setter for them.
+//!
+//! Derived builders split the same way with `#[builder(phase = 2)]` on the resources.
+
+use std::rc::Rc;
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+/// Something that can't leave its thread, standing in for a database handle and the like.
+pub type Connection = Rc<String>;
+
+#[derive(Debug)]
+pub struct Job {
+    pub name: String,
+    pub retries: u32,
+    pub connection: Connection,
+}
+
+/// The phase-1 fields of a `Job`, all owned data. This is `Send` and `'static`, so it can be
+/// prepared, stored or sent anywhere before the job is finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSpec {
+    pub name: String,
+    pub retries: u32,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct JobSpecBuilder<N: FieldState, R: FieldState> {
+    name: Slot<String, N>,
+    retries: Slot<u32, R>,
+}
+
+impl JobSpecBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            name: Slot::empty(),
+            retries: Slot::empty(),
+        }
+    }
+}
+
+impl Default for JobSpecBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: FieldState, R: FieldState> JobSpecBuilder<N, R> {
+    pub fn name(self, name: String) -> JobSpecBuilder<Set, R> {
+        JobSpecBuilder {
+            name: self.name.write(name),
+            retries: self.retries,
+        }
+    }
+
+    pub fn retries(self, retries: u32) -> JobSpecBuilder<N, Set> {
+        JobSpecBuilder {
+            name: self.name,
+            retries: self.retries.write(retries),
+        }
+    }
+}
+
+impl JobSpecBuilder<Set, Set> {
+    pub fn construct(self) -> JobSpec {
+        JobSpec {
+            name: self.name.into_inner(),
+            retries: self.retries.into_inner(),
+        }
+    }
+}
+
+impl From<JobSpec> for JobSpecBuilder<Set, Set> {
+    fn from(spec: JobSpec) -> Self {
+        JobSpecBuilder::new().name(spec.name).retries(spec.retries)
+    }
+}
+
+/// The second phase, created from a complete spec.
+#[must_use = "builder must be used to construct a value"]
+pub struct JobBuilder<C: FieldState> {
+    spec: JobSpec,
+    connection: Slot<Connection, C>,
+}
+
+impl JobBuilder<Unset> {
+    pub fn from_spec(spec: JobSpec) -> Self {
+        Self {
+            spec,
+            connection: Slot::empty(),
+        }
+    }
+}
+
+impl<C: FieldState> JobBuilder<C> {
+    pub fn connection(self, connection: Connection) -> JobBuilder<Set> {
+        JobBuilder {
+            spec: self.spec,
+            connection: self.connection.write(connection),
+        }
+    }
+}
+
+impl JobBuilder<Set> {
+    pub fn construct(self) -> Job {
+        Job {
+            name: self.spec.name,
+            retries: self.spec.retries,
+            connection: self.connection.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn assert_send<T: Send + 'static>(_: &T) {}
+
+    #[test]
+    fn finish_on_another_thread() {
+        let spec = JobSpecBuilder::new().name("nightly".into()).retries(3).construct();
+        assert_send(&spec);
+
+        let job = thread::spawn(move || {
+            // the connection is created on this thread and never leaves it
+            let job = JobBuilder::from_spec(spec).connection(Rc::new("db".into())).construct();
+            (job.name, job.retries, job.connection.len())
+        });
+        assert_eq!(job.join().unwrap(), ("nightly".into(), 3, 2));
+    }
+
+    #[test]
+    fn partial_spec_builders_are_send() {
+        assert_send(&JobSpecBuilder::new().retries(1));
+    }
+
+    #[test]
+    fn spec_round_trip() {
+        let spec = JobSpec { name: "a".into(), retries: 1 };
+        assert_eq!(JobSpecBuilder::from(spec.clone()).construct(), spec);
+    }
+}
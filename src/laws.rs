@@ -0,0 +1,157 @@
+//! The round-trip laws every buildable target has to satisfy, as assertions that tests of a
+//! target can call with as many values as they like:
+//!
+//! - taking an item apart into a builder and constructing it again gives the same item,
+//! - a cloned builder constructs the same item as the original,
+//! - a complete partial survives the trip to an item, a builder and back to a partial.
+//!
+//! They catch the asymmetries that creep in as conversions accumulate: defaults applied twice,
+//! fields swapped, empty values turned into missing ones. [check_laws](fn.check_laws.html) runs
+//! all of them over generated values.
+//!
+//! Derived targets with `#[builder(into_builder)]` implement `Buildable` with the generated
+//! `into_builder` and `construct`, the partial is theirs to define. `tests/derive.rs` does so for
+//! a derived `Lookup`.
+
+use std::fmt::Debug;
+
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::{Item, ItemBuilder, Set};
+
+/// A target that can be taken apart into its complete builder and its partial.
+pub trait Buildable: Sized + Clone + PartialEq + Debug {
+    /// The builder with every field set.
+    type Builder: Clone;
+    type Partial: Clone + PartialEq + Debug;
+
+    fn into_builder(self) -> Self::Builder;
+    fn construct(builder: Self::Builder) -> Self;
+    fn into_partial(builder: Self::Builder) -> Self::Partial;
+    fn try_from_partial(partial: Self::Partial) -> Result<Self, BuildError>;
+}
+
+impl Buildable for Item {
+    type Builder = ItemBuilder<Set, Set>;
+    type Partial = ItemPartial;
+
+    fn into_builder(self) -> Self::Builder {
+        self.into()
+    }
+
+    fn construct(builder: Self::Builder) -> Self {
+        builder.construct()
+    }
+
+    fn into_partial(builder: Self::Builder) -> Self::Partial {
+        builder.into_partial()
+    }
+
+    fn try_from_partial(partial: Self::Partial) -> Result<Self, BuildError> {
+        partial.try_build()
+    }
+}
+
+/// `item.into_builder().construct() == item`
+pub fn assert_roundtrip<T: Buildable>(item: T) {
+    let rebuilt = T::construct(item.clone().into_builder());
+    assert_eq!(rebuilt, item, "into_builder() followed by construct() changed the item");
+}
+
+/// `builder.clone().construct() == builder.construct()`
+pub fn assert_clone_construct<T: Buildable>(builder: T::Builder) {
+    let cloned = T::construct(builder.clone());
+    assert_eq!(cloned, T::construct(builder), "a cloned builder constructed a different item");
+}
+
+/// A complete `partial` turned into an item, its builder and back into a partial is unchanged.
+pub fn assert_partial_stable<T: Buildable>(partial: T::Partial) {
+    let item = T::try_from_partial(partial.clone()).expect("the partial has to be complete");
+    let again = T::into_partial(item.into_builder());
+    assert_eq!(again, partial, "the partial changed on its way through the builder");
+}
+
+/// Run every law on `cases` items produced by `generate` from a deterministic seed.
+pub fn check_laws<T: Buildable>(cases: usize, mut generate: impl FnMut(&mut Seed) -> T) {
+    let mut seed = Seed(0x1a45);
+    for _ in 0..cases {
+        let item = generate(&mut seed);
+        assert_roundtrip(item.clone());
+        assert_clone_construct::<T>(item.clone().into_builder());
+        assert_partial_stable::<T>(T::into_partial(item.into_builder()));
+    }
+}
+
+/// Deterministic pseudo-random numbers for generating test values.
+#[derive(Debug, Clone)]
+pub struct Seed(u64);
+
+impl Seed {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    pub fn string(&mut self) -> String {
+        let len = self.next_u64() % 8;
+        (0..len).map(|_| char::from(b'a' + (self.next_u64() % 26) as u8)).collect()
+    }
+
+    pub fn numbers(&mut self) -> Vec<i32> {
+        let len = self.next_u64() % 8;
+        (0..len).map(|_| self.next_u64() as i32).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use super::*;
+
+    #[test]
+    fn item_satisfies_the_laws() {
+        check_laws(200, |seed| Item { a: seed.string(), b: seed.numbers() });
+        // empty values are values, they must not turn into missing fields
+        check_laws(1, |_| Item { a: String::new(), b: vec![] });
+    }
+
+    /// A target whose `into_builder` forgets to carry `b` over.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Broken {
+        a: String,
+        b: Vec<i32>,
+    }
+
+    impl Buildable for Broken {
+        type Builder = ItemBuilder<Set, Set>;
+        type Partial = ItemPartial;
+
+        fn into_builder(self) -> Self::Builder {
+            ItemBuilder::new().a(self.a).b(Vec::new())
+        }
+
+        fn construct(builder: Self::Builder) -> Self {
+            let Item { a, b } = builder.construct();
+            Broken { a, b }
+        }
+
+        fn into_partial(builder: Self::Builder) -> Self::Partial {
+            builder.into_partial()
+        }
+
+        fn try_from_partial(partial: Self::Partial) -> Result<Self, BuildError> {
+            partial.try_build().map(|Item { a, b }| Broken { a, b })
+        }
+    }
+
+    #[test]
+    fn broken_into_builder_is_caught() {
+        let result = panic::catch_unwind(|| {
+            check_laws(10, |seed| Broken { a: seed.string(), b: vec![seed.next_u64() as i32] })
+        });
+        let message = result.unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+        assert!(message.contains("into_builder() followed by construct() changed the item"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Configuration loaded from layers: built-in defaults, a file, the environment. A field takes
+//! its value from the highest layer that has one, the environment over the file over the
+//! defaults, whatever order the layers are added in:
+//!
+//! ```ignore
+//! let (builder, report) = Layered::new()
+//!     .env("app")?
+//!     .file(serde_json::from_str(&contents)?)
+//!     .defaults(ItemPartial { a: Some("default".into()), b: None })
+//!     .load();
+//! if !report.is_complete() {
+//!     eprintln!("missing: {}", report.missing.join(", "));
+//! }
+//! let item = builder.into_typed::<Set, Set>()?.construct();
+//! ```
+//!
+//! The result is a [DynItemBuilder](../dynamic/struct.DynItemBuilder.html), so the fields still
+//! missing can be set by name or the builder handed to the typestate builder once it is complete.
+//! The report says which layer each field came from and which required fields are missing.
+
+use crate::dynamic::DynItemBuilder;
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::ItemBuilder;
+
+/// The layer a field's value came from, ordered from lowest to highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    Defaults,
+    File,
+    Env,
+}
+
+/// Collects the layers, see [layered](index.html).
+#[derive(Debug, Default)]
+pub struct Layered {
+    fields: ItemPartial,
+    sources: [Option<Source>; 2],
+}
+
+impl Layered {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the values of `partial` from `source`, keeping those of higher layers.
+    pub fn layer(mut self, source: Source, partial: ItemPartial) -> Self {
+        let wins = |current: Option<Source>| current.is_none_or(|current| source >= current);
+        if partial.a.is_some() && wins(self.sources[0]) {
+            self.fields.a = partial.a;
+            self.sources[0] = Some(source);
+        }
+        if partial.b.is_some() && wins(self.sources[1]) {
+            self.fields.b = partial.b;
+            self.sources[1] = Some(source);
+        }
+        self
+    }
+
+    pub fn defaults(self, partial: ItemPartial) -> Self {
+        self.layer(Source::Defaults, partial)
+    }
+
+    /// The fields loaded from a file, e.g. with the `serde` feature.
+    pub fn file(self, partial: ItemPartial) -> Self {
+        self.layer(Source::File, partial)
+    }
+
+    /// The variables under `prefix`, see
+    /// [ItemPartial::from_env](../partial/struct.ItemPartial.html#method.from_env).
+    pub fn env(self, prefix: &str) -> Result<Self, BuildError> {
+        Ok(self.layer(Source::Env, ItemPartial::from_env(prefix)?))
+    }
+
+    /// The builder filled from every layer and what is still missing.
+    pub fn load(self) -> (DynItemBuilder, Report) {
+        let builder = DynItemBuilder::from(self.fields);
+        let sources = ItemBuilder::FIELDS.iter().map(|field| field.name).zip(self.sources);
+        let report = Report { sources: sources.collect(), missing: builder.missing_fields() };
+        (builder, report)
+    }
+}
+
+/// Where the fields came from after loading the layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Every field with the layer it came from, `None` if no layer had it.
+    pub sources: Vec<(&'static str, Option<Source>)>,
+    /// The required fields no layer had, in declaration order.
+    pub missing: Vec<&'static str>,
+}
+
+impl Report {
+    /// Whether the builder can be built without setting anything else.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::Item;
+
+    fn partial(a: &str) -> ItemPartial {
+        ItemPartial { a: Some(a.into()), b: None }
+    }
+
+    #[test]
+    fn higher_layers_win() {
+        env::set_var("LAYERED_TEST_A", "env");
+        let (builder, report) = Layered::new()
+            .env("layered_test")
+            .unwrap()
+            .defaults(ItemPartial { a: Some("default".into()), b: Some(vec![1]) })
+            .file(partial("file"))
+            .load();
+        env::remove_var("LAYERED_TEST_A");
+
+        assert_eq!(report.sources, [("a", Some(Source::Env)), ("b", Some(Source::Defaults))]);
+        assert!(report.is_complete());
+        assert_eq!(builder.build().unwrap(), Item { a: "env".into(), b: vec![1] });
+    }
+
+    #[test]
+    fn missing_fields_are_reported() {
+        let (mut builder, report) = Layered::new().file(partial("file")).load();
+        assert_eq!(report.sources, [("a", Some(Source::File)), ("b", None)]);
+        assert_eq!(report.missing, ["b"]);
+
+        builder.set_b(vec![]);
+        assert_eq!(builder.build().unwrap().a, "file");
+    }
+}
@@ -0,0 +1,131 @@
+//! Fields whose values are computed when the item is constructed, not when the field is set.
+//! [LazyItemBuilder](struct.LazyItemBuilder.html) stores a [Lazy](enum.Lazy.html) in each slot,
+//! either a value or a boxed closure, and only calls the closures in `construct()`:
+//!
+//! ```ignore
+//! let builder = LazyItemBuilder::new().a_with(|| expensive_name()).b(vec![]);
+//! let item = builder.construct(); // `expensive_name` runs here
+//! ```
+//!
+//! The slots don't care what they hold, so the states work exactly like `ItemBuilder`'s. A
+//! closure that is replaced or abandoned is dropped without being called, dropping whatever it
+//! captured. Closures have to be `Send` so the builder stays `Send`.
+
+use crate::slot::Slot;
+use crate::{FieldState, Item, ItemBuilder, Set, Unset};
+
+/// A value, or the closure computing it.
+pub enum Lazy<T> {
+    Value(T),
+    Thunk(Box<dyn FnOnce() -> T + Send>),
+}
+
+impl<T> Lazy<T> {
+    /// The value, calling the closure if there is one.
+    pub fn force(self) -> T {
+        match self {
+            Lazy::Value(value) => value,
+            Lazy::Thunk(thunk) => thunk(),
+        }
+    }
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct LazyItemBuilder<A: FieldState, B: FieldState> {
+    a: Slot<Lazy<String>, A>,
+    b: Slot<Lazy<Vec<i32>>, B>,
+}
+
+impl LazyItemBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        LazyItemBuilder { a: Slot::empty(), b: Slot::empty() }
+    }
+}
+
+impl Default for LazyItemBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: FieldState, B: FieldState> LazyItemBuilder<A, B> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a(self, a: String) -> LazyItemBuilder<Set, B> {
+        LazyItemBuilder { a: self.a.write(Lazy::Value(a)), b: self.b }
+    }
+
+    /// Set `a` to whatever `f` returns once the item is constructed.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a_with<F>(self, f: F) -> LazyItemBuilder<Set, B>
+    where
+        F: FnOnce() -> String + Send + 'static,
+    {
+        LazyItemBuilder { a: self.a.write(Lazy::Thunk(Box::new(f))), b: self.b }
+    }
+
+    /// Same as [a](#method.a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b(self, b: Vec<i32>) -> LazyItemBuilder<A, Set> {
+        LazyItemBuilder { a: self.a, b: self.b.write(Lazy::Value(b)) }
+    }
+
+    /// Same as [a_with](#method.a_with)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b_with<F>(self, f: F) -> LazyItemBuilder<A, Set>
+    where
+        F: FnOnce() -> Vec<i32> + Send + 'static,
+    {
+        LazyItemBuilder { a: self.a, b: self.b.write(Lazy::Thunk(Box::new(f))) }
+    }
+}
+
+impl LazyItemBuilder<Set, Set> {
+    /// Construct the item, calling the closures of the fields that were set with one, `a`'s
+    /// first.
+    pub fn construct(self) -> Item {
+        Item { a: self.a.into_inner().force(), b: self.b.into_inner().force() }
+    }
+}
+
+/// The values that are set become values of the lazy builder.
+impl<A: FieldState, B: FieldState> From<ItemBuilder<A, B>> for LazyItemBuilder<A, B> {
+    fn from(builder: ItemBuilder<A, B>) -> Self {
+        let partial = builder.into_partial();
+        LazyItemBuilder {
+            a: Slot::from_option(partial.a.map(Lazy::Value)),
+            b: Slot::from_option(partial.b.map(Lazy::Value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn evaluated_in_construct() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let builder = LazyItemBuilder::new().b(vec![1]).a_with(move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+            "lazy".into()
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assert_eq!(builder.construct(), Item { a: "lazy".into(), b: vec![1] });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn replaced_thunks_are_never_called() {
+        let builder = LazyItemBuilder::new().a_with(|| panic!("replaced")).a("a".into());
+        let item = builder.b_with(|| vec![2]).construct();
+        assert_eq!(item, Item { a: "a".into(), b: vec![2] });
+
+        let builder = LazyItemBuilder::from(ItemBuilder::new().a("eager".into()));
+        assert_eq!(builder.b_with(Vec::new).construct().a, "eager");
+    }
+}
@@ -0,0 +1,497 @@
+//! Builders checked at compile-time. Every field of a builder is a [Slot](slot/struct.Slot.html)
+//! whose type-parameter is either [Set](enum.Set.html) or [Unset](enum.Unset.html), setters move
+//! the slots into a builder with that parameter changed, and `construct()` only compiles once all
+//! of them are `Set`. Forgetting a field is a type error naming the field instead of a runtime
+//! panic.
+//!
+//! The storage layer is generic and meant to be reused: [slot](slot/index.html) holds all of the
+//! unsafe code, a builder is a plain struct of slots without any unsafe code or destructor of its
+//! own. [ItemBuilder](struct.ItemBuilder.html) building an [Item](struct.Item.html) is the worked
+//! example, the modules add one feature each on top of it or on a builder of their own.
+//!
+//! ```ignore
+//! let item = ItemBuilder::new().a("a".into()).b(vec![1]).construct();
+//! // ItemBuilder::new().a("a".into()).construct(); // error: `b` hasn't been set
+//! ```
+//!
+//! Everything but the storage layer, [config](config/index.html) and
+//! [drop_order](drop_order/index.html) needs the `std` feature, which is on by default. Without
+//! it the crate is `#![no_std]` and needs no allocator either, so builders of your own can be
+//! written on top of the slots in firmware, with `String` and `Vec` fields if you bring `alloc`.
+//!
+//! With the `derive` feature, `#[derive(TypesafeBuilder)]` generates such a builder for any
+//! struct with named fields. Builders nest through [nested](nested/index.html), a field can be
+//! configured by its own builder with completeness still checked at compile-time.
+//!
+//! Without a proc-macro, [builder!](macro.builder.html) declares a struct and the same kind of
+//! builder from a `macro_rules!` macro.
+//!
+//! With the `forbid-unsafe` feature the crate is `#![forbid(unsafe_code)]`: the slots store an
+//! `Option` instead of uninitialized memory, see [slot](slot/index.html), and the few APIs
+//! that can't be written without unsafe code, the FFI handle, the C API and the raw slot
+//! accessors, are left out.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "forbid-unsafe", forbid(unsafe_code))]
+
+#[cfg(feature = "std")]
+use slot::Slot;
+
+#[cfg(feature = "derive")]
+pub use typestuff_derive::TypesafeBuilder;
+
+#[cfg(all(feature = "abi", not(feature = "forbid-unsafe")))]
+pub mod abi;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod boxed;
+#[cfg(feature = "std")]
+pub mod capacity;
+#[cfg(all(feature = "capi", not(feature = "forbid-unsafe")))]
+pub mod capi;
+#[cfg(feature = "clap")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod cmp;
+#[cfg(feature = "std")]
+pub mod command;
+pub mod config;
+#[cfg(feature = "std")]
+pub mod defaults;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod display;
+pub mod drop_order;
+#[cfg(feature = "std")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod env;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod expiry;
+#[cfg(test)]
+mod feature_audit;
+#[cfg(feature = "std")]
+pub mod getters;
+#[cfg(feature = "std")]
+pub mod groups;
+#[cfg(feature = "std")]
+pub mod guard;
+#[cfg(feature = "std")]
+pub mod job;
+#[cfg(feature = "std")]
+pub mod laws;
+#[cfg(feature = "std")]
+pub mod lazy;
+#[cfg(feature = "std")]
+pub mod layered;
+#[macro_use]
+pub mod macros;
+#[cfg(feature = "std")]
+pub mod mask;
+#[cfg(feature = "std")]
+pub mod migrate;
+#[cfg(all(test, feature = "std", not(feature = "forbid-unsafe")))]
+mod miri_surface;
+pub mod nested;
+#[cfg(feature = "std")]
+pub mod ops;
+#[cfg(all(test, feature = "std"))]
+mod panic_audit;
+#[cfg(feature = "std")]
+pub mod partial;
+#[cfg(feature = "std")]
+pub mod pending;
+#[cfg(feature = "std")]
+#[macro_use]
+pub mod policy;
+#[cfg(feature = "std")]
+#[macro_use]
+pub mod presets;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod push;
+pub mod reflection;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod request;
+#[cfg(feature = "std")]
+pub mod setters;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod sharing;
+#[cfg(not(feature = "forbid-unsafe"))]
+pub mod slot;
+#[cfg(feature = "forbid-unsafe")]
+#[path = "safe_slot.rs"]
+pub mod slot;
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod staged;
+#[cfg(feature = "std")]
+pub mod states;
+#[cfg(feature = "std")]
+pub mod strings;
+#[cfg(feature = "std")]
+pub mod targets;
+pub mod ticket;
+#[cfg(feature = "std")]
+pub mod validate;
+
+/// An unconstructible-type, use it as type-information for the builder indicating a value has not
+/// been set.
+pub enum Unset {}
+/// Another unconstructible type, indicating a value has been set.
+pub enum Set {}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Set {}
+    impl Sealed for super::Unset {}
+}
+
+/// The states a field of the builder can be in. This is sealed, only `Set` and `Unset` implement
+/// it, so the destructor can rely on `IS_SET` to know which fields to drop.
+pub trait FieldState: sealed::Sealed + 'static {
+    const IS_SET: bool;
+}
+
+impl FieldState for Set {
+    const IS_SET: bool = true;
+}
+
+impl FieldState for Unset {
+    const IS_SET: bool = false;
+}
+
+/// Implemented by `Unset` only. Bounding a state with it narrows it down to `Unset` while keeping
+/// it generic, see [states](states/index.html).
+pub trait IsUnset: FieldState {}
+
+impl IsUnset for Unset {}
+
+/// Implemented by `Set` only, the counterpart of `IsUnset`. A state bounded by it holds a value,
+/// `into_set` hands over its slot with the state spelled out.
+pub trait IsSet: FieldState + Sized {
+    fn into_set<T>(slot: slot::Slot<T, Self>) -> slot::Slot<T, Set>;
+}
+
+impl IsSet for Set {
+    fn into_set<T>(slot: slot::Slot<T, Set>) -> slot::Slot<T, Set> {
+        slot
+    }
+}
+
+// The builder never holds a marker, the states are only types, so the markers have to stay
+// zero-sized. If a marker ever needs to carry data this fails and forces a rethink.
+const _: () = assert!(core::mem::size_of::<Set>() == 0);
+const _: () = assert!(core::mem::size_of::<Unset>() == 0);
+const _: () = assert!(Set::IS_SET && !Unset::IS_SET);
+
+/// The item we construct in the end. We use types that free memory on drop to show the builder
+/// does handle memory correctly.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Item {
+    pub a: String,
+    pub b: Vec<i32>,
+}
+
+/// The builder, containing the fields that will be passed to the item and the types that are used
+/// at compile-time to check if the fields are set. A generic type either is `Unset` or `Set`,
+/// indicating whether the corresponding field has been set or not. The fields are stored in
+/// [Slot](slot/struct.Slot.html)s which use the same type-information to know whether they
+/// contain a value or uninitialized memory, so the builder itself needs no unsafe code and no
+/// custom destructor. A dropped builder drops its set fields in declaration order, see
+/// [drop_order](drop_order/index.html).
+///
+/// The builder is `repr(C)` and its slots are `repr(transparent)`, so every combination of states
+/// has the same size, alignment and field offsets. A state change never moves a field to another
+/// place in the builder, the assertions below fail the build if that ever stops being true.
+#[cfg(feature = "std")]
+#[repr(C)]
+#[must_use = "builder must be used to construct a value"]
+pub struct ItemBuilder<A: FieldState, B: FieldState> {
+    a: Slot<String, A>,
+    b: Slot<Vec<i32>, B>,
+}
+
+#[cfg(feature = "std")]
+const _: () = {
+    use core::mem::offset_of;
+    use slot::same_layout;
+
+    const fn like_empty<A: FieldState, B: FieldState>() -> bool {
+        type Empty = ItemBuilder<Unset, Unset>;
+        same_layout::<ItemBuilder<A, B>, Empty>()
+            && offset_of!(ItemBuilder<A, B>, a) == offset_of!(Empty, a)
+            && offset_of!(ItemBuilder<A, B>, b) == offset_of!(Empty, b)
+    }
+    assert!(
+        like_empty::<Set, Unset>() && like_empty::<Unset, Set>() && like_empty::<Set, Set>(),
+        "the layout of `ItemBuilder` depends on its states"
+    );
+    assert!(same_layout::<Slot<String, Unset>, String>());
+    assert!(same_layout::<Slot<Vec<i32>, Unset>, Vec<i32>>());
+};
+
+#[cfg(feature = "std")]
+impl ItemBuilder<Unset, Unset> {
+    /// Construct a new builder with empty slots
+    pub fn new() -> Self {
+        Self {
+            a: Slot::empty(),
+            b: Slot::empty(),
+        }
+    }
+
+    /// Build `count` items in one go. `configure` gets the index and a fresh builder for every
+    /// item and has to hand back a builder with all fields set.
+    pub fn build_many<F>(count: usize, mut configure: F) -> Vec<Item>
+    where
+        F: FnMut(usize, ItemBuilder<Unset, Unset>) -> ItemBuilder<Set, Set>,
+    {
+        (0..count)
+            .map(|index| configure(index, ItemBuilder::new()).construct())
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ItemBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clones the fields that are set.
+#[cfg(feature = "std")]
+impl<A: FieldState, B: FieldState> Clone for ItemBuilder<A, B> {
+    fn clone(&self) -> Self {
+        Self {
+            a: self.a.clone(),
+            b: self.b.clone(),
+        }
+    }
+}
+
+/// Take an item apart again, e.g. to change a field and construct a new one.
+#[cfg(feature = "std")]
+impl From<Item> for ItemBuilder<Set, Set> {
+    fn from(item: Item) -> Self {
+        ItemBuilder::new().a(item.a).b(item.b)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Item {
+    /// A complete builder holding the fields of this item.
+    pub fn into_builder(self) -> ItemBuilder<Set, Set> {
+        self.into()
+    }
+
+    /// Build an item without naming the builder. The closure's return type makes sure every
+    /// field is set, forgetting one is a type error in the closure.
+    pub fn build_with<F>(configure: F) -> Item
+    where
+        F: FnOnce(ItemBuilder<Unset, Unset>) -> ItemBuilder<Set, Set>,
+    {
+        configure(ItemBuilder::new()).construct()
+    }
+}
+
+/// Shorthand for [Item::build_with](struct.Item.html#method.build_with), handy in tests and
+/// examples: `item(|b| b.a("a".into()).b(vec![]))`.
+#[cfg(feature = "std")]
+pub fn item<F>(configure: F) -> Item
+where
+    F: FnOnce(ItemBuilder<Unset, Unset>) -> ItemBuilder<Set, Set>,
+{
+    Item::build_with(configure)
+}
+
+#[cfg(feature = "std")]
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Set a new value into the field and return the builder. That will also change the
+    /// corresponding type-parameter to `Set` to indicate a value has been set. A previous value
+    /// is dropped by the slot. Since the builder has no destructor of its own we can simply move
+    /// the other slots over into a builder of the new type.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a(self, a: String) -> ItemBuilder<Set, B> {
+        ItemBuilder {
+            a: self.a.write(a),
+            b: self.b,
+        }
+    }
+
+    /// Same as [a](#method.a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b(self, b: Vec<i32>) -> ItemBuilder<A, Set> {
+        ItemBuilder {
+            a: self.a,
+            b: self.b.write(b),
+        }
+    }
+
+    /// Set `a` from anything that converts into a `String`. The plain setter stays strict, this
+    /// one makes the conversion explicit at the call-site.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn coerce_a<V: Into<String>>(self, a: V) -> ItemBuilder<Set, B> {
+        self.a(a.into())
+    }
+
+    /// Same as [coerce_a](#method.coerce_a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn coerce_b<V: Into<Vec<i32>>>(self, b: V) -> ItemBuilder<A, Set> {
+        self.b(b.into())
+    }
+
+    /// Drop the value of `a` if there is one and go back to `Unset`, e.g. to clear a field of a
+    /// template before handing it on.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn unset_a(self) -> ItemBuilder<Unset, B> {
+        ItemBuilder {
+            a: self.a.clear(),
+            b: self.b,
+        }
+    }
+
+    /// Same as [unset_a](#method.unset_a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn unset_b(self) -> ItemBuilder<A, Unset> {
+        ItemBuilder {
+            a: self.a,
+            b: self.b.clear(),
+        }
+    }
+
+    /// Drop every value that has been set and start over, for reusing the builder's variable in
+    /// a loop. An unset slot holds nothing, so nothing is kept either, the buffers of the fields
+    /// are reused by [PooledBuilder::reset](pool/struct.PooledBuilder.html#method.reset).
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn reset(self) -> ItemBuilder<Unset, Unset> {
+        ItemBuilder {
+            a: self.a.clear(),
+            b: self.b.clear(),
+        }
+    }
+
+    /// The fields that have been set as a bitmask, bit 0 is `a` and bit 1 is `b`. This is
+    /// [STATE_BITS](#associatedconstant.STATE_BITS), a constant for every combination of
+    /// type-parameters. `ItemPartial::state_bits` uses the same bits.
+    pub fn state_bits(&self) -> u64 {
+        Self::STATE_BITS
+    }
+}
+
+/// `a` has been set on an `ItemBuilder`. `construct()` is bounded by one of these per field, so
+/// calling it too early is an error naming each field that is missing instead of a method that
+/// can't be found.
+#[cfg(feature = "std")]
+#[diagnostic::on_unimplemented(
+    message = "field `a` has not been set on `ItemBuilder`",
+    label = "`construct()` needs `a`",
+    note = "set it with `.a(...)` before constructing the item"
+)]
+pub trait ASet: IsSet {}
+
+#[cfg(feature = "std")]
+impl ASet for Set {}
+
+/// `b` has been set, see [ASet](trait.ASet.html).
+#[cfg(feature = "std")]
+#[diagnostic::on_unimplemented(
+    message = "field `b` has not been set on `ItemBuilder`",
+    label = "`construct()` needs `b`",
+    note = "set it with `.b(...)` before constructing the item"
+)]
+pub trait BSet: IsSet {}
+
+#[cfg(feature = "std")]
+impl BSet for Set {}
+
+/// Implementation for constructing an `Item`. This only can be done when both fields are `Set`,
+/// meaning both fields are initialized
+#[cfg(feature = "std")]
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Consume this builder and construct an item with the values set in the builder.
+    pub fn construct(self) -> Item
+    where
+        A: ASet,
+        B: BSet,
+    {
+        Item {
+            a: A::into_set(self.a).into_inner(),
+            b: B::into_set(self.b).into_inner(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ItemBuilder<Set, Set> {
+    /// Construct the item and hand out an empty builder for the next one.
+    pub fn construct_and_reset(self) -> (Item, ItemBuilder<Unset, Unset>) {
+        (self.construct(), ItemBuilder::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty match only compiles for types without values, so these two functions are the proof
+    // that nobody can ever construct a marker.
+    fn _no_set_values(state: Set) -> ! {
+        match state {}
+    }
+
+    fn _no_unset_values(state: Unset) -> ! {
+        match state {}
+    }
+
+    #[test]
+    fn markers() {
+        assert_eq!(core::mem::size_of::<Set>(), 0);
+        assert_eq!(core::mem::size_of::<Unset>(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn unset() {
+        let template = ItemBuilder::new().a("template".into()).b(vec![1]);
+        let builder = template.unset_a().unset_a();
+        assert_eq!(builder.state_bits(), 0b10);
+        assert_eq!(builder.a("a".into()).construct(), Item { a: "a".into(), b: vec![1] });
+        assert_eq!(ItemBuilder::new().b(vec![]).unset_b().state_bits(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reset() {
+        let mut builder = ItemBuilder::new();
+        for i in 0..3 {
+            let (item, next) = builder.a(i.to_string()).b(vec![i]).construct_and_reset();
+            assert_eq!(item, Item { a: i.to_string(), b: vec![i] });
+            builder = next;
+        }
+        assert_eq!(builder.a("a".into()).reset().state_bits(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_with() {
+        let expected = Item { a: "a".into(), b: vec![1] };
+        assert_eq!(Item::build_with(|b| b.b(vec![1]).a("a".into())), expected);
+        assert_eq!(item(|b| b.a("a".into()).b(vec![1])), expected);
+    }
+}
@@ -0,0 +1,232 @@
+//! `builder!`, declaring a struct together with its typestate builder without a proc-macro:
+//!
+//! ```ignore
+//! typestuff::builder!(pub struct Config { pub host: String, port: u16 } => ConfigBuilder);
+//!
+//! let config = Config::builder().port(80).host("localhost".into()).construct();
+//! ```
+//!
+//! The builder is the same as the one of `#[derive(TypesafeBuilder)]` without any of its
+//! attributes: one slot and one state per field, setters in any order, `construct()` once every
+//! field is set, `Clone` and `Debug` where the fields have them, and the traits of
+//! [nested](../nested/index.html). `macro_rules!` can't make up names, so the builder's name is
+//! given after the `=>`, and the state parameters are named after the fields they track,
+//! `ConfigBuilder<host, port>`.
+//!
+//! The macro works without `std` and doesn't need the `derive` feature.
+
+/// Declare a struct with named fields and a typestate builder for it, see
+/// [macros](macros/index.html).
+#[macro_export]
+macro_rules! builder {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        } => $builder:ident
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        #[doc = concat!("Builder for [`", stringify!($name), "`].")]
+        #[must_use = "builder must be used to construct a value"]
+        #[allow(non_camel_case_types)]
+        $vis struct $builder<$($field: $crate::FieldState),*> {
+            $($field: $crate::slot::Slot<$ty, $field>),*
+        }
+
+        #[allow(dead_code)]
+        impl $builder<$($crate::__builder_state!($field, Unset)),*> {
+            pub fn new() -> Self {
+                $builder { $($field: $crate::slot::Slot::empty()),* }
+            }
+        }
+
+        impl ::core::default::Default for $builder<$($crate::__builder_state!($field, Unset)),*> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// A builder with no field set yet.
+            $vis fn builder() -> $builder<$($crate::__builder_state!($field, Unset)),*> {
+                $builder::new()
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $builder<$($crate::__builder_state!($field, Set)),*> {
+            pub fn construct(self) -> $name {
+                $name { $($field: self.$field.into_inner()),* }
+            }
+        }
+
+        impl $crate::nested::HasBuilder for $name {
+            type Builder = $builder<$($crate::__builder_state!($field, Unset)),*>;
+
+            fn builder() -> Self::Builder {
+                $builder::new()
+            }
+        }
+
+        impl $crate::nested::Complete for $builder<$($crate::__builder_state!($field, Set)),*> {
+            type Output = $name;
+
+            fn construct(self) -> $name {
+                <$builder<$($crate::__builder_state!($field, Set)),*>>::construct(self)
+            }
+        }
+
+        #[allow(non_camel_case_types)]
+        impl<$($field: $crate::FieldState),*> ::core::clone::Clone for $builder<$($field),*>
+        where
+            $($crate::slot::Slot<$ty, $field>: ::core::clone::Clone),*
+        {
+            fn clone(&self) -> Self {
+                $builder { $($field: ::core::clone::Clone::clone(&self.$field)),* }
+            }
+        }
+
+        #[allow(non_camel_case_types)]
+        impl<$($field: $crate::FieldState),*> ::core::fmt::Debug for $builder<$($field),*>
+        where
+            $($crate::slot::Slot<$ty, $field>: ::core::fmt::Debug),*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct(stringify!($builder))
+                    $(.field(stringify!($field), &self.$field))*
+                    .finish()
+            }
+        }
+
+        $crate::__builder_setters!($builder [$($field),*] [] [$($field: $ty),*]);
+    };
+}
+
+/// `$crate::$state`, ignoring `$field`. Repeating it once per field spells out a builder with all
+/// fields in the same state.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builder_state {
+    ($field:ident, $state:ident) => {
+        $crate::$state
+    };
+}
+
+/// One setter per field. The fields before the current one are in `[$done]`, the ones after it
+/// in the last list, so the setter's builder can have `Set` in the current field's place.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builder_setters {
+    ($builder:ident [$($all:ident),*] [$($done:ident),*] []) => {};
+    (
+        $builder:ident [$($all:ident),*] [$($done:ident),*]
+        [$field:ident : $ty:ty $(, $rest:ident : $rest_ty:ty)*]
+    ) => {
+        #[allow(dead_code, non_camel_case_types)]
+        impl<$($all: $crate::FieldState),*> $builder<$($all),*> {
+            #[must_use = "setter returns a new builder; did you mean to chain?"]
+            pub fn $field(self, $field: $ty) -> $builder<$($done,)* $crate::Set $(, $rest)*> {
+                $builder {
+                    $($done: self.$done,)*
+                    $field: self.$field.write($field),
+                    $($rest: self.$rest),*
+                }
+            }
+        }
+
+        $crate::__builder_setters!(
+            $builder [$($all),*] [$($done,)* $field] [$($rest : $rest_ty),*]
+        );
+    };
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::rc::Rc;
+
+    builder!(
+        /// Declared by the macro.
+        #[derive(Debug, PartialEq)]
+        pub struct Declared {
+            pub name: String,
+            #[doc = "the values"]
+            values: Vec<u8>,
+            shared: Rc<()>,
+        } => DeclaredBuilder
+    );
+
+    builder!(struct Nothing {} => NothingBuilder);
+
+    #[test]
+    fn construct_in_any_order() {
+        let shared = Rc::new(());
+        let declared = Declared::builder()
+            .values(vec![1])
+            .shared(shared.clone())
+            .name("a".into())
+            .name("b".into())
+            .construct();
+        assert_eq!(declared, Declared { name: "b".into(), values: vec![1], shared });
+
+        NothingBuilder::new().construct();
+    }
+
+    #[test]
+    fn clone_and_debug() {
+        let shared = Rc::new(());
+        let builder = DeclaredBuilder::new().shared(shared.clone()).values(vec![]);
+        let fork = builder.clone();
+        assert_eq!(Rc::strong_count(&shared), 3);
+        assert_eq!(
+            format!("{:?}", fork),
+            "DeclaredBuilder { name: <unset>, values: [], shared: () }",
+        );
+        drop((builder, fork));
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+}
+
+/// The `BuilderDescriptor` static of a derived builder. The derive can't see the features of this
+/// crate, so it always asks for one and this expands to nothing without `registry`.
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builder_descriptor {
+    ($vis:vis $descriptor:ident, $builder:ty, $name:expr) => {
+        #[allow(dead_code)]
+        #[doc = "The registry descriptor of this builder."]
+        $vis static $descriptor: $crate::registry::BuilderDescriptor =
+            $crate::registry::BuilderDescriptor { name: $name, fields: <$builder>::FIELDS };
+    };
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builder_descriptor {
+    ($vis:vis $descriptor:ident, $builder:ty, $name:expr) => {};
+}
+
+/// The items of a derived builder that may panic, like a `construct()` parsing the environment
+/// defaults of `#[builder(from_str_default)]`. The derive can't see the features of this crate
+/// either, so this drops them with `no-panic` and only the fallible `try_construct()` is left.
+#[cfg(not(feature = "no-panic"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __unless_no_panic {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(feature = "no-panic")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __unless_no_panic {
+    ($($item:item)*) => {};
+}
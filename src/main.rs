@@ -1,143 +1,299 @@
-use std::any::TypeId;
-use std::marker::PhantomData;
-use std::mem::{ManuallyDrop, transmute};
-
-/// An unconstructible-type, use it as type-information for the builder indicating a value has not
-/// been set.
-#[allow(dead_code)]
-pub enum Unset {}
-/// Another unconstructible type, indicating a value has been set.
-pub enum Set {}
-
-/// A helper-function to check if the type is `Set`
-fn is_set<A: 'static>() -> bool {
-    TypeId::of::<A>() == TypeId::of::<Set>()
-}
+//! A tour of the builders, every feature of the library in a few lines.
 
-/// The item we construct in the end. We use types that free memory on drop to show the builder
-/// does handle memory correctly.
-#[derive(Debug)]
-pub struct Item {
-    pub a: String,
-    pub b: Vec<i32>,
-}
+use typestuff::*;
 
-/// The builder, containing the fields that will be passed to the item and the types that are used
-/// at compile-time to check if the fields are set. A generic type either is `Unset` or `Set`,
-/// indicating whether the corresponding field has been set or not. If the field is not set it will
-/// contain uninitialized memory. The fields are stored as `ManuallyDrop` to bypass rusts destructor
-/// because they might be uninitialized.
-pub struct ItemBuilder<A: 'static, B: 'static> {
-    a: ManuallyDrop<String>,
-    b: ManuallyDrop<Vec<i32>>,
-    _a: PhantomData<A>,
-    _b: PhantomData<B>,
-}
+fn main() {
+    let builder = ItemBuilder::new();
 
-impl ItemBuilder<Unset, Unset> {
-    /// Construct a new builder, set fields to uninitialized and set types to `Unset`
-    pub fn new() -> Self {
-        unsafe {
-            Self {
-                a: std::mem::uninitialized(),
-                b: std::mem::uninitialized(),
-                _a: PhantomData,
-                _b: PhantomData,
-            }
-        }
-    }
-}
+    let with_field = builder.a("incomplete".into());
+    let complete = with_field.b(vec![]);
 
+    println!("{:?}", complete.construct());
 
-impl<A, B> ItemBuilder<A, B> {
-    /// Set a new value into the field and return the builder. That will also change the
-    /// corresponding type-parameter to the fields type to indicate a value has been set. Since we
-    /// can't construct a new object with a new type because of our custom destructor we simply
-    /// cast it. The builder always has the same size and memory-layout regardless of
-    /// type-parameters, so this will never be an issue (i guess).
-    pub fn a(mut self, a: String) -> ItemBuilder<Set, B> {
-        // if we already set a value before, drop it
-        if is_set::<A>() {
-            unsafe { ManuallyDrop::drop(&mut self.a); }
-        }
+    // Try uncommenting this code and see it won't work. The builder will have the type
+    // `ItemBuilder<Unset, Unset>` indicating both fields have not been set yet. This and other
+    // misuse is checked by the programs in `tests/compile_fail`.
+    // println!("{:?}", ItemBuilder::new().construct());
+
+    // behold, no memory-errors. memory-leaks and double drops are counted in `tests/leaks.rs`,
+    // so you don't have to believe me on this one.
+    drop(ItemBuilder::new());
+    drop(ItemBuilder::new().a("str".into()));
+    drop(ItemBuilder::new().b(vec![1, 2, 3, 4]));
 
-        self.a = ManuallyDrop::new(a);
-        unsafe { transmute(self) }
+    drop(ItemBuilder::new().a("str".into()).a("str2".into()));
+    drop(ItemBuilder::new().b(vec![1, 2, 3, 4]).b(vec![5, 6, 7, 8, 9, 10]));
+    drop(ItemBuilder::new().a("str".into()).b(vec![5, 6, 7, 8, 9, 10]).construct());
+
+    // the fields that are set can be read back without giving up the builder
+    let peek = ItemBuilder::new().a("peeked".into());
+    println!("{}", peek.get_a());
+    drop(peek);
+
+    // conversions are opt-in and searchable through the `coerce_` setters
+    println!("{:?}", ItemBuilder::new().coerce_a("coerced").coerce_b([1, 2]).construct());
+
+    // complete builders and items can be compared field by field
+    let old = ItemBuilder::new().a("config".into()).b(vec![1]);
+    print!("{}", old.diff(&ItemBuilder::new().a("config".into()).b(vec![2])));
+    print!("{}", old.construct().diff(&Item { a: "changed".into(), b: vec![1] }));
+
+    // quick one-shot construction through a closure, which has to set every field as well.
+    // uncommenting the second line fails, the closure returns an `ItemBuilder<Set, Unset>`. the
+    // same mistake is kept in `tests/compile_fail/build_with_incomplete.rs`
+    println!("{:?}", item(|b| b.a("one-shot".into()).b(vec![1])));
+    // println!("{:?}", item(|b| b.a("forgot b".into())));
+
+    // the same works for any type with a builder, which is how nested fields are configured
+    let nested = nested::build::<Item, _, _>(|b| b.b(vec![2]).a("nested".into()));
+    println!("{:?}", nested);
+
+    // builders can be passed around, the state traits narrow down what a function accepts
+    use states::{CompleteItemBuilder, EmptyItemBuilder, IsUnset};
+    fn with_default_a(builder: ItemBuilder<impl IsUnset, Set>) -> CompleteItemBuilder {
+        builder.a("default".into())
     }
+    let empty: EmptyItemBuilder = ItemBuilder::new();
+    println!("{:?}", with_default_a(empty.b(vec![])).construct());
 
-    /// Same as [a](#method.a)
-    pub fn b(mut self, b: Vec<i32>) -> ItemBuilder<A, Set> {
-        if is_set::<B>() {
-            unsafe { ManuallyDrop::drop(&mut self.b); }
-        }
+    // the states can be tracked as a single bitmask too, keeping the type name short
+    println!("{:?}", mask::MaskedItemBuilder::new().b(vec![]).a("masked".into()).construct());
+
+    // fields can depend on each other: exactly one way to authenticate, the last one picked wins
+    let conn = groups::ConnectionBuilder::new().host("db".into()).username("u".into());
+    println!("{:?}", conn.password("p".into()).token("t".into()).construct());
+
+    // or have to be set in stages, TLS can't be configured before the connection is complete
+    let session = staged::SessionBuilder::new().host("example.com".into()).port(443);
+    let session = session.server_name("example.com".into()).verify(true);
+    println!("{:?}", session.protocols(vec!["h2".into()]).construct());
+
+    // partial builders can be merged, the right side wins for fields set on both sides
+    let base = ItemBuilder::new().a("base".into()).b(vec![1]);
+    let overrides = ItemBuilder::new().b(vec![2, 3]);
+    println!("{:?}", (base | overrides).construct());
+
+    // collection fields can be preallocated and filled element by element
+    let builder = ItemBuilder::new().a_with_capacity(8).b_with_capacity(3);
+    let builder = builder.push_a("filled").push_b(1).push_b(2).push_b(3);
+    println!("{:?}", builder.construct());
+
+    // fields can be produced on different threads through one token per field
+    let (a, b) = ItemBuilder::new().split();
+    let a = std::thread::spawn(move || a.set("from a thread".into())).join().unwrap();
+    println!("{:?}", ItemBuilder::merge(a, b.set(vec![])).construct());
+
+    // string fields take `&str`, `String` or `Cow<str>`, copying only what is borrowed
+    println!("{:?}", ItemBuilder::new().a_str("from a literal").b(vec![]).construct());
+
+    // abandoned builders drop their fields in declaration order, or in reverse where the target
+    // needs it
+    drop(drop_order::PairBuilder::new().left(String::from("dropped first")).right(vec![1]));
+    let scoped = drop_order::ScopedBuilder::default().resource(vec![1]).guard("guard");
+    let scoped = scoped.construct();
+    println!("{} {:?}", scoped.guard, scoped.resource);
+    let pair = drop_order::PairBuilder::default().right(2).left("left").construct();
+    println!("{} {}", pair.left, pair.right);
+
+    // complete builders can construct right into a vector
+    let mut items = Vec::with_capacity(1);
+    ItemBuilder::new().a("pushed".into()).b(vec![]).construct_push(&mut items);
+    println!("{:?}", items);
+
+    // or into another representation of the same fields
+    let builder = ItemBuilder::new().a("compact".into()).b(vec![1]);
+    let compact: targets::CompactItem = builder.construct_into();
+    println!("{:?}", compact);
+
+    // big targets can keep their fields on the heap, the setters only move a pointer
+    println!("{:?}", boxed::BoxedItemBuilder::new().a("boxed".into()).b(vec![]).construct());
+
+    // fields can be computed only once the item is constructed
+    println!("{:?}", lazy::LazyItemBuilder::new().a_with(|| "lazy".into()).b(vec![]).construct());
+
+    // unset fields can be filled with their defaults instead
+    println!("{:?}", ItemBuilder::new().a("defaults".into()).finish_with_defaults());
 
-        self.b = ManuallyDrop::new(b);
-        unsafe { transmute(self) }
+    // builders can be migrated to the next version of the item, keeping what has been set
+    let v1 = ItemBuilder::new().a("old".into()).b(vec![1]);
+    println!("{:?}", migrate::ItemV2Builder::from_v1(v1).label("migrated".into()).construct());
+    drop(migrate::ItemV2Builder::default().a("a".into()).values(vec![]));
+
+    // batches of items can be built from a closure configuring one builder per index
+    let batch = ItemBuilder::build_many(3, |i, builder| {
+        builder.a(format!("item {}", i)).b(vec![i as i32])
+    });
+    println!("{:?}", batch);
+
+    // builders for `Copy`-types work in const contexts
+    println!("{:?}", config::DEFAULT_CONFIG);
+    println!("{:?}", config::ConfigBuilder::default().retries(1).timeout(5).construct());
+
+    // `a` falls back to the environment variable `BUILDER_A` if it isn't set
+    #[cfg(not(feature = "no-panic"))]
+    println!("{:?}", ItemBuilder::new().b(vec![1]).construct_with_env_defaults());
+    // or reports an error instead of panicking when the variable can't be parsed
+    println!("{:?}", ItemBuilder::new().b(vec![1]).try_construct_with_env_defaults());
+
+    // map fields hand out entries borrowed from the builder once they are set
+    let mut request = request::RequestBuilder::default()
+        .with_header_entry("accept", |entry| {
+            entry.or_insert_with(|| "*/*".into());
+        });
+    request.header_entry("host").or_insert_with(|| "example.com".into());
+    let request = request.url("/".into()).construct();
+    println!("{} {:?} {:?}", request.url, request.headers, request.sent_at);
+
+    // time-dependent defaults can be made deterministic by injecting a clock
+    let epoch = std::time::SystemTime::UNIX_EPOCH;
+    let request = request::RequestBuilder::new().with_clock(move || epoch);
+    let request = request.url("/".into()).headers(Default::default()).construct();
+    assert_eq!(request.sent_at, epoch);
+
+    // the setters are available as traits too, for code that forwards values generically
+    use setters::{SetA, SetB};
+    println!("{:?}", ItemBuilder::new().set_a("generic").set_b([1, 2]).construct());
+
+    // every builder type and its fields can be listed for diagnostics
+    #[cfg(feature = "registry")]
+    {
+        registry::init();
+        for descriptor in registry::iter() {
+            let defaults = if descriptor.has_defaults() { " with defaults" } else { "" };
+            println!("{} {:?}{}", descriptor.name, descriptor.fields, defaults);
+        }
     }
-}
 
-/// Implementation for constructing an `Item`. This only can be done when both fields are `Set`,
-/// meaning both fields are initialized
-impl ItemBuilder<Set, Set> {
-    /// Consume this builder and construct an item with the values set in the builder. Do some
-    /// memory-magic to avoid problems.
-    pub fn construct(self) -> Item {
-        let (a, b) = unsafe {
-            // get pointers to fields
-            let s = &self.a as *const ManuallyDrop<String>;
-            let v = &self.b as *const ManuallyDrop<Vec<i32>>;
-
-            // forget the builder, otherwise this would destroy the fields as soon as the builder
-            // gets dropped
-            std::mem::forget(self);
-            // read the pointers to reclaim ownership of values we "forgot"
-            (std::ptr::read(s), std::ptr::read(v))
-        };
-
-        Item {
-            // remove the `ManuallyDrop` as we can be sure that the memory-locations are
-            // initialized thanks to the type-information
-            a: ManuallyDrop::into_inner(a),
-            b: ManuallyDrop::into_inner(b),
+    // large fields can be shared between builders instead of being cloned into each of them
+    let table = std::sync::Arc::new(vec![1, 2, 3]);
+    let shared = sharing::LookupBuilder::new().name("shared".into()).table(table.clone());
+    println!("{:?}", shared.construct_shared());
+    let owned = sharing::LookupBuilder::default().name("owned".into()).table(table);
+    println!("{:?}", owned.construct());
+
+    // builders holding credentials can be limited to a time frame, after which they refuse to
+    // construct the item
+    let ttl = std::time::Duration::from_secs(60);
+    let expiring = ItemBuilder::new().a("token".into()).expires_in(ttl);
+    println!("{:?}", expiring.b(vec![]).construct());
+
+    // the state of a builder can be logged as a compact bitmask
+    println!("{:#04x}", ItemBuilder::new().b(vec![]));
+
+    // or as a sorted map of the fields that are set
+    println!("{:?}", ItemBuilder::new().a("shown".into()).into_display_map());
+
+    // some builders come with a ticket that only constructing the item accepts
+    let (builder, ticket) = ItemBuilder::new_with_ticket();
+    println!("{:?}", builder.a("ticket".into()).b(vec![]).construct_with_ticket(ticket));
+
+    // list fields can be appended to like the arguments of `std::process::Command`
+    let command = command::CommandBuilder::new().program("echo").arg("hello").args(["a", "b"]);
+    let command = command.construct();
+    println!("{:?} {:?}", command.program, command.args);
+
+    // two-phase builders: the spec can be prepared on another thread, the local resources are
+    // only added in the second phase
+    let spec = std::thread::spawn(|| job::JobSpecBuilder::new().name("job".into()).retries(2))
+        .join()
+        .unwrap()
+        .construct();
+    let job = job::JobBuilder::from_spec(spec).connection(std::rc::Rc::new("local".into()));
+    let job = job.construct();
+    println!("{} {} {}", job.name, job.retries, job.connection);
+
+    // setters of fields behind runtime flags fail right away while the flag is off, the error
+    // hands the builder back for another try
+    use std::sync::atomic::Ordering;
+    let builder = match guard::ExportBuilder::new().path("export".into()).compression(6) {
+        Ok(builder) => builder,
+        Err(err) => {
+            println!("{}, turning it on", err);
+            let builder = err.into_builder();
+            println!("fields set so far: {:#05b}", builder.state_bits());
+            guard::flags::COMPRESSION.store(true, Ordering::Relaxed);
+            builder.compression(6).expect("compression is enabled now")
         }
+    };
+    guard::flags::ENCRYPTION.store(true, Ordering::Relaxed);
+    if let Ok(builder) = builder.key("key".into()) {
+        let export = builder.construct();
+        println!("{} {} {}", export.path, export.compression, export.key);
+        let rotated = guard::Export { key: "rotated".into(), ..export };
+        print!("{}", rotated.diff(&guard::Export { compression: 9, ..rotated.clone() }));
     }
-}
 
-/// Since we can't let rust handle destruction because fields might not be initialized yet we have
-/// to provide our own destructor. We simply use the type-information of the generics to check
-/// which field is initialized. Again, this is generated at compile-time and will result in an
-/// destructor rust couldn't do better.
-impl<A, B> Drop for ItemBuilder<A, B> {
-    fn drop(&mut self) {
-        if is_set::<A>() {
-            unsafe { ManuallyDrop::drop(&mut self.a); }
+    // setters can validate the value first, a rejected one leaves the builder as it was
+    use validate::rules;
+    match ItemBuilder::new().validated_a(String::new(), rules::non_empty) {
+        Ok(builder) => drop(builder),
+        Err(err) => {
+            println!("{}", err);
+            let builder = err.into_builder().a("valid".into());
+            let item = builder.validated_b(vec![1], rules::all_in(0..=9)).map(|b| b.construct());
+            println!("{:?}", item);
         }
-        if is_set::<B>() {
-            unsafe { ManuallyDrop::drop(&mut self.b); }
+    }
+
+    // partials are checked at runtime, batches of them report every failure with its index
+    let partials = vec![
+        partial::ItemPartial { a: Some("ok".into()), b: Some(vec![]) },
+        partial::ItemPartial { a: None, b: Some(vec![]) },
+    ];
+    match Item::build_batch(partials, batch::BatchPolicy::CollectAll) {
+        Ok(items) => println!("{:?}", items),
+        Err(errors) => {
+            for (index, err) in errors {
+                println!("partial {}: {}", index, err);
+            }
         }
     }
-}
 
-fn main() {
-    let builder = ItemBuilder::new();
+    // builders in any state can be checked at runtime as well, a failure hands the builder back
+    match ItemBuilder::new().a("only a".into()).try_construct() {
+        Ok(item) => println!("{:?}", item),
+        Err((builder, err)) => {
+            println!("{}", err);
+            println!("{:?}", builder.b(vec![]).try_construct().map_err(|(_, err)| err));
+        }
+    }
 
-    let with_field = builder.a("incomplete".into());
-    let complete = with_field.b(vec![]);
+    // builders from a pool reuse the buffers of items that have been dropped before
+    #[cfg(feature = "std")]
+    {
+        let pool = pool::BuilderPool::new(8);
+        drop(pool.get().a("pooled").b(&[1, 2, 3]).construct_pooled());
+        assert!(!pool.is_empty());
 
-    println!("{:?}", complete.construct());
+        let item = pool.get().a("reused").b(&[4]).construct_pooled().into_inner();
+        println!("{:?}, {} shells left in the pool", item, pool.len());
+    }
 
-    // Try uncommenting this code and see it won't work. The builder will have the type
-    // `ItemBuilder<Unset, Unset>` indicating both fields have not been set yet.
-    // println!("{:?}", ItemBuilder::new().construct());
+    // fields decided at runtime go through the dynamic builder, which converts back into the
+    // typestate builder once the states are known
+    #[cfg(feature = "std")]
+    {
+        let mut dynamic = dynamic::DynItemBuilder::from(ItemBuilder::new().a("dynamic".into()));
+        dynamic.set("b", dynamic::Value::List(vec![1])).unwrap();
+        println!("{:?}", dynamic.into_typed::<Set, Set>().map(ItemBuilder::construct));
 
-    // behold, no memory-errors. although memory-leaks are not checked for, you have to believe me
-    // on this one or test for yourself.
-    drop(ItemBuilder::new());
-    drop(ItemBuilder::new().a("str".into()));
-    drop(ItemBuilder::new().b(vec![1, 2, 3, 4]));
+        // or all at once from a map, e.g. a form submission
+        use std::convert::TryFrom;
+        let text = dynamic::Value::Text("from a map".into());
+        let fields = [("a".to_string(), text), ("b".into(), dynamic::Value::List(vec![]))];
+        println!("{:?}", Item::try_from(std::collections::HashMap::from(fields)));
+    }
 
-    drop(ItemBuilder::new().a("str".into()).a("str2".into()));
-    drop(ItemBuilder::new().b(vec![1, 2, 3, 4]).b(vec![5, 6, 7, 8, 9, 10]));
-    drop(ItemBuilder::new().a("str".into()).b(vec![5, 6, 7, 8, 9, 10]).construct());
+    // a shared builder can be filled in by several owners, checked when it is constructed
+    #[cfg(feature = "std")]
+    {
+        let shared = shared::SharedBuilder::new();
+        let other = shared.clone();
+        std::thread::spawn(move || {
+            other.b(vec![1]);
+        })
+        .join()
+        .unwrap();
+        println!("{:?}", shared.a("shared".into()).construct());
+    }
 }
-
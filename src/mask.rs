@@ -0,0 +1,131 @@
+//! The states of all fields as one const-generic bitmask instead of one type-parameter per field.
+//! `MaskedItemBuilder<0b01>` is an `ItemBuilder<Set, Unset>`, with the bits laid out like
+//! [STATE_BITS](../struct.ItemBuilder.html#associatedconstant.STATE_BITS): bit 0 is `a`, bit 1 is
+//! `b`. The name of the type stays the same length however many fields there are, which keeps
+//! signatures and error messages of wide builders readable.
+//!
+//! ```ignore
+//! let item = MaskedItemBuilder::new().b(vec![1]).a("a".into()).construct();
+//! // MaskedItemBuilder::new().a("a".into()).construct(); // error: no `construct` on `<1>`
+//! ```
+//!
+//! Stable Rust can't compute `{ SET | 1 }` from a generic `SET`, so the setters can't be written
+//! once for every mask. `item_masks!` spells out every mask instead, mapping it to the states of
+//! the fields through [ItemMask](trait.ItemMask.html) and generating the setters for it. The
+//! values are kept in an `ItemBuilder`, so this is only a different name for the same storage.
+
+use crate::{FieldState, Item, ItemBuilder, Set, Unset};
+
+pub const A: u64 = 0b01;
+pub const B: u64 = 0b10;
+/// The mask with every field set.
+pub const COMPLETE: u64 = A | B;
+
+/// A bitmask as a type, to hang the states of the fields off.
+pub struct Bits<const SET: u64>;
+
+/// The states of the fields of `Item` for a mask.
+pub trait ItemMask {
+    type A: FieldState;
+    type B: FieldState;
+}
+
+/// The builder for the mask `SET`.
+pub type MaskedState<const SET: u64> =
+    ItemBuilder<<Bits<SET> as ItemMask>::A, <Bits<SET> as ItemMask>::B>;
+
+#[must_use = "builder must be used to construct a value"]
+pub struct MaskedItemBuilder<const SET: u64>
+where
+    Bits<SET>: ItemMask,
+{
+    inner: MaskedState<SET>,
+}
+
+impl MaskedItemBuilder<0> {
+    pub fn new() -> Self {
+        Self { inner: ItemBuilder::new() }
+    }
+}
+
+impl Default for MaskedItemBuilder<0> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SET: u64> MaskedItemBuilder<SET>
+where
+    Bits<SET>: ItemMask,
+{
+    /// The mask, same as `state_bits` of the builder underneath.
+    pub const SET: u64 = SET;
+
+    /// The builder with one type-parameter per field.
+    pub fn into_builder(self) -> MaskedState<SET> {
+        self.inner
+    }
+}
+
+impl MaskedItemBuilder<COMPLETE> {
+    pub fn construct(self) -> Item {
+        self.inner.construct()
+    }
+}
+
+macro_rules! item_masks {
+    ($($mask:literal => ($a:ty, $b:ty),)*) => {$(
+        impl ItemMask for Bits<$mask> {
+            type A = $a;
+            type B = $b;
+        }
+
+        impl From<ItemBuilder<$a, $b>> for MaskedItemBuilder<$mask> {
+            fn from(inner: ItemBuilder<$a, $b>) -> Self {
+                Self { inner }
+            }
+        }
+
+        impl MaskedItemBuilder<$mask> {
+            #[must_use = "setter returns a new builder; did you mean to chain?"]
+            pub fn a(self, a: String) -> MaskedItemBuilder<{ $mask | A }> {
+                MaskedItemBuilder { inner: self.inner.a(a) }
+            }
+
+            #[must_use = "setter returns a new builder; did you mean to chain?"]
+            pub fn b(self, b: Vec<i32>) -> MaskedItemBuilder<{ $mask | B }> {
+                MaskedItemBuilder { inner: self.inner.b(b) }
+            }
+        }
+    )*};
+}
+
+item_masks! {
+    0b00 => (Unset, Unset),
+    0b01 => (Set, Unset),
+    0b10 => (Unset, Set),
+    0b11 => (Set, Set),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_order() {
+        let item = MaskedItemBuilder::new().b(vec![1]).a("a".into()).construct();
+        assert_eq!(item, Item { a: "a".into(), b: vec![1] });
+
+        let builder = MaskedItemBuilder::new().a("first".into()).a("second".into());
+        assert_eq!(builder.b(vec![]).construct().a, "second");
+    }
+
+    #[test]
+    fn same_bits_as_the_builder() {
+        let builder = MaskedItemBuilder::new().b(vec![]);
+        assert_eq!(MaskedItemBuilder::<0b10>::SET, builder.into_builder().state_bits());
+
+        let builder: MaskedItemBuilder<0b01> = ItemBuilder::new().a("a".into()).into();
+        assert_eq!(builder.into_builder().state_bits(), 0b01);
+    }
+}
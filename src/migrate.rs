@@ -0,0 +1,127 @@
+//! Moving a builder over to the next version of its item. `ItemV2` keeps `a` but stores it in a
+//! new format, renames `b` to `values` and adds `label`. A half-finished `ItemBuilder` migrates
+//! into an `ItemV2Builder` that has exactly the same fields set, the new `label` always starts out
+//! unset.
+//!
+//! Derived builders generate the same migration from `#[builder(migrate_from(...))]`, see the
+//! `typestuff-derive` crate.
+
+use crate::slot::Slot;
+use crate::{FieldState, ItemBuilder, Set, Unset};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ItemV2 {
+    pub a: String,
+    pub values: Vec<i32>,
+    pub label: String,
+}
+
+/// The transform applied to `a` on the way from version 1 to version 2.
+pub fn upgrade_a(a: String) -> String {
+    format!("v2:{}", a)
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct ItemV2Builder<A: FieldState, V: FieldState, L: FieldState> {
+    a: Slot<String, A>,
+    values: Slot<Vec<i32>, V>,
+    label: Slot<String, L>,
+}
+
+impl ItemV2Builder<Unset, Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            a: Slot::empty(),
+            values: Slot::empty(),
+            label: Slot::empty(),
+        }
+    }
+}
+
+impl Default for ItemV2Builder<Unset, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemV2Builder<A, B, Unset> {
+    /// Move the fields over from a version 1 builder: `a` is upgraded, `b` becomes `values`.
+    pub fn from_v1(builder: ItemBuilder<A, B>) -> Self {
+        ItemV2Builder {
+            a: Slot::from_option(builder.a.into_option().map(upgrade_a)),
+            values: builder.b,
+            label: Slot::empty(),
+        }
+    }
+}
+
+impl<A: FieldState, V: FieldState, L: FieldState> ItemV2Builder<A, V, L> {
+    pub fn a(self, a: String) -> ItemV2Builder<Set, V, L> {
+        ItemV2Builder {
+            a: self.a.write(a),
+            values: self.values,
+            label: self.label,
+        }
+    }
+
+    pub fn values(self, values: Vec<i32>) -> ItemV2Builder<A, Set, L> {
+        ItemV2Builder {
+            a: self.a,
+            values: self.values.write(values),
+            label: self.label,
+        }
+    }
+
+    pub fn label(self, label: String) -> ItemV2Builder<A, V, Set> {
+        ItemV2Builder {
+            a: self.a,
+            values: self.values,
+            label: self.label.write(label),
+        }
+    }
+}
+
+impl ItemV2Builder<Set, Set, Set> {
+    pub fn construct(self) -> ItemV2 {
+        ItemV2 {
+            a: self.a.into_inner(),
+            values: self.values.into_inner(),
+            label: self.label.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_v1() {
+        let v1 = ItemBuilder::new().a("a".into()).b(vec![1, 2]);
+        let item = ItemV2Builder::from_v1(v1).label("new".into()).construct();
+        assert_eq!(item, ItemV2 { a: "v2:a".into(), values: vec![1, 2], label: "new".into() });
+    }
+
+    #[test]
+    fn partial_v1() {
+        let v1 = ItemBuilder::new().b(vec![3]);
+        // `a` is still unset after the migration, otherwise this wouldn't need the setter
+        let v2: ItemV2Builder<Unset, Set, Unset> = ItemV2Builder::from_v1(v1);
+        let item = v2.label("new".into()).a("set in v2".into()).construct();
+        assert_eq!(item.a, "set in v2");
+        assert_eq!(item.values, vec![3]);
+    }
+
+    // every setter once with a distinct value, `a` and `label` are both strings and a swap of
+    // their slots would still type-check. derived builders generate this test with
+    // `#[builder(generate_usage_test)]`
+    #[test]
+    fn every_setter() {
+        let item = ItemV2Builder::new()
+            .a("a".into())
+            .values(vec![1])
+            .label("label".into())
+            .construct();
+        assert_eq!(item, ItemV2 { a: "a".into(), values: vec![1], label: "label".into() });
+    }
+}
@@ -0,0 +1,548 @@
+//! One test per unsafe code path in the crate. Running this test binary under Miri
+//! (`MIRIFLAGS=-Zmiri-disable-isolation cargo +nightly miri test`, some tests read the clock and
+//! the environment) walks every `unsafe` block at least once, so Miri gets to check all of the
+//! memory-magic instead of just the parts `main` happens to touch.
+//!
+//! New unsafe blocks have to be registered here: the number of `unsafe` occurrences in the crate
+//! is compared against the `sites` count given to `unsafe_surface!`, so adding one without
+//! updating this module fails the build. The builders are made of `Slot`s, so the builder tests
+//! below are what walks the slots' destructors and setters in all the states they can be in.
+
+use std::collections::HashMap;
+#[cfg(feature = "capi")]
+use std::ptr;
+
+use super::*;
+use crate::slot::Slot;
+
+/// Register the tests for the unsafe paths. `sites` is the number of `unsafe` occurrences the
+/// tests below are meant to cover, it is checked against the sources in `every_unsafe_is_covered`.
+macro_rules! unsafe_surface {
+    (sites: $sites:expr; $($(#[$meta:meta])* $name:ident => $body:block)*) => {
+        const UNSAFE_SITES: usize = $sites;
+        const REGISTERED: &[&str] = &[$(stringify!($name)),*];
+
+        $(
+            $(#[$meta])*
+            #[test]
+            fn $name() $body
+        )*
+    };
+}
+
+/// The sources of every module. Unsafe code shows up in `slot.rs`, in the FFI modules `abi.rs` and
+/// `capi.rs`, in `config.rs`'s const builder and in `push.rs` writing into spare capacity.
+const SOURCES: &[(&str, &str)] = &[
+    ("abi.rs", include_str!("abi.rs")),
+    ("batch.rs", include_str!("batch.rs")),
+    ("boxed.rs", include_str!("boxed.rs")),
+    ("capacity.rs", include_str!("capacity.rs")),
+    ("capi.rs", include_str!("capi.rs")),
+    ("cli.rs", include_str!("cli.rs")),
+    ("clock.rs", include_str!("clock.rs")),
+    ("cmp.rs", include_str!("cmp.rs")),
+    ("command.rs", include_str!("command.rs")),
+    ("config.rs", include_str!("config.rs")),
+    ("defaults.rs", include_str!("defaults.rs")),
+    ("diff.rs", include_str!("diff.rs")),
+    ("display.rs", include_str!("display.rs")),
+    ("drop_order.rs", include_str!("drop_order.rs")),
+    ("dynamic.rs", include_str!("dynamic.rs")),
+    ("env.rs", include_str!("env.rs")),
+    ("error.rs", include_str!("error.rs")),
+    ("expiry.rs", include_str!("expiry.rs")),
+    ("feature_audit.rs", include_str!("feature_audit.rs")),
+    ("getters.rs", include_str!("getters.rs")),
+    ("groups.rs", include_str!("groups.rs")),
+    ("guard.rs", include_str!("guard.rs")),
+    ("job.rs", include_str!("job.rs")),
+    ("laws.rs", include_str!("laws.rs")),
+    ("lazy.rs", include_str!("lazy.rs")),
+    ("layered.rs", include_str!("layered.rs")),
+    ("lib.rs", include_str!("lib.rs")),
+    ("macros.rs", include_str!("macros.rs")),
+    ("mask.rs", include_str!("mask.rs")),
+    ("migrate.rs", include_str!("migrate.rs")),
+    ("nested.rs", include_str!("nested.rs")),
+    ("ops.rs", include_str!("ops.rs")),
+    ("panic_audit.rs", include_str!("panic_audit.rs")),
+    ("partial.rs", include_str!("partial.rs")),
+    ("pending.rs", include_str!("pending.rs")),
+    ("policy.rs", include_str!("policy.rs")),
+    ("pool.rs", include_str!("pool.rs")),
+    ("presets.rs", include_str!("presets.rs")),
+    ("push.rs", include_str!("push.rs")),
+    ("reflection.rs", include_str!("reflection.rs")),
+    ("registry.rs", include_str!("registry.rs")),
+    ("request.rs", include_str!("request.rs")),
+    ("safe_slot.rs", include_str!("safe_slot.rs")),
+    ("setters.rs", include_str!("setters.rs")),
+    ("shared.rs", include_str!("shared.rs")),
+    ("sharing.rs", include_str!("sharing.rs")),
+    ("slot.rs", include_str!("slot.rs")),
+    ("split.rs", include_str!("split.rs")),
+    ("staged.rs", include_str!("staged.rs")),
+    ("states.rs", include_str!("states.rs")),
+    ("strings.rs", include_str!("strings.rs")),
+    ("targets.rs", include_str!("targets.rs")),
+    ("ticket.rs", include_str!("ticket.rs")),
+    ("validate.rs", include_str!("validate.rs")),
+];
+
+/// Count the `unsafe` keywords in some source, ignoring comments and longer words like
+/// `unsafe_code` or `forbid-unsafe`.
+fn count_unsafe(source: &str) -> usize {
+    let word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    source
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .map(|code| code.split(|c| !word(c)).filter(|&token| token == "unsafe").count())
+        .sum()
+}
+
+unsafe_surface! {
+    sites: 50;
+
+    /// `get` and `get_mut` on a set slot
+    slot_get => {
+        let mut slot = Slot::new(String::from("a"));
+        slot.get_mut().push('b');
+        assert_eq!(slot.get(), "ab");
+    }
+
+    /// `take` moves the value out and leaves an empty slot
+    slot_take => {
+        let (value, empty) = Slot::new(vec![1]).take();
+        assert_eq!(value, vec![1]);
+        assert!(empty.as_option().is_none());
+    }
+
+    /// `as_option` and `into_option` for both states
+    slot_options => {
+        let set = Slot::new(String::from("a"));
+        assert_eq!(set.as_option().map(String::as_str), Some("a"));
+        assert_eq!(set.into_option().as_deref(), Some("a"));
+        assert_eq!(Slot::<String, Unset>::empty().into_option(), None);
+    }
+
+    /// `from_option` drops values handed to an unset slot
+    slot_from_option => {
+        let set: Slot<String, Set> = Slot::from_option(Some("a".into()));
+        assert_eq!(set.get(), "a");
+        drop(Slot::<String, Unset>::from_option(Some("dropped".into())));
+    }
+
+    /// the raw escape hatch, initializing in place and then flipping the state
+    slot_raw => {
+        let mut slot = Slot::<String, Unset>::empty();
+        let slot = unsafe {
+            slot.raw_mut().write("in place".into());
+            slot.assume_set()
+        };
+        assert_eq!(slot.into_inner(), "in place");
+    }
+
+    /// unset slots are poisoned when created and checked when dropped or read
+    slot_poison => {
+        let (_, empty) = Slot::new(String::from("a")).take();
+        assert_eq!(empty.into_option(), None);
+        drop(Slot::<Vec<u32>, Unset>::from_option(None));
+        // padding isn't kept by moves, the pattern isn't read back for this one
+        drop(Slot::<(u8, u16, u32), Unset>::from_option(None));
+    }
+
+    /// writing through the escape hatch without flipping the state trips the poison check
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "written to without changing its state")]
+    slot_poison_misuse => {
+        let mut slot = Slot::<u32, Unset>::empty();
+        unsafe { slot.raw_mut().write(7); }
+        drop(slot);
+    }
+
+    /// `clear` drops the value
+    slot_clear => {
+        drop(Slot::new(String::from("a")).clear());
+    }
+
+    /// setter `a` on an unset field, writing into an empty slot
+    a_first_set => {
+        let builder = ItemBuilder::new().a("first".into());
+        drop(builder);
+    }
+
+    /// setter `a` on a set field, the slot drops the old value
+    a_overwrite => {
+        let builder = ItemBuilder::new().a("first".into()).a("second".into());
+        drop(builder);
+    }
+
+    /// setter `b` on an unset field
+    b_first_set => {
+        let builder = ItemBuilder::new().b(vec![1, 2, 3]);
+        drop(builder);
+    }
+
+    /// setter `b` on a set field
+    b_overwrite => {
+        let builder = ItemBuilder::new().b(vec![1]).b(vec![2, 3]);
+        drop(builder);
+    }
+
+    /// `construct` moves the values out of the slots
+    construct => {
+        let item = ItemBuilder::new().a("a".into()).b(vec![1]).construct();
+        assert_eq!(item.a, "a");
+        assert_eq!(item.b, vec![1]);
+    }
+
+    /// `construct_push` into spare capacity that is already there
+    construct_push => {
+        let mut items = Vec::with_capacity(2);
+        ItemBuilder::new().a("a".into()).b(vec![1]).construct_push(&mut items);
+        ItemBuilder::new().a("b".into()).b(vec![2]).construct_push(&mut items);
+        assert_eq!(items[1].a, "b");
+    }
+
+    /// `construct_push` into a full vector, the elements are moved by the reallocation and
+    /// dropped along with the vector
+    construct_push_grow => {
+        let mut items = vec![ItemBuilder::new().a("a".into()).b(vec![1]).construct()];
+        items.shrink_to_fit();
+        ItemBuilder::new().a("b".into()).b(vec![2]).construct_push(&mut items);
+        assert_eq!(items.len(), 2);
+        drop(items);
+    }
+
+    /// a failed `try_construct` hands back the builder with its slots untouched, they are only
+    /// read after the retry succeeds
+    try_construct_retry => {
+        let (builder, _) = ItemBuilder::new().a("a".into()).try_construct().err().unwrap();
+        let (builder, _) = builder.try_construct().err().unwrap();
+        drop(builder.b(vec![1]).try_construct().unwrap());
+    }
+
+    /// destructor with nothing set, neither drop may run
+    drop_unset_unset => {
+        drop(ItemBuilder::new());
+    }
+
+    /// destructor with only `a` set
+    drop_set_unset => {
+        drop(ItemBuilder::new().a("a".into()));
+    }
+
+    /// destructor with only `b` set
+    drop_unset_set => {
+        drop(ItemBuilder::new().b(vec![1]));
+    }
+
+    /// destructor with both fields set
+    drop_set_set => {
+        drop(ItemBuilder::new().a("a".into()).b(vec![1]));
+    }
+
+    /// `|` with fields set on both sides, the left values get dropped
+    bitor_overlapping => {
+        let merged = ItemBuilder::new().a("left".into()).b(vec![1])
+            | ItemBuilder::new().a("right".into()).b(vec![2]);
+        let item = merged.construct();
+        assert_eq!(item.a, "right");
+        assert_eq!(item.b, vec![2]);
+    }
+
+    /// `|` with disjoint fields moves every value over
+    bitor_disjoint => {
+        let merged = ItemBuilder::new().a("left".into()) | ItemBuilder::new().b(vec![2]);
+        let item = merged.construct();
+        assert_eq!(item.a, "left");
+        assert_eq!(item.b, vec![2]);
+    }
+
+    /// `|` of two empty builders
+    bitor_empty => {
+        drop(ItemBuilder::new() | ItemBuilder::new());
+    }
+
+    /// `&` keeps only the fields set on both sides, everything else is dropped
+    bitand_partial => {
+        let intersected = ItemBuilder::new().a("left".into()).b(vec![1])
+            | ItemBuilder::new().b(vec![2]);
+        let intersected = intersected & ItemBuilder::new().a("right".into());
+        assert_eq!(intersected.set_fields(), ["a"]);
+        let partial = intersected.into_partial();
+        assert_eq!(partial, partial::ItemPartial { a: Some("right".into()), b: None });
+    }
+
+    /// `&` with everything set on both sides
+    bitand_complete => {
+        let intersected = ItemBuilder::new().a("left".into()).b(vec![1])
+            & ItemBuilder::new().a("right".into()).b(vec![2]);
+        let item = intersected.construct();
+        assert_eq!(item.a, "right");
+        assert_eq!(item.b, vec![2]);
+    }
+
+    /// the const builder reads its `Copy`-fields out, also at runtime
+    config_construct => {
+        let config = config::Config::builder().timeout(1).timeout(2).retries(3).construct();
+        assert_eq!(config, config::Config { timeout: 2, retries: 3 });
+    }
+
+    /// environment defaults with `a` already set take the value out of the slot
+    env_defaults_set => {
+        let item = ItemBuilder::new().a("set".into()).b(vec![1]).try_construct_with_env_defaults();
+        assert_eq!(item.unwrap().a, "set");
+    }
+
+    /// request setters on unset and set fields
+    request_setters => {
+        let builder = request::RequestBuilder::new().url("/a".into()).url("/b".into());
+        drop(builder.headers(HashMap::new()).headers(HashMap::new()));
+    }
+
+    /// borrowing the header map, both through the initializing variant and when already set
+    request_header_entry => {
+        let mut builder = request::RequestBuilder::new()
+            .with_header_entry("a", |entry| {
+                entry.or_insert_with(String::new);
+            });
+        builder.header_entry("b").or_insert_with(|| "b".into());
+        let request = builder.url("/".into()).construct();
+        assert_eq!(request.headers.len(), 2);
+    }
+
+    /// swapping the clock moves the set slots into a builder of a different type
+    request_with_clock => {
+        let epoch = std::time::SystemTime::UNIX_EPOCH;
+        let builder = request::RequestBuilder::new().url("/".into()).with_clock(move || epoch);
+        let request = builder.headers(HashMap::new()).construct();
+        assert_eq!(request.sent_at, epoch);
+        drop(request::RequestBuilder::new().headers(HashMap::new()).with_clock(move || epoch));
+    }
+
+    /// command setter for the program, unset and set
+    command_program => {
+        drop(command::CommandBuilder::new().program("a").program("b"));
+    }
+
+    /// the first `arg` initializes the list
+    command_first_arg => {
+        drop(command::CommandBuilder::new().arg("a"));
+    }
+
+    /// later `args` calls append to the list that is already there
+    command_more_args => {
+        drop(command::CommandBuilder::new().arg("a").args(["b", "c"]));
+    }
+
+    /// `construct` moves program and arguments out
+    command_construct => {
+        let command = command::CommandBuilder::new().arg("a").args(["b", "c"]).program("p");
+        assert_eq!(command.construct().args.len(), 3);
+    }
+
+    /// a panicking iterator while appending, the list taken out of the slot must be dropped
+    command_args_panic => {
+        let result = std::panic::catch_unwind(|| {
+            command::CommandBuilder::new().arg("a").args((0..2).map(|i| {
+                if i == 1 {
+                    panic!("iterator broke");
+                }
+                "b"
+            }))
+        });
+        assert!(result.is_err());
+    }
+
+    /// command destructor with both fields set
+    command_drop => {
+        drop(command::CommandBuilder::new().arg("a").program("p"));
+    }
+
+    /// the plugin side setting fields through the handle's function pointers
+    #[cfg(feature = "abi")]
+    abi_setters => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { abi::PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("a");
+        plugin.a("overwritten");
+        plugin.b(&[1, 2, 3]);
+        assert!(plugin.is_set(abi::FIELD_B));
+        assert_eq!(host.try_construct().unwrap().a, "overwritten");
+    }
+
+    /// constructing through the handle describes the host's item in the plugin's `RawItem`, and
+    /// leaves it alone while fields are missing
+    #[cfg(feature = "abi")]
+    abi_try_construct => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { abi::PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("a");
+        assert!(plugin.try_construct().is_none());
+        plugin.b(&[]);
+        assert!(plugin.try_construct().is_some());
+        drop(host);
+    }
+
+    /// the plugin reads the fields through the raw pointers before handing the item back
+    #[cfg(feature = "abi")]
+    abi_copy_out => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { abi::PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("text");
+        plugin.b(&[1, 2, 3]);
+        assert_eq!(plugin.try_construct().unwrap(), Item { a: "text".into(), b: vec![1, 2, 3] });
+    }
+
+    /// the host frees its item and forgets the pointers
+    #[cfg(feature = "abi")]
+    abi_free_item => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut raw = abi::RawItem::empty();
+        unsafe {
+            (handle.set_a)(handle.builder, b"a".as_ptr(), 1);
+            (handle.set_b)(handle.builder, [1].as_ptr(), 1);
+            assert!((handle.try_construct)(handle.builder, &mut raw));
+            (handle.free_item)(&mut raw);
+        }
+        assert!(raw.owner.is_null());
+    }
+
+    /// freeing an item that was never filled in, or was freed before, does nothing
+    #[cfg(feature = "abi")]
+    abi_free_empty => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut raw = abi::RawItem::empty();
+        unsafe { (handle.free_item)(&mut raw) };
+    }
+
+    /// bytes that aren't UTF-8 are rejected without touching the field
+    #[cfg(feature = "abi")]
+    abi_invalid_utf8 => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        assert!(!unsafe { (handle.set_a)(handle.builder, [0xc3].as_ptr(), 1) });
+    }
+
+    /// empty fields passed to the host as null pointers, as C callers do
+    #[cfg(feature = "abi")]
+    abi_null_empty => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        unsafe {
+            assert!((handle.set_a)(handle.builder, ptr::null(), 0));
+            (handle.set_b)(handle.builder, ptr::null(), 0);
+        }
+        assert!(host.try_construct().unwrap().b.is_empty());
+    }
+
+    /// the plugin copying empty fields out of the host's item
+    #[cfg(feature = "abi")]
+    abi_copy_out_empty => {
+        let mut host = abi::HostBuilder::new();
+        let handle = host.handle();
+        let mut plugin = unsafe { abi::PluginBuilder::new(&handle) }.unwrap();
+        plugin.a("");
+        plugin.b(&[]);
+        assert_eq!(plugin.try_construct().unwrap(), Item { a: String::new(), b: vec![] });
+    }
+
+    /// the C API copying fields out of caller memory, including empty arrays passed as null
+    #[cfg(feature = "capi")]
+    capi_setters => {
+        let builder = capi::item_builder_new();
+        unsafe {
+            capi::item_builder_set_a(builder, b"a".as_ptr(), 1);
+            capi::item_builder_set_a(builder, b"overwritten".as_ptr(), 11);
+            capi::item_builder_set_b(builder, ptr::null(), 0);
+            capi::item_builder_set_b(builder, [1, 2].as_ptr(), 2);
+            capi::item_builder_free(builder);
+        }
+    }
+
+    /// a construct with fields missing writes the bits and keeps the builder alive
+    #[cfg(feature = "capi")]
+    capi_missing => {
+        let builder = capi::item_builder_new();
+        let (mut item, mut missing) = (ptr::null_mut(), 0);
+        unsafe {
+            capi::item_builder_set_b(builder, [1].as_ptr(), 1);
+            capi::item_builder_construct(builder, &mut item, &mut missing);
+            assert_eq!(missing, capi::ITEM_FIELD_A);
+            capi::item_builder_free(builder);
+        }
+    }
+
+    /// a builder handed back by a failed construct can be completed and constructed
+    #[cfg(feature = "capi")]
+    capi_missing_then_complete => {
+        let builder = capi::item_builder_new();
+        let (mut item, mut missing) = (ptr::null_mut(), 0);
+        unsafe {
+            capi::item_builder_set_a(builder, b"a".as_ptr(), 1);
+            capi::item_builder_construct(builder, &mut item, &mut missing);
+            assert_eq!(missing, capi::ITEM_FIELD_B);
+            capi::item_builder_set_b(builder, [1].as_ptr(), 1);
+            capi::item_builder_construct(builder, &mut item, ptr::null_mut());
+            assert_eq!(*capi::item_b(item, &mut 0), 1);
+            capi::item_free(item);
+        }
+    }
+
+    /// a successful construct consumes the builder and hands out a boxed item
+    #[cfg(feature = "capi")]
+    capi_construct => {
+        let builder = capi::item_builder_new();
+        let mut item = ptr::null_mut();
+        unsafe {
+            capi::item_builder_set_a(builder, b"a".as_ptr(), 1);
+            capi::item_builder_set_b(builder, [1].as_ptr(), 1);
+            capi::item_builder_construct(builder, &mut item, ptr::null_mut());
+            capi::item_free(item);
+        }
+    }
+
+    /// the accessors lend out the item's buffers
+    #[cfg(feature = "capi")]
+    capi_accessors => {
+        let builder = capi::item_builder_new();
+        let mut item = ptr::null_mut();
+        let mut len = 0;
+        unsafe {
+            capi::item_builder_set_a(builder, b"ab".as_ptr(), 2);
+            capi::item_builder_set_b(builder, [3].as_ptr(), 1);
+            capi::item_builder_construct(builder, &mut item, ptr::null_mut());
+            assert_eq!(std::slice::from_raw_parts(capi::item_a(item, &mut len), len), b"ab");
+            assert_eq!(*capi::item_b(item, &mut len), 3);
+            assert!(capi::item_a(ptr::null(), &mut len).is_null());
+            capi::item_free(item);
+        }
+    }
+
+    /// request destructor with only the url set
+    request_drop_url => {
+        drop(request::RequestBuilder::new().url("/".into()));
+    }
+
+    /// request destructor with only the headers set
+    request_drop_headers => {
+        drop(request::RequestBuilder::new().headers(HashMap::new()));
+    }
+}
+
+#[test]
+fn every_unsafe_is_covered() {
+    let found: usize = SOURCES.iter().map(|(_, source)| count_unsafe(source)).sum();
+    assert_eq!(
+        found, UNSAFE_SITES,
+        "the number of unsafe blocks changed, register tests for the new paths in miri_surface \
+         and update `sites`",
+    );
+    assert!(REGISTERED.len() >= UNSAFE_SITES);
+}
@@ -0,0 +1,82 @@
+//! Builders for fields that have builders of their own. A type with a typesafe builder
+//! implements [HasBuilder](trait.HasBuilder.html), its builders that are complete implement
+//! [Complete](trait.Complete.html). [build](fn.build.html) hands a fresh builder to a closure and
+//! constructs whatever complete builder comes back, so a missing field deep down in a nested
+//! configuration is still a type error at the top:
+//!
+//! ```ignore
+//! let item = nested::build::<Item, _, _>(|b| b.a("a".into()).b(vec![]));
+//! // nested::build::<Item, _, _>(|b| b.a("a".into())); // error: `ItemBuilder<Set, Unset>` isn't
+//! //                                                   // `Complete`
+//! ```
+//!
+//! The closure's argument is a projection of `T`, which the compiler doesn't infer from the type
+//! the result is assigned to, so `T` has to be named.
+//!
+//! The derive implements both traits, and a field marked `#[builder(nested)]` gets a setter
+//! `<field>_with(|b| ...)` configuring the field's builder in place. A sub-builder that has been
+//! finished elsewhere is passed as `|_| finished`.
+//!
+//! The traits don't need `std`, builders of your own can implement them without it.
+
+#[cfg(feature = "std")]
+use crate::{Item, ItemBuilder, Set, Unset};
+
+/// A type with a typesafe builder.
+pub trait HasBuilder: Sized {
+    /// The builder with nothing set yet.
+    type Builder;
+
+    fn builder() -> Self::Builder;
+}
+
+/// A builder with every required field set.
+pub trait Complete {
+    type Output;
+
+    fn construct(self) -> Self::Output;
+}
+
+/// Configure a fresh builder of `T` and construct the value. The closure has to return a
+/// complete builder for `T`, not necessarily the one it was handed.
+pub fn build<T, F, B>(configure: F) -> T
+where
+    T: HasBuilder,
+    F: FnOnce(T::Builder) -> B,
+    B: Complete<Output = T>,
+{
+    configure(T::builder()).construct()
+}
+
+#[cfg(feature = "std")]
+impl HasBuilder for Item {
+    type Builder = ItemBuilder<Unset, Unset>;
+
+    fn builder() -> Self::Builder {
+        ItemBuilder::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Complete for ItemBuilder<Set, Set> {
+    type Output = Item;
+
+    fn construct(self) -> Item {
+        ItemBuilder::construct(self)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_item() {
+        let item = build::<Item, _, _>(|b| b.b(vec![1]).a("a".into()));
+        assert_eq!(item, Item { a: "a".into(), b: vec![1] });
+
+        // a builder finished elsewhere stands in for the fresh one
+        let finished = ItemBuilder::new().a("finished".into()).b(vec![]);
+        assert_eq!(build::<Item, _, _>(|_| finished).a, "finished");
+    }
+}
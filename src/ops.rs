@@ -0,0 +1,128 @@
+//! Operators for combining two partial builders. `left | right` merges them, with the fields set
+//! in `right` overriding the ones in `left`. `left & right` only keeps the fields set in both,
+//! again taking the values from `right`.
+
+use std::ops::{BitAnd, BitOr};
+
+use crate::slot::Slot;
+use crate::{FieldState, ItemBuilder, Set, Unset};
+
+/// Type-level or, the state of a field after merging two builders.
+pub trait Or<Rhs> {
+    type Output: FieldState;
+}
+
+impl Or<Unset> for Unset {
+    type Output = Unset;
+}
+impl Or<Set> for Unset {
+    type Output = Set;
+}
+impl Or<Unset> for Set {
+    type Output = Set;
+}
+impl Or<Set> for Set {
+    type Output = Set;
+}
+
+/// Type-level and, the state of a field after intersecting two builders.
+pub trait And<Rhs> {
+    type Output: FieldState;
+}
+
+impl And<Unset> for Unset {
+    type Output = Unset;
+}
+impl And<Set> for Unset {
+    type Output = Unset;
+}
+impl And<Unset> for Set {
+    type Output = Unset;
+}
+impl And<Set> for Set {
+    type Output = Set;
+}
+
+/// The value of one field of two builders that get combined. If both are set the value of
+/// `right` wins, if the result is unset anything left over is dropped.
+fn combine_slot<T, L, R, O>(left: Slot<T, L>, right: Slot<T, R>) -> Slot<T, O>
+where
+    L: FieldState,
+    R: FieldState,
+    O: FieldState,
+{
+    Slot::from_option(right.into_option().or(left.into_option()))
+}
+
+impl<A1, B1, A2, B2> BitOr<ItemBuilder<A2, B2>> for ItemBuilder<A1, B1>
+where
+    A1: FieldState + Or<A2>,
+    B1: FieldState + Or<B2>,
+    A2: FieldState,
+    B2: FieldState,
+{
+    type Output = ItemBuilder<A1::Output, B1::Output>;
+
+    fn bitor(self, rhs: ItemBuilder<A2, B2>) -> Self::Output {
+        ItemBuilder {
+            a: combine_slot(self.a, rhs.a),
+            b: combine_slot(self.b, rhs.b),
+        }
+    }
+}
+
+impl<A1, B1, A2, B2> BitAnd<ItemBuilder<A2, B2>> for ItemBuilder<A1, B1>
+where
+    A1: FieldState + And<A2>,
+    B1: FieldState + And<B2>,
+    A2: FieldState,
+    B2: FieldState,
+{
+    type Output = ItemBuilder<A1::Output, B1::Output>;
+
+    fn bitand(self, rhs: ItemBuilder<A2, B2>) -> Self::Output {
+        ItemBuilder {
+            a: combine_slot(self.a, rhs.a),
+            b: combine_slot(self.b, rhs.b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::ItemPartial;
+
+    #[test]
+    fn or_merges_with_right_winning() {
+        let merged: ItemBuilder<Set, Set> =
+            ItemBuilder::new().a("left".into()).b(vec![1]) | ItemBuilder::new().a("right".into());
+        let item = merged.construct();
+        assert_eq!((item.a.as_str(), item.b), ("right", vec![1]));
+
+        let merged: ItemBuilder<Set, Unset> = ItemBuilder::new() | ItemBuilder::new().a("a".into());
+        assert_eq!(merged.set_fields(), ["a"]);
+        assert_eq!(merged.into_partial(), ItemPartial { a: Some("a".into()), b: None });
+
+        let merged: ItemBuilder<Unset, Unset> = ItemBuilder::new() | ItemBuilder::new();
+        assert_eq!(merged.set_fields(), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn and_keeps_what_both_set() {
+        let intersected: ItemBuilder<Set, Unset> =
+            ItemBuilder::new().a("left".into()).b(vec![1]) & ItemBuilder::new().a("right".into());
+        assert_eq!(intersected.set_fields(), ["a"]);
+        assert_eq!(intersected.into_partial(), ItemPartial { a: Some("right".into()), b: None });
+
+        let intersected: ItemBuilder<Unset, Unset> =
+            ItemBuilder::new().a("left".into()) & ItemBuilder::new().b(vec![2]);
+        assert_eq!(intersected.missing_fields(), ["a", "b"]);
+        assert_eq!(intersected.into_partial(), ItemPartial { a: None, b: None });
+
+        let intersected: ItemBuilder<Set, Set> = ItemBuilder::new().a("left".into()).b(vec![1])
+            & ItemBuilder::new().a("right".into()).b(vec![2]);
+        let item = intersected.construct();
+        assert_eq!((item.a.as_str(), item.b), ("right", vec![2]));
+    }
+}
@@ -0,0 +1,156 @@
+//! Checks that the runtime-checked APIs report bad input as errors instead of panicking. Every
+//! exported function that validates something at runtime is fed a generated corpus of inputs, any
+//! panic fails the audit. The typestate APIs aren't part of it, they can't fail at runtime.
+//!
+//! New runtime-checked APIs have to be added to `audit` below.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use crate::batch::BatchPolicy;
+use crate::dynamic::{DynItemBuilder, Value};
+use crate::env::try_from_str_default;
+use crate::guard::ExportBuilder;
+use crate::partial::ItemPartial;
+use crate::validate::rules;
+use crate::{Item, ItemBuilder};
+
+/// Deterministic pseudo-random inputs, a linear congruential generator is plenty for this.
+struct Corpus(u64);
+
+impl Corpus {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn coin(&mut self) -> bool {
+        self.next() & 1 == 1
+    }
+
+    fn string(&mut self) -> String {
+        const PIECES: &[&str] = &["", "0", "-1", "255", "256", "99999999999", "a", " ", "ü", "\t"];
+        let len = self.next() % 4;
+        (0..len).map(|_| PIECES[self.next() as usize % PIECES.len()]).collect()
+    }
+
+    fn numbers(&mut self) -> Vec<i32> {
+        let len = self.next() % 4;
+        (0..len).map(|_| self.next() as i32).collect()
+    }
+
+    /// A field name, not always one `Item` has.
+    fn field(&mut self) -> &'static str {
+        ["a", "b", "c", ""][self.next() as usize % 4]
+    }
+
+    fn value(&mut self) -> Value {
+        if self.coin() {
+            Value::Text(self.string())
+        } else {
+            Value::List(self.numbers())
+        }
+    }
+
+    fn partial(&mut self) -> ItemPartial {
+        ItemPartial {
+            a: if self.coin() { Some(self.string()) } else { None },
+            b: if self.coin() { Some(self.numbers()) } else { None },
+        }
+    }
+}
+
+/// Run every runtime-checked API on one set of inputs.
+fn audit(corpus: &mut Corpus) {
+    let _ = corpus.partial().try_build();
+    let _ = ItemBuilder::new().try_construct();
+    let _ = ItemBuilder::new().b(corpus.numbers()).try_construct();
+    let _ = ItemBuilder::new().b(corpus.numbers()).try_fill(corpus.partial());
+    let _ = ItemBuilder::new().b(corpus.numbers()).try_construct_with_env_defaults();
+
+    let partials: Vec<_> = (0..corpus.next() % 4).map(|_| corpus.partial()).collect();
+    let _ = Item::build_batch(partials.clone(), BatchPolicy::FailFast);
+    let _ = Item::build_batch(partials, BatchPolicy::CollectAll);
+
+    env::set_var("BUILDER_PANIC_AUDIT", corpus.string());
+    let _ = try_from_str_default::<u8>("panic_audit");
+    let _ = try_from_str_default::<i64>("panic_audit");
+    let _ = try_from_str_default::<f32>("panic_audit");
+    let _ = try_from_str_default::<bool>("panic_audit");
+    env::set_var("PANIC_AUDIT_B", corpus.string());
+    let _ = ItemPartial::from_env("panic_audit");
+
+    let mut dynamic = DynItemBuilder::from(corpus.partial());
+    let _ = dynamic.set(corpus.field(), corpus.value());
+    let _ = dynamic.unset(corpus.field());
+    let fields: Vec<_> = (0..corpus.next() % 4).map(|_| (corpus.field(), corpus.value())).collect();
+    let _ = dynamic.set_all(fields.clone());
+    let _ = dynamic.build();
+    let map: HashMap<String, _> =
+        fields.into_iter().map(|(field, value)| (field.into(), value)).collect();
+    let _ = DynItemBuilder::try_from(map.clone());
+    let _ = Item::try_from(map);
+
+    let ttl = Duration::from_secs(corpus.next());
+    let _ = ItemBuilder::new().b(corpus.numbers()).expires_in(ttl).try_construct();
+    let _ = ItemBuilder::new().expires_in(Duration::MAX).into_inner();
+
+    let _ = ExportBuilder::new().compression(corpus.next() as u8);
+    let _ = ExportBuilder::new().key(corpus.string());
+
+    let _ = ItemBuilder::new().validated_a(corpus.string(), rules::non_empty);
+    let _ = ItemBuilder::new().validated_b(corpus.numbers(), rules::all_in(-9..=9));
+
+    #[cfg(feature = "clap")]
+    {
+        let mut args = vec![String::from("item")];
+        for _ in 0..corpus.next() % 6 {
+            let flag = ["--a", "--b", "--c", "-"][corpus.next() as usize % 4];
+            args.push(if corpus.coin() { flag.into() } else { corpus.string() });
+        }
+        let _ = Item::try_from_args(args);
+    }
+
+    #[cfg(all(feature = "capi", not(feature = "forbid-unsafe")))]
+    {
+        use crate::capi;
+
+        let builder = capi::item_builder_new();
+        // cut anywhere, possibly in the middle of a character
+        let a = corpus.string();
+        let a = &a.as_bytes()[..corpus.next() as usize % (a.len() + 1)];
+        let b = corpus.numbers();
+        let (mut item, mut missing) = (std::ptr::null_mut(), 0);
+        // the pointers are the builder's own and buffers of the given lengths
+        unsafe {
+            if corpus.coin() {
+                capi::item_builder_set_a(builder, a.as_ptr(), a.len());
+            }
+            if corpus.coin() {
+                capi::item_builder_set_b(builder, b.as_ptr(), b.len());
+            }
+            match capi::item_builder_construct(builder, &mut item, &mut missing) {
+                capi::ItemStatus::Ok => capi::item_free(item),
+                _ => capi::item_builder_free(builder),
+            }
+        }
+    }
+
+    let shared = crate::shared::SharedBuilder::new();
+    if let Some(a) = corpus.partial().a {
+        shared.a(a);
+    }
+    let _ = shared.construct();
+}
+
+#[test]
+fn runtime_checks_never_panic() {
+    let mut corpus = Corpus(0x5eed);
+    let panics = (0..500)
+        .filter(|_| panic::catch_unwind(AssertUnwindSafe(|| audit(&mut corpus))).is_err())
+        .count();
+    assert_eq!(panics, 0, "runtime-checked APIs panicked on {} inputs", panics);
+}
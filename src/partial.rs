@@ -0,0 +1,176 @@
+//! A plain struct with every field of `Item` optional, for data that arrives from somewhere the
+//! compiler can't see (deserialization, user input, ...). Turning it into an `Item` has to check
+//! at runtime which fields are there.
+//!
+//! With the `serde` feature partials can be deserialized, a missing field is `None`. Fields that
+//! come from a file and fields that are set in code meet in
+//! [try_fill](../struct.ItemBuilder.html#method.try_fill):
+//!
+//! ```ignore
+//! let partial: ItemPartial = serde_json::from_str(r#"{ "a": "from the file" }"#)?;
+//! let item = ItemBuilder::new().b(vec![1]).try_fill(partial)?.construct();
+//! ```
+
+use crate::error::BuildError;
+use crate::reflection::{missing_fields, state_bits};
+use crate::{FieldState, Item, ItemBuilder, Set};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemPartial {
+    pub a: Option<String>,
+    pub b: Option<Vec<i32>>,
+}
+
+impl ItemPartial {
+    /// The fields that are set as a bitmask, with the same bits as
+    /// [ItemBuilder::state_bits](../struct.ItemBuilder.html#method.state_bits).
+    pub fn state_bits(&self) -> u64 {
+        state_bits(&[self.a.is_some(), self.b.is_some()])
+    }
+
+    /// Build the item, or report every field that is missing.
+    pub fn try_build(self) -> Result<Item, BuildError> {
+        match self {
+            ItemPartial { a: Some(a), b: Some(b) } => Ok(Item { a, b }),
+            ItemPartial { a, b } => {
+                let states = [a.is_some(), b.is_some()];
+                Err(BuildError::MissingFields(missing_fields(ItemBuilder::FIELDS, &states)))
+            }
+        }
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Move whatever has been set into a partial. This is also the way to salvage the values of
+    /// a builder that won't be finished, e.g. to hand a large buffer back to a pool: the values
+    /// are moved out as they are, nothing is dropped and unset slots are never read.
+    pub fn into_partial(self) -> ItemPartial {
+        ItemPartial {
+            a: self.a.into_option(),
+            b: self.b.into_option(),
+        }
+    }
+
+    /// Construct the item if every field has been set, checked at runtime. Useful where the
+    /// state isn't known statically, e.g. in generic code. On failure the builder is handed back
+    /// untouched, so the missing fields can be set and construction retried.
+    pub fn try_construct(self) -> Result<Item, (Self, BuildError)> {
+        // check before moving anything out of the slots, a failure has to return every field
+        let missing = self.missing_fields();
+        if !missing.is_empty() {
+            return Err((self, BuildError::MissingFields(missing.to_vec())));
+        }
+
+        match (self.a.into_option(), self.b.into_option()) {
+            (Some(a), Some(b)) => Ok(Item { a, b }),
+            _ => unreachable!("every field was checked to be set"),
+        }
+    }
+
+    /// Fill the fields that haven't been set from `partial`, fields that have been set keep their
+    /// value. Fails if a field is set in neither of them, handing back the builder untouched.
+    pub fn try_fill(
+        self,
+        partial: ItemPartial,
+    ) -> Result<ItemBuilder<Set, Set>, (Self, BuildError)> {
+        let states = [A::IS_SET || partial.a.is_some(), B::IS_SET || partial.b.is_some()];
+        let missing = missing_fields(ItemBuilder::FIELDS, &states);
+        if !missing.is_empty() {
+            return Err((self, BuildError::MissingFields(missing)));
+        }
+
+        match (self.a.into_option().or(partial.a), self.b.into_option().or(partial.b)) {
+            (Some(a), Some(b)) => Ok(ItemBuilder::new().a(a).b(b)),
+            _ => unreachable!("every field was checked to be set"),
+        }
+    }
+
+    /// Which fields are set, like `ItemBuilder { a: Set, b: Unset }`.
+    pub fn describe_state(&self) -> String {
+        let fields: Vec<_> = ItemBuilder::FIELDS
+            .iter()
+            .zip(&Self::STATES)
+            .map(|(field, &set)| format!("{}: {}", field.name, if set { "Set" } else { "Unset" }))
+            .collect();
+        format!("ItemBuilder {{ {} }}", fields.join(", "))
+    }
+
+    /// [try_construct](#method.try_construct) with the state of the builder added to the error.
+    #[cfg(feature = "anyhow")]
+    pub fn try_construct_anyhow(self) -> anyhow::Result<Item> {
+        use anyhow::Context;
+
+        let state = self.describe_state();
+        self.try_construct()
+            .map_err(|(_, err)| err)
+            .with_context(|| format!("failed to construct Item\n\nBuilder state: {}", state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salvage() {
+        let b = Vec::with_capacity(1024);
+        let (ptr, capacity) = (b.as_ptr(), b.capacity());
+
+        let partial = ItemBuilder::new().b(b).into_partial();
+        assert_eq!(partial.a, None);
+        let b = partial.b.unwrap();
+        assert_eq!((b.as_ptr(), b.capacity()), (ptr, capacity));
+    }
+
+    #[test]
+    fn try_construct() {
+        assert!(ItemBuilder::new().a("a".into()).b(vec![]).try_construct().is_ok());
+
+        let (builder, err) = ItemBuilder::new().b(vec![1]).try_construct().err().unwrap();
+        assert_eq!(err, BuildError::MissingFields(vec!["a"]));
+        assert_eq!(builder.missing_fields(), ["a"]);
+
+        // nothing was moved out of the returned builder, `b` is still there
+        let item = builder.a("fixed".into()).try_construct().unwrap();
+        assert_eq!(item, Item { a: "fixed".into(), b: vec![1] });
+    }
+
+    #[test]
+    fn try_fill() {
+        let partial = ItemPartial { a: Some("partial".into()), b: Some(vec![2]) };
+        let builder = ItemBuilder::new().b(vec![1]).try_fill(partial).unwrap();
+        assert_eq!(builder.construct(), Item { a: "partial".into(), b: vec![1] });
+
+        let builder = ItemBuilder::new().b(vec![1]);
+        let (builder, err) = builder.try_fill(ItemPartial::default()).unwrap_err();
+        assert_eq!(err, BuildError::MissingFields(vec!["a"]));
+        assert_eq!(builder.state_bits(), 0b10);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        let partial: ItemPartial = serde_json::from_str(r#"{ "a": "from json" }"#).unwrap();
+        assert_eq!(partial, ItemPartial { a: Some("from json".into()), b: None });
+
+        let item = ItemBuilder::new().b(vec![1]).try_fill(partial).unwrap().construct();
+        assert_eq!(item, Item { a: "from json".into(), b: vec![1] });
+
+        let partial: ItemPartial = serde_json::from_str("{}").unwrap();
+        let (_, err) = ItemBuilder::new().try_fill(partial).unwrap_err();
+        assert_eq!(err.fields(), ["a", "b"]);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_context() {
+        let err = ItemBuilder::new().a("a".into()).try_construct_anyhow().unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "failed to construct Item\n\nBuilder state: ItemBuilder { a: Set, b: Unset }: \
+             missing fields: b",
+        );
+        assert_eq!(err.downcast_ref(), Some(&BuildError::MissingFields(vec!["b"])));
+    }
+}
@@ -0,0 +1,195 @@
+//! Fields whose values come from async lookups. [AsyncItemBuilder](struct.AsyncItemBuilder.html)
+//! stores a [Pending](enum.Pending.html) in each slot, either a value or a boxed future, and
+//! `construct_async()` awaits the futures before assembling the item:
+//!
+//! ```ignore
+//! let builder = AsyncItemBuilder::new().a_from(secrets.get("name")).b(vec![1]);
+//! let item = builder.construct_async().await; // the lookup is awaited here
+//! ```
+//!
+//! The futures of all fields are polled together, so two lookups take as long as the slower one
+//! instead of both in a row. A field only counts as `Set` once a value or a future has been
+//! registered for it, so `construct_async()` needs every field covered at compile-time like
+//! `construct()` does. A future that is replaced or abandoned is dropped without being polled.
+//!
+//! Nothing here depends on a runtime, any executor can drive `construct_async()`. Futures have to
+//! be `Send` so the builder and the future of `construct_async()` can move between threads.
+
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::slot::Slot;
+use crate::{FieldState, Item, ItemBuilder, Set, Unset};
+
+/// A value, or the future producing it.
+pub enum Pending<T> {
+    Ready(T),
+    Future(Pin<Box<dyn Future<Output = T> + Send>>),
+}
+
+impl<T> Pending<T> {
+    /// Poll the future if there is one, keeping its output. Returns whether the value is there.
+    fn poll_ready(&mut self, cx: &mut Context) -> bool {
+        if let Pending::Future(future) = self {
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => *self = Pending::Ready(value),
+                Poll::Pending => return false,
+            }
+        }
+        true
+    }
+
+    fn into_ready(self) -> T {
+        match self {
+            Pending::Ready(value) => value,
+            Pending::Future(_) => unreachable!("the future was polled to completion"),
+        }
+    }
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct AsyncItemBuilder<A: FieldState, B: FieldState> {
+    a: Slot<Pending<String>, A>,
+    b: Slot<Pending<Vec<i32>>, B>,
+}
+
+impl AsyncItemBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        AsyncItemBuilder { a: Slot::empty(), b: Slot::empty() }
+    }
+}
+
+impl Default for AsyncItemBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: FieldState, B: FieldState> AsyncItemBuilder<A, B> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a(self, a: String) -> AsyncItemBuilder<Set, B> {
+        AsyncItemBuilder { a: self.a.write(Pending::Ready(a)), b: self.b }
+    }
+
+    /// Set `a` to the output of `future`, awaited in `construct_async()`.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a_from<F>(self, future: F) -> AsyncItemBuilder<Set, B>
+    where
+        F: Future<Output = String> + Send + 'static,
+    {
+        AsyncItemBuilder { a: self.a.write(Pending::Future(Box::pin(future))), b: self.b }
+    }
+
+    /// Same as [a](#method.a)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b(self, b: Vec<i32>) -> AsyncItemBuilder<A, Set> {
+        AsyncItemBuilder { a: self.a, b: self.b.write(Pending::Ready(b)) }
+    }
+
+    /// Same as [a_from](#method.a_from)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn b_from<F>(self, future: F) -> AsyncItemBuilder<A, Set>
+    where
+        F: Future<Output = Vec<i32>> + Send + 'static,
+    {
+        AsyncItemBuilder { a: self.a, b: self.b.write(Pending::Future(Box::pin(future))) }
+    }
+}
+
+impl AsyncItemBuilder<Set, Set> {
+    /// Await the futures of all fields at once and construct the item.
+    pub async fn construct_async(self) -> Item {
+        let (mut a, mut b) = (self.a.into_inner(), self.b.into_inner());
+        future::poll_fn(|cx| {
+            // both are polled every time, one that is waiting doesn't hold up the other
+            let a_ready = a.poll_ready(cx);
+            let b_ready = b.poll_ready(cx);
+            if a_ready && b_ready {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        Item { a: a.into_ready(), b: b.into_ready() }
+    }
+}
+
+/// The values that are set become ready values of the async builder.
+impl<A: FieldState, B: FieldState> From<ItemBuilder<A, B>> for AsyncItemBuilder<A, B> {
+    fn from(builder: ItemBuilder<A, B>) -> Self {
+        let partial = builder.into_partial();
+        AsyncItemBuilder {
+            a: Slot::from_option(partial.a.map(Pending::Ready)),
+            b: Slot::from_option(partial.b.map(Pending::Ready)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    use super::*;
+
+    /// The smallest executor there is, parking the thread until the future wakes it.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn futures_resolve_together() {
+        // `a` only finishes once `b` has run, awaiting them one after the other would never end
+        let b_ran = Arc::new(AtomicBool::new(false));
+        let waiting = b_ran.clone();
+        let a = future::poll_fn(move |cx| {
+            if waiting.load(Ordering::SeqCst) {
+                Poll::Ready(String::from("a"))
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        });
+        let b = async move {
+            b_ran.store(true, Ordering::SeqCst);
+            vec![1]
+        };
+
+        let construct = AsyncItemBuilder::new().a_from(a).b_from(b).construct_async();
+        let construct = thread::spawn(move || construct).join().unwrap();
+        let item = block_on(construct);
+        assert_eq!(item, Item { a: "a".into(), b: vec![1] });
+    }
+
+    #[test]
+    fn values_and_replaced_futures() {
+        let builder = AsyncItemBuilder::new().a_from(async { panic!("replaced") });
+        let item = block_on(builder.a("a".into()).b(vec![2]).construct_async());
+        assert_eq!(item, Item { a: "a".into(), b: vec![2] });
+
+        let builder = AsyncItemBuilder::from(ItemBuilder::new().a("eager".into()));
+        let item = block_on(builder.b_from(async { vec![3] }).construct_async());
+        assert_eq!(item, Item { a: "eager".into(), b: vec![3] });
+    }
+}
@@ -0,0 +1,100 @@
+//! Compile-time policy over the fields of a builder. A check is a `const fn` taking the
+//! [FIELDS](../reflection/struct.FieldInfo.html) table of a builder, `const_check!` runs it while
+//! compiling and turns an error into a compile error carrying the message:
+//!
+//! ```ignore
+//! const_check!(ExportBuilder::FIELDS, policy::secrets_are_sensitive);
+//! // error[E0080]: evaluation panicked: a field holding a secret has to be marked as sensitive
+//! ```
+//!
+//! Projects can write their own checks the same way, anything that can be decided from the
+//! names, types and flags of the fields. Messages have to be plain `&'static str`s, const code
+//! can't format the name of the offending field into them.
+
+use crate::reflection::FieldInfo;
+
+/// Run `$check` on the field table `$fields` at compile-time, failing the build with its message
+/// if it returns an error.
+#[macro_export]
+macro_rules! const_check {
+    ($fields:expr, $check:path) => {
+        const _: () = match $check($fields) {
+            Ok(()) => (),
+            Err(message) => panic!("{}", message),
+        };
+    };
+}
+
+/// `a == b`, which isn't available in const contexts yet.
+pub const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Field names that hold secrets wherever they show up.
+pub const SECRET_NAMES: &[&str] = &["password", "key", "token", "secret"];
+
+/// Fields named like a secret have to be marked as sensitive, so they stay out of logs and diffs.
+pub const fn secrets_are_sensitive(fields: &[FieldInfo]) -> Result<(), &'static str> {
+    let mut i = 0;
+    while i < fields.len() {
+        let mut j = 0;
+        while j < SECRET_NAMES.len() {
+            if str_eq(fields[i].name, SECRET_NAMES[j]) && !fields[i].sensitive {
+                return Err("a field holding a secret has to be marked as sensitive");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::ExportBuilder;
+    use crate::ItemBuilder;
+
+    /// A project-specific check: no builder may have more than two required fields.
+    const fn at_most_two_required(fields: &[FieldInfo]) -> Result<(), &'static str> {
+        let (mut i, mut required) = (0, 0);
+        while i < fields.len() {
+            required += fields[i].required as usize;
+            i += 1;
+        }
+        if required > 2 {
+            Err("too many required fields")
+        } else {
+            Ok(())
+        }
+    }
+
+    const_check!(ItemBuilder::FIELDS, at_most_two_required);
+
+    #[test]
+    fn passing_and_failing_checks() {
+        assert_eq!(at_most_two_required(ItemBuilder::FIELDS), Ok(()));
+        // this one would fail the build if it was passed to `const_check!`
+        assert_eq!(at_most_two_required(ExportBuilder::FIELDS), Err("too many required fields"));
+    }
+
+    #[test]
+    fn sensitive_flag() {
+        assert_eq!(secrets_are_sensitive(ExportBuilder::FIELDS), Ok(()));
+
+        let plain_key = [FieldInfo { sensitive: false, ..ExportBuilder::FIELDS[2] }];
+        assert!(secrets_are_sensitive(&plain_key).is_err());
+        assert!(str_eq("key", "key") && !str_eq("key", "keys") && !str_eq("key", "kei"));
+    }
+}
@@ -0,0 +1,250 @@
+//! A pool handing out builders that reuse the buffers of items that have been built before. The
+//! typestate works just like with the plain builder, the only difference is that setters copy
+//! into a recycled buffer instead of taking ownership of a freshly allocated one.
+
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{FieldState, Item, ItemBuilder, Set, Unset};
+
+/// Types whose allocations can be kept around for the next value. `recycle` has to clear the
+/// contents while keeping the capacity.
+pub trait Recycle: Default {
+    fn recycle(&mut self);
+}
+
+impl Recycle for Item {
+    fn recycle(&mut self) {
+        self.a.clear();
+        self.b.clear();
+    }
+}
+
+/// A thread-safe stash of cleared values. At most `limit` shells are retained, anything returned
+/// beyond that is simply dropped.
+pub struct BuilderPool<T> {
+    shells: Mutex<Vec<T>>,
+    limit: usize,
+}
+
+impl<T: Recycle> BuilderPool<T> {
+    /// Create a pool that retains at most `limit` shells.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            shells: Mutex::new(Vec::new()),
+            limit,
+        }
+    }
+
+    /// Number of shells currently waiting to be reused.
+    pub fn len(&self) -> usize {
+        self.shells.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Whether there is no shell waiting to be reused.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take a shell out of the pool, or create an empty one if there is none.
+    fn take(&self) -> T {
+        self.shells
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Clear a value and keep it for the next builder, unless the pool is full.
+    fn put(&self, mut shell: T) {
+        let mut shells = self.shells.lock().unwrap_or_else(PoisonError::into_inner);
+        if shells.len() < self.limit {
+            shell.recycle();
+            shells.push(shell);
+        }
+    }
+}
+
+impl BuilderPool<Item> {
+    /// Hand out an empty builder, backed by a recycled shell once the first field is set.
+    pub fn get(&self) -> PooledBuilder<'_, Unset, Unset> {
+        PooledBuilder { builder: ItemBuilder::new(), shell: None, pool: self }
+    }
+}
+
+/// An `ItemBuilder` that carries a recycled item around. Setting a field copies the value into
+/// the buffer of the shell and moves that buffer into the builder. The shell is taken from the
+/// pool by the first setter, so items dropped in the meantime are reused.
+#[must_use = "builder must be used to construct a value"]
+pub struct PooledBuilder<'p, A: FieldState, B: FieldState> {
+    builder: ItemBuilder<A, B>,
+    shell: Option<Item>,
+    pool: &'p BuilderPool<Item>,
+}
+
+impl<'p, A: FieldState, B: FieldState> PooledBuilder<'p, A, B> {
+    /// Set `a` by copying into the recycled string.
+    pub fn a(self, a: &str) -> PooledBuilder<'p, Set, B> {
+        let PooledBuilder { builder, shell, pool } = self;
+        let mut shell = shell.unwrap_or_else(|| pool.take());
+        let mut buffer = mem::take(&mut shell.a);
+        buffer.push_str(a);
+
+        PooledBuilder { builder: builder.a(buffer), shell: Some(shell), pool }
+    }
+
+    /// Set `b` by copying into the recycled vector.
+    pub fn b(self, b: &[i32]) -> PooledBuilder<'p, A, Set> {
+        let PooledBuilder { builder, shell, pool } = self;
+        let mut shell = shell.unwrap_or_else(|| pool.take());
+        let mut buffer = mem::take(&mut shell.b);
+        buffer.extend_from_slice(b);
+
+        PooledBuilder { builder: builder.b(buffer), shell: Some(shell), pool }
+    }
+
+    /// Start over without giving up the buffers: the values that have been set are cleared and
+    /// their buffers go back into the shell, so the next setters copy into them again.
+    pub fn reset(self) -> PooledBuilder<'p, Unset, Unset> {
+        let PooledBuilder { builder, mut shell, pool } = self;
+        let partial = builder.into_partial();
+        // a field can only be set once the shell has been taken
+        if let Some(shell) = &mut shell {
+            if let Some(a) = partial.a {
+                shell.a = a;
+            }
+            if let Some(b) = partial.b {
+                shell.b = b;
+            }
+            shell.recycle();
+        }
+
+        PooledBuilder { builder: ItemBuilder::new(), shell, pool }
+    }
+}
+
+impl<'p> PooledBuilder<'p, Set, Set> {
+    /// Construct the item. It goes back to the pool as soon as the returned guard is dropped.
+    pub fn construct_pooled(self) -> Pooled<'p, Item> {
+        Pooled {
+            item: Some(self.builder.construct()),
+            pool: self.pool,
+        }
+    }
+
+    /// Construct the item and hand out the next builder. The item takes the buffers, so the
+    /// emptied shell is dropped and the next builder takes a new one from the pool once it sets
+    /// a field, the item's own if it has been dropped by then.
+    pub fn construct_and_reset(self) -> (Pooled<'p, Item>, PooledBuilder<'p, Unset, Unset>) {
+        let item = Pooled { item: Some(self.builder.construct()), pool: self.pool };
+        (item, self.pool.get())
+    }
+}
+
+/// An item built from a pool, returning its buffers to the pool on drop.
+pub struct Pooled<'p, T: Recycle> {
+    item: Option<T>,
+    pool: &'p BuilderPool<T>,
+}
+
+impl<'p, T: Recycle> Pooled<'p, T> {
+    /// Keep the item for good, it won't go back to the pool.
+    pub fn into_inner(mut self) -> T {
+        self.item.take().expect("the item is only taken on drop")
+    }
+}
+
+impl<'p, T: Recycle> Deref for Pooled<'p, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.item.as_ref().expect("the item is only taken on drop")
+    }
+}
+
+impl<'p, T: Recycle> DerefMut for Pooled<'p, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.item.as_mut().expect("the item is only taken on drop")
+    }
+}
+
+impl<'p, T: Recycle> Drop for Pooled<'p, T> {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.pool.put(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused() {
+        let pool = BuilderPool::new(4);
+
+        let first = pool
+            .get()
+            .a("a fairly long string")
+            .b(&[1, 2, 3, 4, 5, 6, 7, 8])
+            .construct_pooled();
+        let (a_ptr, b_ptr) = (first.a.as_ptr(), first.b.as_ptr());
+        let (a_cap, b_cap) = (first.a.capacity(), first.b.capacity());
+        drop(first);
+        assert_eq!(pool.len(), 1);
+
+        let second = pool.get().a("short").b(&[1]).construct_pooled();
+        assert_eq!(second.a, "short");
+        assert_eq!(second.b, vec![1]);
+        assert_eq!((second.a.as_ptr(), second.b.as_ptr()), (a_ptr, b_ptr));
+        assert_eq!((second.a.capacity(), second.b.capacity()), (a_cap, b_cap));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn reset_keeps_the_buffers() {
+        let pool = BuilderPool::new(4);
+
+        let buffers = |b: &PooledBuilder<Set, Set>| {
+            (b.builder.get_a().as_ptr(), b.builder.get_b().as_ptr())
+        };
+        let builder = pool.get().a("a fairly long string").b(&[1, 2, 3, 4]);
+        let before = buffers(&builder);
+        let builder = builder.reset().b(&[5]).a("short");
+        assert_eq!(buffers(&builder), before);
+
+        // the item goes back to the pool, the next builder gets its buffers from there
+        let (item, builder) = builder.construct_and_reset();
+        assert_eq!((item.a.as_str(), item.b.as_slice()), ("short", &[5][..]));
+        let (a_ptr, b_ptr) = (item.a.as_ptr(), item.b.as_ptr());
+        let (a_cap, b_cap) = (item.a.capacity(), item.b.capacity());
+        drop(item);
+        let item = builder.reset().a("next").b(&[6]).construct_pooled();
+        assert_eq!((item.a.as_str(), item.b.as_slice()), ("next", &[6][..]));
+        assert_eq!((item.a.as_ptr(), item.b.as_ptr()), (a_ptr, b_ptr));
+        assert_eq!((item.a.capacity(), item.b.capacity()), (a_cap, b_cap));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn retained_shells_are_capped() {
+        let pool = BuilderPool::new(1);
+
+        let first = pool.get().a("1").b(&[1]).construct_pooled();
+        let second = pool.get().a("2").b(&[2]).construct_pooled();
+        drop(first);
+        drop(second);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn detached_items_stay_out_of_the_pool() {
+        let pool = BuilderPool::new(1);
+
+        let item = pool.get().a("kept").b(&[]).construct_pooled().into_inner();
+        assert_eq!(item.a, "kept");
+        assert!(pool.is_empty());
+    }
+}
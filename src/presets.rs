@@ -0,0 +1,195 @@
+//! Setter templates for std types that keep coming up in configuration structs. Each macro is
+//! invoked inside the `impl` block of a builder and generates convenience setters forwarding to
+//! the strict setter of the field, so the typestate transition stays in one place:
+//!
+//! ```ignore
+//! impl<A, B> ServerBuilder<A, B> {
+//!     duration_preset!(timeout: timeout_secs, timeout_ms -> ServerBuilder<Set, B>);
+//!     path_preset!(root: root_path -> ServerBuilder<A, Set>);
+//! }
+//! ```
+//!
+//! The parsing presets can fail, they follow the try-setter convention of returning the error
+//! instead of the builder. Derived builders get the same setters from
+//! `#[builder(template = duration)]`, `template = path` or `template = addr` on the field.
+
+use std::fmt;
+
+use crate::error::BuildError;
+
+/// `Duration` fields, generates setters taking whole seconds and milliseconds.
+#[macro_export]
+macro_rules! duration_preset {
+    ($field:ident: $secs:ident, $millis:ident -> $out:ty) => {
+        pub fn $secs(self, secs: u64) -> $out {
+            self.$field(::std::time::Duration::from_secs(secs))
+        }
+
+        pub fn $millis(self, millis: u64) -> $out {
+            self.$field(::std::time::Duration::from_millis(millis))
+        }
+    };
+}
+
+/// `PathBuf` fields, generates a setter taking anything that can be viewed as a path.
+#[macro_export]
+macro_rules! path_preset {
+    ($field:ident: $setter:ident -> $out:ty) => {
+        pub fn $setter<P: AsRef<::std::path::Path>>(self, path: P) -> $out {
+            self.$field(path.as_ref().to_path_buf())
+        }
+    };
+}
+
+/// `IpAddr`, `SocketAddr` and friends, generates a try-setter parsing a string. Input that
+/// doesn't parse is a `BuildError::Invalid` of the field.
+#[macro_export]
+macro_rules! addr_preset {
+    ($field:ident: $setter:ident -> $out:ty) => {
+        pub fn $setter(self, addr: &str) -> Result<$out, $crate::error::BuildError> {
+            match addr.parse() {
+                Ok(parsed) => Ok(self.$field(parsed)),
+                Err(err) => Err($crate::presets::invalid(stringify!($field), addr, err)),
+            }
+        }
+    };
+}
+
+/// The error of a parsing preset, used by the expansions of `addr_preset!` and the derive.
+#[doc(hidden)]
+pub fn invalid(field: &'static str, input: &str, err: impl fmt::Display) -> BuildError {
+    BuildError::Invalid { field, reason: format!("can't parse {:?}: {}", input, err) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use crate::error::{BuildError, ErrorKind};
+    use crate::slot::Slot;
+    use crate::{FieldState, Set, Unset};
+
+    #[derive(Debug, PartialEq)]
+    struct Server {
+        timeout: Duration,
+        root: PathBuf,
+        ip: IpAddr,
+        listen: SocketAddr,
+    }
+
+    /// The presets only forward to the strict setters, so they move the same field to `Set`.
+    struct ServerBuilder<T: FieldState, R: FieldState, I: FieldState, L: FieldState> {
+        timeout: Slot<Duration, T>,
+        root: Slot<PathBuf, R>,
+        ip: Slot<IpAddr, I>,
+        listen: Slot<SocketAddr, L>,
+    }
+
+    impl ServerBuilder<Unset, Unset, Unset, Unset> {
+        fn new() -> Self {
+            Self {
+                timeout: Slot::empty(),
+                root: Slot::empty(),
+                ip: Slot::empty(),
+                listen: Slot::empty(),
+            }
+        }
+    }
+
+    impl<T: FieldState, R: FieldState, I: FieldState, L: FieldState> ServerBuilder<T, R, I, L> {
+        fn timeout(self, timeout: Duration) -> ServerBuilder<Set, R, I, L> {
+            let Self { timeout: slot, root, ip, listen } = self;
+            ServerBuilder { timeout: slot.write(timeout), root, ip, listen }
+        }
+
+        fn root(self, root: PathBuf) -> ServerBuilder<T, Set, I, L> {
+            let Self { timeout, root: slot, ip, listen } = self;
+            ServerBuilder { timeout, root: slot.write(root), ip, listen }
+        }
+
+        fn ip(self, ip: IpAddr) -> ServerBuilder<T, R, Set, L> {
+            let Self { timeout, root, ip: slot, listen } = self;
+            ServerBuilder { timeout, root, ip: slot.write(ip), listen }
+        }
+
+        fn listen(self, listen: SocketAddr) -> ServerBuilder<T, R, I, Set> {
+            let Self { timeout, root, ip, listen: slot } = self;
+            ServerBuilder { timeout, root, ip, listen: slot.write(listen) }
+        }
+
+        duration_preset!(timeout: timeout_secs, timeout_ms -> ServerBuilder<Set, R, I, L>);
+        path_preset!(root: root_path -> ServerBuilder<T, Set, I, L>);
+        addr_preset!(ip: try_ip -> ServerBuilder<T, R, Set, L>);
+        addr_preset!(listen: try_listen -> ServerBuilder<T, R, I, Set>);
+    }
+
+    impl ServerBuilder<Set, Set, Set, Set> {
+        fn construct(self) -> Server {
+            Server {
+                timeout: self.timeout.into_inner(),
+                root: self.root.into_inner(),
+                ip: self.ip.into_inner(),
+                listen: self.listen.into_inner(),
+            }
+        }
+    }
+
+    fn local() -> ServerBuilder<Unset, Unset, Set, Set> {
+        ServerBuilder::new().try_ip("127.0.0.1").unwrap().try_listen("0.0.0.0:8080").unwrap()
+    }
+
+    #[test]
+    fn duration() {
+        let server = local().root_path("/").timeout_secs(30).construct();
+        assert_eq!(server.timeout, Duration::from_secs(30));
+
+        // the last setter wins, whether it is a preset or not
+        let builder = local().root_path("/").timeout_secs(30);
+        assert_eq!(builder.timeout_ms(500).construct().timeout, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn path() {
+        let builder = || local().timeout(Duration::from_secs(1));
+        let expected = PathBuf::from("/srv/www");
+        assert_eq!(builder().root_path("/srv/www").construct().root, expected);
+        assert_eq!(builder().root_path(String::from("/srv/www")).construct().root, expected);
+        assert_eq!(builder().root_path(Path::new("/srv/www")).construct().root, expected);
+    }
+
+    #[test]
+    fn addr() {
+        let server = local().timeout_ms(1).root_path("/").construct();
+        assert_eq!(
+            server,
+            Server {
+                timeout: Duration::from_millis(1),
+                root: "/".into(),
+                ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                listen: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            },
+        );
+    }
+
+    #[test]
+    fn addr_errors() {
+        let err = ServerBuilder::new().try_ip("localhost").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        assert_eq!(err.fields(), ["ip"]);
+
+        let err = ServerBuilder::new().try_listen("0.0.0.0").err().unwrap();
+        assert_eq!(
+            err,
+            BuildError::Invalid {
+                field: "listen",
+                reason: "can't parse \"0.0.0.0\": invalid socket address syntax".into(),
+            },
+        );
+        assert_eq!(
+            err.to_string(),
+            "invalid `listen`: can't parse \"0.0.0.0\": invalid socket address syntax",
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Constructing straight into a `Vec`. `items.push(builder.construct())` moves every field twice,
+//! once into the `Item` on the stack and once more into the vector. `construct_push` moves the
+//! fields out of their slots and right into the vector's spare capacity instead.
+
+#[cfg(not(feature = "forbid-unsafe"))]
+use std::ptr;
+
+use crate::{Item, ItemBuilder, Set};
+
+impl ItemBuilder<Set, Set> {
+    /// Construct the item as the new last element of `items`.
+    #[cfg(not(feature = "forbid-unsafe"))]
+    pub fn construct_push(self, items: &mut Vec<Item>) {
+        items.reserve(1);
+        let item = items.spare_capacity_mut()[0].as_mut_ptr();
+
+        // the pointer is valid for writes thanks to `reserve`, the fields are written without
+        // ever reading or dropping the uninitialized memory
+        unsafe {
+            ptr::addr_of_mut!((*item).a).write(self.a.into_inner());
+            ptr::addr_of_mut!((*item).b).write(self.b.into_inner());
+        }
+
+        // the length only covers the new element once all of its fields are written. a panic
+        // while writing (there is none for plain moves, but there could be with validation or
+        // hooks in between) leaks the fields written so far, the vector never sees a half-written
+        // item.
+        unsafe { items.set_len(items.len() + 1); }
+    }
+
+    /// Without unsafe code the item is constructed on the stack and pushed.
+    #[cfg(feature = "forbid-unsafe")]
+    pub fn construct_push(self, items: &mut Vec<Item>) {
+        items.push(self.construct());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends() {
+        let mut items = vec![Item { a: "first".into(), b: vec![] }];
+        ItemBuilder::new().a("second".into()).b(vec![1]).construct_push(&mut items);
+        ItemBuilder::new().a("third".into()).b(vec![2]).construct_push(&mut items);
+
+        let names: Vec<_> = items.iter().map(|item| item.a.as_str()).collect();
+        assert_eq!(names, ["first", "second", "third"]);
+        assert_eq!(items[2].b, vec![2]);
+    }
+
+    #[test]
+    fn same_as_push() {
+        let mut pushed = Vec::new();
+        let mut constructed = Vec::new();
+        for i in 0..32 {
+            pushed.push(ItemBuilder::new().a(i.to_string()).b(vec![i]).construct());
+            ItemBuilder::new().a(i.to_string()).b(vec![i]).construct_push(&mut constructed);
+        }
+        assert_eq!(pushed, constructed);
+    }
+}
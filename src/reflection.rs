@@ -0,0 +1,245 @@
+//! A table describing the fields of a builder, for tooling that wants to know about them without
+//! parsing the source: code generators, schema emitters, admin UIs. The runtime checks are built
+//! on the same table, so it can't go out of date without them going wrong too.
+//!
+//! The position of a field in its table is also its bit in the `state_bits` of the builder, so
+//! `ItemBuilder::FIELDS[1]` is bit 1. The typestate builders compute the bits at compile-time,
+//! the runtime builders from what they hold, and both agree.
+//!
+//! Derived builders have the same table, so whatever works on the fields of `ItemBuilder` works
+//! on theirs. `FieldInfo` and `state_bits` don't need `std`, neither do the tables of derived
+//! builders.
+
+#[cfg(feature = "std")]
+use crate::guard::ExportBuilder;
+#[cfg(feature = "std")]
+use crate::{FieldState, ItemBuilder, Unset};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    /// The type as written in the source.
+    pub type_name: &'static str,
+    /// Whether `construct()` needs the field to be set.
+    pub required: bool,
+    /// Whether there is a builder-level default, see `finish_with_defaults`.
+    pub has_default: bool,
+    /// Whether the value must not show up in logs or diffs.
+    pub sensitive: bool,
+}
+
+// only the tables of the hand-written builders need these, the derive spells out every field
+#[cfg(feature = "std")]
+impl FieldInfo {
+    const fn new(name: &'static str, type_name: &'static str) -> Self {
+        FieldInfo { name, type_name, required: true, has_default: false, sensitive: false }
+    }
+
+    const fn with_default(self) -> Self {
+        FieldInfo { has_default: true, ..self }
+    }
+
+    const fn sensitive(self) -> Self {
+        FieldInfo { sensitive: true, ..self }
+    }
+}
+
+/// The names of the fields that aren't set, `states` holds whether each field of `fields` is set.
+#[cfg(feature = "std")]
+pub fn missing_fields(fields: &[FieldInfo], states: &[bool]) -> Vec<&'static str> {
+    debug_assert_eq!(fields.len(), states.len());
+    fields
+        .iter()
+        .zip(states)
+        .filter(|(field, set)| field.required && !**set)
+        .map(|(field, _)| field.name)
+        .collect()
+}
+
+/// The bitmask of `states`, bit `i` is set if field `i` is.
+pub const fn state_bits(states: &[bool]) -> u64 {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < states.len() {
+        bits |= (states[i] as u64) << i;
+        i += 1;
+    }
+    bits
+}
+
+/// Whether `fields` are named `names`, in the same order. Derived builders migrating from an
+/// older builder check the fields they were told about against its table with this.
+pub const fn names_are(fields: &[FieldInfo], names: &[&str]) -> bool {
+    if fields.len() != names.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < fields.len() {
+        let (field, name) = (fields[i].name.as_bytes(), names[i].as_bytes());
+        if field.len() != name.len() {
+            return false;
+        }
+        let mut j = 0;
+        while j < field.len() {
+            if field[j] != name[j] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(feature = "std")]
+impl ItemBuilder<Unset, Unset> {
+    pub const FIELDS: &'static [FieldInfo] = &[
+        FieldInfo::new("a", "String").with_default(),
+        FieldInfo::new("b", "Vec<i32>").with_default(),
+    ];
+}
+
+/// The names of the fields of `ItemBuilder` that are set and of those that are missing, indexed by
+/// the state bits. A generic `const` can't filter a slice into a shorter one, so every state is
+/// spelled out, the tests check them against `FIELDS`.
+#[cfg(feature = "std")]
+const ITEM_SET: [&[&str]; 4] = [&[], &["a"], &["b"], &["a", "b"]];
+#[cfg(feature = "std")]
+const ITEM_MISSING: [&[&str]; 4] = [&["a", "b"], &["b"], &["a"], &[]];
+
+#[cfg(feature = "std")]
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Whether each field of [FIELDS](#associatedconstant.FIELDS) is set, in the same order.
+    pub const STATES: [bool; 2] = [A::IS_SET, B::IS_SET];
+    /// [STATES](#associatedconstant.STATES) as a bitmask.
+    pub const STATE_BITS: u64 = state_bits(&Self::STATES);
+
+    /// The fields that have been set, in declaration order.
+    pub fn set_fields(&self) -> &'static [&'static str] {
+        ITEM_SET[Self::STATE_BITS as usize]
+    }
+
+    /// The fields that haven't been set, in declaration order, e.g. to report them to the user.
+    pub fn missing_fields(&self) -> &'static [&'static str] {
+        ITEM_MISSING[Self::STATE_BITS as usize]
+    }
+
+    /// Whether every field is set, that is whether `construct()` is available.
+    pub fn is_complete(&self) -> bool {
+        self.missing_fields().is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ExportBuilder<Unset, Unset, Unset> {
+    pub const FIELDS: &'static [FieldInfo] = &[
+        FieldInfo::new("path", "String"),
+        FieldInfo::new("compression", "u8"),
+        FieldInfo::new("key", "String").sensitive(),
+    ];
+}
+
+#[cfg(feature = "std")]
+impl<P: FieldState, C: FieldState, K: FieldState> ExportBuilder<P, C, K> {
+    /// Whether each field of [FIELDS](#associatedconstant.FIELDS) is set, in the same order.
+    pub const STATES: [bool; 3] = [P::IS_SET, C::IS_SET, K::IS_SET];
+    /// [STATES](#associatedconstant.STATES) as a bitmask.
+    pub const STATE_BITS: u64 = state_bits(&Self::STATES);
+
+    /// The fields that have been set as a bitmask, bit 0 is `path`, 1 `compression` and 2 `key`.
+    pub fn state_bits(&self) -> u64 {
+        Self::STATE_BITS
+    }
+}
+
+// the policies every builder has to follow, one state per field and one bit per field
+#[cfg(feature = "std")]
+const _: () = {
+    const_check!(ItemBuilder::FIELDS, crate::policy::secrets_are_sensitive);
+    const_check!(ExportBuilder::FIELDS, crate::policy::secrets_are_sensitive);
+
+    assert!(ItemBuilder::FIELDS.len() == ItemBuilder::<Unset, Unset>::STATES.len());
+    assert!(ExportBuilder::FIELDS.len() == ExportBuilder::<Unset, Unset, Unset>::STATES.len());
+    assert!(ItemBuilder::FIELDS.len() <= 64 && ExportBuilder::FIELDS.len() <= 64);
+};
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::partial::ItemPartial;
+    use crate::Set;
+
+    #[test]
+    fn item_fields() {
+        let names: Vec<_> = ItemBuilder::FIELDS.iter().map(|field| field.name).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(ItemBuilder::FIELDS[1].type_name, "Vec<i32>");
+        assert!(ItemBuilder::FIELDS.iter().all(|field| field.required && field.has_default));
+        assert!(ItemBuilder::FIELDS.iter().all(|field| !field.sensitive));
+    }
+
+    #[test]
+    fn export_fields() {
+        let sensitive: Vec<_> = ExportBuilder::FIELDS
+            .iter()
+            .filter(|field| field.sensitive)
+            .map(|field| field.name)
+            .collect();
+        assert_eq!(sensitive, ["key"]);
+        assert!(ExportBuilder::FIELDS.iter().all(|field| !field.has_default));
+    }
+
+    #[test]
+    fn bits() {
+        assert_eq!(ItemBuilder::<Unset, Unset>::STATE_BITS, 0b00);
+        assert_eq!(ItemBuilder::<Set, Unset>::STATE_BITS, 0b01);
+        assert_eq!(ItemBuilder::<Unset, Set>::STATE_BITS, 0b10);
+        assert_eq!(ItemBuilder::<Set, Set>::STATE_BITS, 0b11);
+        assert_eq!(ExportBuilder::<Unset, Set, Unset>::STATE_BITS, 0b010);
+        assert_eq!(ExportBuilder::<Set, Set, Set>::STATE_BITS, 0b111);
+        assert_eq!(ExportBuilder::new().path("out".into()).state_bits(), 0b001);
+    }
+
+    #[test]
+    fn bits_agree_with_partials() {
+        let builders = [
+            ItemBuilder::new().into_partial(),
+            ItemBuilder::new().a("a".into()).into_partial(),
+            ItemBuilder::new().b(vec![]).into_partial(),
+            ItemBuilder::new().a("a".into()).b(vec![]).into_partial(),
+        ];
+        let bits: Vec<_> = builders.iter().map(ItemPartial::state_bits).collect();
+        assert_eq!(bits, [0b00, 0b01, 0b10, 0b11]);
+
+        // e.g. checking that everything the v1 wire format needs is there
+        let v1 = ItemBuilder::<Set, Set>::STATE_BITS;
+        assert_eq!(ItemBuilder::new().a("a".into()).b(vec![]).state_bits() & v1, v1);
+        assert_ne!(ItemBuilder::new().b(vec![]).state_bits() & v1, v1);
+    }
+
+    #[test]
+    fn introspection() {
+        fn check<A: FieldState, B: FieldState>(builder: ItemBuilder<A, B>) {
+            let states = ItemBuilder::<A, B>::STATES;
+            assert_eq!(builder.missing_fields(), missing_fields(ItemBuilder::FIELDS, &states));
+            let negated = states.map(|set| !set);
+            assert_eq!(builder.set_fields(), missing_fields(ItemBuilder::FIELDS, &negated));
+            assert_eq!(builder.is_complete(), states.iter().all(|&set| set));
+        }
+
+        check(ItemBuilder::new());
+        check(ItemBuilder::new().a("a".into()));
+        check(ItemBuilder::new().b(vec![]));
+        check(ItemBuilder::new().a("a".into()).b(vec![]));
+
+        let builder = ItemBuilder::new().b(vec![]);
+        assert_eq!((builder.set_fields(), builder.missing_fields()), (&["b"][..], &["a"][..]));
+    }
+
+    #[test]
+    fn missing() {
+        let states = ItemBuilder::<Unset, Set>::STATES;
+        assert_eq!(missing_fields(ItemBuilder::FIELDS, &states), ["a"]);
+        assert!(missing_fields(ItemBuilder::FIELDS, &[true, true]).is_empty());
+    }
+}
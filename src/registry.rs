@@ -0,0 +1,78 @@
+//! A process-wide list of the builder types, for support tooling that wants to dump every builder
+//! and its fields from a running process. Nothing registers itself, [init](fn.init.html) adds the
+//! builders of this crate and has to be called once at startup; builders defined elsewhere can be
+//! added with [register](fn.register.html). Derived builders come with a descriptor of their own,
+//! a static named after the builder like `SERVER_BUILDER`. Registering changes nothing about the
+//! builders, the descriptors only point at their [FIELDS](../reflection/struct.FieldInfo.html)
+//! tables.
+
+use std::sync::{Mutex, PoisonError};
+
+use crate::guard::ExportBuilder;
+use crate::reflection::FieldInfo;
+use crate::ItemBuilder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuilderDescriptor {
+    /// The name of the builder type.
+    pub name: &'static str,
+    pub fields: &'static [FieldInfo],
+}
+
+impl BuilderDescriptor {
+    /// Whether any field falls back to a default when it isn't set.
+    pub fn has_defaults(&self) -> bool {
+        self.fields.iter().any(|field| field.has_default)
+    }
+}
+
+pub static ITEM_BUILDER: BuilderDescriptor =
+    BuilderDescriptor { name: "ItemBuilder", fields: ItemBuilder::FIELDS };
+pub static EXPORT_BUILDER: BuilderDescriptor =
+    BuilderDescriptor { name: "ExportBuilder", fields: ExportBuilder::FIELDS };
+
+static REGISTRY: Mutex<Vec<&'static BuilderDescriptor>> = Mutex::new(Vec::new());
+
+/// Add a builder, registering the same descriptor again does nothing.
+pub fn register(descriptor: &'static BuilderDescriptor) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    if !registry.iter().any(|registered| std::ptr::eq(*registered, descriptor)) {
+        registry.push(descriptor);
+    }
+}
+
+/// Register the builders of this crate.
+pub fn init() {
+    register(&ITEM_BUILDER);
+    register(&EXPORT_BUILDER);
+}
+
+/// The registered builders, in the order they were registered. Builders registered while
+/// iterating don't show up.
+pub fn iter() -> impl Iterator<Item = &'static BuilderDescriptor> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner).clone().into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a single test, the registry is shared between the test threads
+    #[test]
+    fn discover_builders() {
+        static OTHER: BuilderDescriptor = BuilderDescriptor { name: "OtherBuilder", fields: &[] };
+
+        init();
+        init();
+        register(&OTHER);
+
+        let names: Vec<_> = iter().map(|descriptor| descriptor.name).collect();
+        assert_eq!(names, ["ItemBuilder", "ExportBuilder", "OtherBuilder"]);
+
+        let item = iter().find(|descriptor| descriptor.name == "ItemBuilder").unwrap();
+        assert_eq!(item.fields.len(), 2);
+        assert!(item.has_defaults());
+        assert!(!EXPORT_BUILDER.has_defaults());
+        assert!(EXPORT_BUILDER.fields[2].sensitive);
+    }
+}
@@ -0,0 +1,161 @@
+//! A builder with a map field, showing how mutable access to a field fits into the typestate.
+//! Once `headers` is `Set` the builder hands out the map's `Entry` borrowed straight from its
+//! storage. While it is `Unset` there is no map to borrow from, so the entry has to be used inside
+//! a closure that runs after the builder created an empty map and moved to the `Set` state.
+//!
+//! `sent_at` is never set by hand, it is taken from the builder's clock in `construct()`. Use
+//! [with_clock](struct.RequestBuilder.html#method.with_clock) to make it deterministic.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use crate::clock::{Clock, SystemClock};
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug)]
+pub struct Request {
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub sent_at: SystemTime,
+}
+
+/// The clock is a type-parameter as well, that way the default clock takes up no space at all.
+#[must_use = "builder must be used to construct a value"]
+pub struct RequestBuilder<U: FieldState, H: FieldState, C: Clock = SystemClock> {
+    url: Slot<String, U>,
+    headers: Slot<HashMap<String, String>, H>,
+    clock: C,
+}
+
+impl RequestBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            url: Slot::empty(),
+            headers: Slot::empty(),
+            clock: SystemClock,
+        }
+    }
+}
+
+impl Default for RequestBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<U: FieldState, H: FieldState, C: Clock> RequestBuilder<U, H, C> {
+    pub fn url(self, url: String) -> RequestBuilder<Set, H, C> {
+        RequestBuilder {
+            url: self.url.write(url),
+            headers: self.headers,
+            clock: self.clock,
+        }
+    }
+
+    pub fn headers(self, headers: HashMap<String, String>) -> RequestBuilder<U, Set, C> {
+        RequestBuilder {
+            url: self.url,
+            headers: self.headers.write(headers),
+            clock: self.clock,
+        }
+    }
+
+    /// Replace the clock `sent_at` is taken from.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> RequestBuilder<U, H, C2> {
+        RequestBuilder {
+            url: self.url,
+            headers: self.headers,
+            clock,
+        }
+    }
+}
+
+impl<U: FieldState, C: Clock> RequestBuilder<U, Unset, C> {
+    /// Create the header map and pass the entry for `key` to `f`. Afterwards the headers are set,
+    /// further entries can be borrowed with [header_entry](#method.header_entry).
+    pub fn with_header_entry<F>(self, key: &str, f: F) -> RequestBuilder<U, Set, C>
+    where
+        F: FnOnce(Entry<'_, String, String>),
+    {
+        let mut builder = self.headers(HashMap::new());
+        f(builder.header_entry(key));
+        builder
+    }
+}
+
+impl<U: FieldState, C: Clock> RequestBuilder<U, Set, C> {
+    /// Borrow the entry for `key` from the header map.
+    pub fn header_entry(&mut self, key: &str) -> Entry<'_, String, String> {
+        self.headers.get_mut().entry(key.into())
+    }
+}
+
+impl<C: Clock> RequestBuilder<Set, Set, C> {
+    pub fn construct(self) -> Request {
+        Request {
+            url: self.url.into_inner(),
+            headers: self.headers.into_inner(),
+            sent_at: self.clock.now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{size_of, size_of_val};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn vacant_and_occupied() {
+        let mut builder = RequestBuilder::new().headers(HashMap::new());
+
+        let value = builder.header_entry("accept").or_insert_with(|| "text/html".into());
+        assert_eq!(value, "text/html");
+        let value = builder.header_entry("accept").or_insert_with(|| "ignored".into());
+        assert_eq!(value, "text/html");
+        builder.header_entry("accept").and_modify(|value| value.push_str(", */*"));
+
+        let request = builder.url("/".into()).construct();
+        assert_eq!(request.headers["accept"], "text/html, */*");
+    }
+
+    #[test]
+    fn first_use_initializes() {
+        let builder = RequestBuilder::new().with_header_entry("host", |entry| {
+            entry.or_insert_with(|| "example.com".into());
+        });
+
+        // the headers are `Set` now, otherwise this wouldn't compile
+        let mut builder = builder.url("/".into());
+        builder.header_entry("host").or_insert_with(|| "ignored".into());
+
+        let request = builder.construct();
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(request.headers["host"], "example.com");
+    }
+
+    #[test]
+    fn injected_clock() {
+        let fixed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let request = RequestBuilder::new()
+            .url("/".into())
+            .with_clock(move || fixed)
+            .headers(HashMap::new())
+            .construct();
+        assert_eq!(request.sent_at, fixed);
+    }
+
+    #[test]
+    fn clock_sizes() {
+        let without = size_of::<RequestBuilder<Unset, Unset>>();
+        assert_eq!(without, size_of::<String>() + size_of::<HashMap<String, String>>());
+
+        let fixed = SystemTime::UNIX_EPOCH;
+        let with = RequestBuilder::new().with_clock(move || fixed);
+        assert_eq!(size_of_val(&with), without + size_of::<SystemTime>());
+    }
+}
@@ -0,0 +1,179 @@
+//! The slots without unsafe code, used instead of [slot](../slot/index.html) with the
+//! `forbid-unsafe` feature. A slot holds an `Option<T>` that is `Some` exactly when the state is
+//! `Set`, so the compiler tracks initialization and drops the value instead of the slot doing it
+//! by hand. The typestate API is the same, builders built on slots don't notice the difference.
+//!
+//! The price is a discriminant for field types without a niche and a branch where the unsafe
+//! slots trust the state, one that can never be taken. The raw escape hatch, `raw_mut` and
+//! `assume_set`, only exists on the unsafe slots, and there is no poisoning either: an unset slot
+//! holds `None`, not uninitialized memory.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::{FieldState, Set, Unset};
+
+/// Storage for one field. A `Slot<T, Set>` always holds `Some(T)`, a `Slot<T, Unset>` holds
+/// `None`.
+///
+/// A slot is `Send`, `Sync` and `Unpin` exactly when `T` is, and covariant in `T`, whatever its
+/// state, like the unsafe slots.
+#[repr(transparent)]
+pub struct Slot<T, S: FieldState> {
+    value: Option<T>,
+    _state: PhantomData<fn() -> S>,
+}
+
+// the guarantees above, the negative side is `tests/compile_fail/safe_slot/not_send.rs`
+const _: fn() = || {
+    fn auto_traits<T: Send + Sync + Unpin>() {}
+    fn slot<T: Send + Sync + Unpin, S: FieldState>() {
+        auto_traits::<Slot<T, S>>();
+    }
+    fn covariant<'a, T, S: FieldState>(slot: Slot<&'static T, S>) -> Slot<&'a T, S> {
+        slot
+    }
+    let _ = (slot::<(), Set>, covariant::<(), Unset>);
+};
+
+/// Whether `A` and `B` have the same size and alignment, for the layout assertions.
+pub(crate) const fn same_layout<A, B>() -> bool {
+    mem::size_of::<A>() == mem::size_of::<B>() && mem::align_of::<A>() == mem::align_of::<B>()
+}
+
+// the states still don't change the layout, only `Option` might
+const _: () = {
+    assert!(same_layout::<Slot<u8, Set>, Slot<u8, Unset>>());
+    assert!(same_layout::<Slot<&str, Set>, &str>());
+};
+
+impl<T> Slot<T, Unset> {
+    /// An empty slot.
+    pub const fn empty() -> Self {
+        Slot {
+            value: None,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Slot<T, Unset> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T> Slot<T, Set> {
+    /// A slot holding `value`.
+    pub const fn new(value: T) -> Self {
+        Slot {
+            value: Some(value),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        match &self.value {
+            Some(value) => value,
+            None => unreachable!("a set slot holds a value"),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        match &mut self.value {
+            Some(value) => value,
+            None => unreachable!("a set slot holds a value"),
+        }
+    }
+
+    /// Move the value out, consuming the slot.
+    pub fn into_inner(self) -> T {
+        match self.value {
+            Some(value) => value,
+            None => unreachable!("a set slot holds a value"),
+        }
+    }
+
+    /// Move the value out and leave an empty slot behind.
+    pub fn take(self) -> (T, Slot<T, Unset>) {
+        (self.into_inner(), Slot::empty())
+    }
+}
+
+impl<T, S: FieldState> Slot<T, S> {
+    /// Put `value` into the slot, dropping the previous value if there is one. The new value is
+    /// in its slot before the old one is dropped, so a panicking destructor unwinds through a
+    /// slot that owns it and it is dropped as well.
+    pub fn write(self, value: T) -> Slot<T, Set> {
+        let written = Slot::new(value);
+        drop(self);
+        written
+    }
+
+    /// Drop the value if there is one.
+    pub fn clear(self) -> Slot<T, Unset> {
+        drop(self);
+        Slot::empty()
+    }
+
+    /// Borrow the value if there is one.
+    pub fn as_option(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Move the value out if there is one.
+    pub fn into_option(self) -> Option<T> {
+        self.value
+    }
+
+    /// The reverse of `into_option`. An unset slot drops the value it is given, a set slot needs
+    /// one and panics on `None`.
+    pub fn from_option(value: Option<T>) -> Self {
+        match value {
+            Some(value) if S::IS_SET => Slot {
+                value: Some(value),
+                _state: PhantomData,
+            },
+            None if S::IS_SET => panic!("a set slot needs a value"),
+            _ => Slot {
+                value: None,
+                _state: PhantomData,
+            },
+        }
+    }
+}
+
+/// Clones the value if there is one.
+impl<T: Clone, S: FieldState> Clone for Slot<T, S> {
+    fn clone(&self) -> Self {
+        Slot::from_option(self.as_option().cloned())
+    }
+}
+
+/// Shows the value, or `<unset>` if there is none.
+impl<T: fmt::Debug, S: FieldState> fmt::Debug for Slot<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_option() {
+            Some(value) => value.fmt(f),
+            None => f.write_str("<unset>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_api() {
+        let (value, empty) = Slot::new(String::from("a")).take();
+        assert_eq!((value.as_str(), empty.as_option()), ("a", None));
+
+        let mut slot = empty.write("b".into());
+        slot.get_mut().push('c');
+        assert_eq!(format!("{:?}", slot.clone()), "\"bc\"");
+        assert_eq!(slot.clear().into_option(), None);
+        assert_eq!(Slot::<u8, Set>::from_option(Some(1)).into_inner(), 1);
+    }
+}
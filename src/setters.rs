@@ -0,0 +1,73 @@
+//! The setters as traits, one per field. Adapter layers forwarding values to a builder can be
+//! written once against these instead of against every state of every builder:
+//!
+//! ```ignore
+//! fn set_field<Bld: SetA<V>, V>(builder: Bld, value: V) -> Bld::Out {
+//!     builder.set_a(value)
+//! }
+//! ```
+//!
+//! Every state implements the traits for every value that converts `Into` the field's type,
+//! which includes the field's type itself.
+//!
+//! Derived builders get the same traits with `#[builder(setter_traits)]`, named after the builder
+//! and the setter: `ServerBuilderSetHost<V>` with `set_host`.
+
+use crate::{FieldState, ItemBuilder, Set};
+
+/// Set the field `a`.
+pub trait SetA<V> {
+    type Out;
+
+    fn set_a(self, a: V) -> Self::Out;
+}
+
+/// Set the field `b`.
+pub trait SetB<V> {
+    type Out;
+
+    fn set_b(self, b: V) -> Self::Out;
+}
+
+impl<A: FieldState, B: FieldState, V: Into<String>> SetA<V> for ItemBuilder<A, B> {
+    type Out = ItemBuilder<Set, B>;
+
+    fn set_a(self, a: V) -> Self::Out {
+        self.a(a.into())
+    }
+}
+
+impl<A: FieldState, B: FieldState, V: Into<Vec<i32>>> SetB<V> for ItemBuilder<A, B> {
+    type Out = ItemBuilder<A, Set>;
+
+    fn set_b(self, b: V) -> Self::Out {
+        self.b(b.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An adapter that knows nothing about `ItemBuilder` or its states.
+    fn forward<Bld, VA, VB>(builder: Bld, a: VA, b: VB) -> <Bld::Out as SetB<VB>>::Out
+    where
+        Bld: SetA<VA>,
+        Bld::Out: SetB<VB>,
+    {
+        builder.set_a(a).set_b(b)
+    }
+
+    #[test]
+    fn generic_forwarding() {
+        let item = forward(ItemBuilder::new(), "converted", vec![1, 2]).construct();
+        assert_eq!(item.a, "converted");
+        assert_eq!(item.b, vec![1, 2]);
+
+        // also works on builders that already have values, the forwarded ones replace them
+        let builder = ItemBuilder::new().a("old".into()).b(vec![]);
+        let item = forward(builder, String::from("exact"), [3]).construct();
+        assert_eq!(item.a, "exact");
+        assert_eq!(item.b, vec![3]);
+    }
+}
@@ -0,0 +1,90 @@
+//! A builder with many owners, e.g. several threads or tasks that each contribute some of the
+//! fields. The typestate can't help here: no single owner knows which fields the others have set
+//! already, so the fields are tracked at runtime in an `ItemPartial` behind a lock and checked
+//! when the item is constructed.
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::error::BuildError;
+use crate::partial::ItemPartial;
+use crate::Item;
+
+/// A handle to a builder shared between owners. Cloning the handle does not copy the builder,
+/// the clone sets fields on the same builder as the original.
+#[derive(Debug, Default, Clone)]
+pub struct SharedBuilder {
+    partial: Arc<RwLock<ItemPartial>>,
+}
+
+impl SharedBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `a`, replacing whatever another owner might have set before.
+    pub fn a(&self, a: String) -> &Self {
+        self.partial.write().unwrap_or_else(PoisonError::into_inner).a = Some(a);
+        self
+    }
+
+    /// Set `b`, replacing whatever another owner might have set before.
+    pub fn b(&self, b: Vec<i32>) -> &Self {
+        self.partial.write().unwrap_or_else(PoisonError::into_inner).b = Some(b);
+        self
+    }
+
+    /// A copy of the fields that have been set so far.
+    pub fn snapshot(&self) -> ItemPartial {
+        self.partial.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Construct the item from the fields set so far, or report the ones that are missing. The
+    /// builder is left as it is, other owners can still change fields and construct again.
+    pub fn construct(&self) -> Result<Item, BuildError> {
+        self.snapshot().try_build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn fields_from_several_threads() {
+        let builder = SharedBuilder::new();
+
+        let a = {
+            let builder = builder.clone();
+            thread::spawn(move || {
+                builder.a("from a thread".into());
+            })
+        };
+        let b = {
+            let builder = builder.clone();
+            thread::spawn(move || {
+                builder.b(vec![1, 2]);
+            })
+        };
+        a.join().unwrap();
+        b.join().unwrap();
+
+        let item = builder.construct().unwrap();
+        assert_eq!(item.a, "from a thread");
+        assert_eq!(item.b, vec![1, 2]);
+    }
+
+    #[test]
+    fn clones_share_the_builder() {
+        let builder = SharedBuilder::new();
+        let other = builder.clone();
+
+        other.a("shared".into());
+        assert_eq!(builder.snapshot().a.as_deref(), Some("shared"));
+        assert_eq!(builder.construct(), Err(BuildError::MissingFields(vec!["b"])));
+
+        builder.b(vec![]);
+        assert_eq!(other.construct().unwrap().a, "shared");
+    }
+}
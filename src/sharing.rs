@@ -0,0 +1,121 @@
+//! Fields shared between builders instead of being cloned into each of them. Thousands of
+//! builders that differ in a few small fields but carry the same large lookup table would
+//! otherwise hold thousands of copies of it. The table of a `LookupBuilder` is stored as an
+//! `Arc`, the setter takes either the table itself or an `Arc` already shared with other
+//! builders.
+//!
+//! `construct` moves the table out of the `Arc` if this builder was the last one holding it, and
+//! clones it otherwise. `construct_shared` keeps sharing it with a target that holds an `Arc`
+//! itself. The typestate is the same as for every other field, only the storage differs.
+//!
+//! Derived builders store a field like this with `#[builder(shared)]`.
+
+use std::sync::Arc;
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    pub name: String,
+    pub table: Vec<i32>,
+}
+
+/// A `Lookup` that still shares its table with whatever else holds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedLookup {
+    pub name: String,
+    pub table: Arc<Vec<i32>>,
+}
+
+#[must_use = "builder must be used to construct a value"]
+pub struct LookupBuilder<N: FieldState, T: FieldState> {
+    name: Slot<String, N>,
+    table: Slot<Arc<Vec<i32>>, T>,
+}
+
+impl LookupBuilder<Unset, Unset> {
+    pub fn new() -> Self {
+        Self {
+            name: Slot::empty(),
+            table: Slot::empty(),
+        }
+    }
+}
+
+impl Default for LookupBuilder<Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: FieldState, T: FieldState> LookupBuilder<N, T> {
+    pub fn name(self, name: String) -> LookupBuilder<Set, T> {
+        LookupBuilder {
+            name: self.name.write(name),
+            table: self.table,
+        }
+    }
+
+    /// Takes a `Vec<i32>`, which is wrapped, or an `Arc<Vec<i32>>` shared with other builders.
+    pub fn table<V: Into<Arc<Vec<i32>>>>(self, table: V) -> LookupBuilder<N, Set> {
+        LookupBuilder {
+            name: self.name,
+            table: self.table.write(table.into()),
+        }
+    }
+}
+
+impl LookupBuilder<Set, Set> {
+    /// Construct the lookup, cloning the table only if it is still shared.
+    pub fn construct(self) -> Lookup {
+        Lookup {
+            name: self.name.into_inner(),
+            table: Arc::unwrap_or_clone(self.table.into_inner()),
+        }
+    }
+
+    /// Construct the lookup without ever cloning the table.
+    pub fn construct_shared(self) -> SharedLookup {
+        SharedLookup {
+            name: self.name.into_inner(),
+            table: self.table.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_table_is_moved() {
+        let table = vec![1, 2, 3];
+        let ptr = table.as_ptr();
+        let lookup = LookupBuilder::new().name("unique".into()).table(table).construct();
+        assert_eq!(lookup.table.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn shared_table_is_cloned() {
+        let table = Arc::new(vec![1, 2, 3]);
+        let lookup = LookupBuilder::new().name("shared".into()).table(table.clone()).construct();
+        assert_eq!(lookup.table, *table);
+        assert_ne!(lookup.table.as_ptr(), table.as_ptr());
+        assert_eq!(Arc::strong_count(&table), 1);
+    }
+
+    #[test]
+    fn builders_share_one_table() {
+        let table = Arc::new((0..1000).collect::<Vec<_>>());
+        let builders: Vec<_> = (0..100)
+            .map(|i| LookupBuilder::new().name(i.to_string()).table(Arc::clone(&table)))
+            .collect();
+        assert_eq!(Arc::strong_count(&table), 101);
+
+        let lookups: Vec<_> = builders.into_iter().map(LookupBuilder::construct_shared).collect();
+        assert!(lookups.iter().all(|lookup| Arc::ptr_eq(&lookup.table, &table)));
+        drop(lookups);
+        assert_eq!(Arc::strong_count(&table), 1);
+    }
+}
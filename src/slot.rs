@@ -0,0 +1,254 @@
+//! The state machine underneath every builder: a slot that may or may not hold a value, with the
+//! state tracked by a type-parameter. Builders are plain structs of slots, so all of the unsafe
+//! code lives in here instead of being repeated for every field of every builder.
+//!
+//! In debug builds the storage of unset slots is filled with [POISON](constant.POISON.html) bytes,
+//! which sanitizers and debuggers show instead of stale data. For field types without padding,
+//! dropping or reading an unset slot also asserts that the pattern is still intact, so a write
+//! that didn't flip the state to `Set` (e.g. through [raw_mut](struct.Slot.html#method.raw_mut)
+//! without `assume_set`) fails loudly instead of leaking. Moving a slot may lose the bytes in the
+//! padding of `T`, so the pattern is only read for the types listed in `PADDING_FREE`. Release
+//! builds neither write nor check the pattern.
+//!
+//! A panic can't leave a slot claiming to be `Set` without a value. Slots change state by being
+//! moved into a slot of the other state, never in place, so whatever a setter is doing when it
+//! unwinds (converting its argument, validating it, dropping the previous value) every value is
+//! owned by exactly one slot or local and dropped exactly once. `tests/leaks.rs` injects panics
+//! at those points to check.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::{ptr, slice};
+
+use crate::{FieldState, Set, Unset};
+
+/// The byte unset slots are filled with in debug builds.
+pub const POISON: u8 = 0xAA;
+
+/// Field types that have no padding, whatever they are instantiated with. Every byte of their
+/// storage survives a move, so the poison pattern can be read back.
+const PADDING_FREE: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "&str",
+    "alloc::string::String", "alloc::vec::Vec<", "alloc::boxed::Box<", "alloc::rc::Rc<",
+    "alloc::sync::Arc<",
+];
+
+fn padding_free<T>() -> bool {
+    let name = core::any::type_name::<T>();
+    PADDING_FREE.iter().any(|free| name == *free || free.ends_with('<') && name.starts_with(free))
+}
+
+/// Storage for an unset slot, poisoned in debug builds.
+const fn poisoned<T>() -> MaybeUninit<T> {
+    let mut value = MaybeUninit::<T>::uninit();
+    if cfg!(debug_assertions) {
+        // only bytes are written, no `T` is created, so any pattern is fine for any type
+        unsafe { value.as_mut_ptr().cast::<u8>().write_bytes(POISON, mem::size_of::<T>()); }
+    }
+    value
+}
+
+/// Storage for one field. A `Slot<T, Set>` always holds an initialized `T`, a `Slot<T, Unset>`
+/// holds uninitialized memory. Dropping a slot drops the value only if there is one.
+///
+/// A slot is `Send`, `Sync` and `Unpin` exactly when `T` is, and covariant in `T`, whatever its
+/// state: the state is only ever returned by the `fn` in the marker, so it takes no part in
+/// either. Builders made of slots inherit this field by field.
+///
+/// The slot is `repr(transparent)`, so it has the size, alignment and ABI of `T` in either state.
+#[repr(transparent)]
+pub struct Slot<T, S: FieldState> {
+    value: MaybeUninit<T>,
+    _state: PhantomData<fn() -> S>,
+}
+
+// the guarantees above, for every `T` and state at once. the negative side, a slot of a type that
+// isn't `Send` not being `Send` either, is `tests/compile_fail/slot/not_send.rs`
+const _: fn() = || {
+    fn auto_traits<T: Send + Sync + Unpin>() {}
+    fn slot<T: Send + Sync + Unpin, S: FieldState>() {
+        auto_traits::<Slot<T, S>>();
+    }
+    fn covariant<'a, T, S: FieldState>(slot: Slot<&'static T, S>) -> Slot<&'a T, S> {
+        slot
+    }
+    let _ = (slot::<(), Set>, covariant::<(), Unset>);
+};
+
+/// Whether `A` and `B` have the same size and alignment, for the layout assertions.
+pub(crate) const fn same_layout<A, B>() -> bool {
+    mem::size_of::<A>() == mem::size_of::<B>() && mem::align_of::<A>() == mem::align_of::<B>()
+}
+
+const _: () = {
+    assert!(same_layout::<Slot<u8, Set>, u8>() && same_layout::<Slot<u8, Unset>, u8>());
+    assert!(same_layout::<Slot<u64, Unset>, u64>());
+    assert!(same_layout::<Slot<[u16; 3], Set>, [u16; 3]>());
+};
+
+impl<T> Slot<T, Unset> {
+    /// An empty slot.
+    pub const fn empty() -> Self {
+        Slot {
+            value: poisoned(),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Slot<T, Unset> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T> Slot<T, Set> {
+    /// A slot holding `value`.
+    pub const fn new(value: T) -> Self {
+        Slot {
+            value: MaybeUninit::new(value),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        // the type-information guarantees the value is initialized
+        unsafe { self.value.assume_init_ref() }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { self.value.assume_init_mut() }
+    }
+
+    /// Move the value out, consuming the slot.
+    pub fn into_inner(self) -> T {
+        // read the value and forget the slot, otherwise the destructor would drop the value we
+        // just moved out
+        let this = ManuallyDrop::new(self);
+        unsafe { this.value.assume_init_read() }
+    }
+
+    /// Move the value out and leave an empty slot behind.
+    pub fn take(self) -> (T, Slot<T, Unset>) {
+        (self.into_inner(), Slot::empty())
+    }
+}
+
+impl<T, S: FieldState> Slot<T, S> {
+    /// Put `value` into the slot, dropping the previous value if there is one. The new value is
+    /// in its slot before the old one is dropped, so a panicking destructor unwinds through a
+    /// slot that owns it and it is dropped as well.
+    pub fn write(self, value: T) -> Slot<T, Set> {
+        let written = Slot::new(value);
+        drop(self);
+        written
+    }
+
+    /// Drop the value if there is one.
+    pub fn clear(self) -> Slot<T, Unset> {
+        drop(self);
+        Slot::empty()
+    }
+
+    /// Borrow the value if there is one.
+    pub fn as_option(&self) -> Option<&T> {
+        if S::IS_SET {
+            Some(unsafe { self.value.assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Move the value out if there is one.
+    pub fn into_option(self) -> Option<T> {
+        self.check_poison();
+        let this = ManuallyDrop::new(self);
+        if S::IS_SET {
+            Some(unsafe { this.value.assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    /// The reverse of `into_option`. An unset slot drops the value it is given, a set slot needs
+    /// one and panics on `None`.
+    pub fn from_option(value: Option<T>) -> Self {
+        match value {
+            Some(value) if S::IS_SET => Slot {
+                value: MaybeUninit::new(value),
+                _state: PhantomData,
+            },
+            None if S::IS_SET => panic!("a set slot needs a value"),
+            _ => Slot {
+                value: poisoned(),
+                _state: PhantomData,
+            },
+        }
+    }
+
+    /// The raw storage, for code that needs to initialize a value in place.
+    ///
+    /// # Safety
+    ///
+    /// The storage has to be initialized when the state says `Set` at the time the slot is
+    /// dropped or read. Writing to an unset slot without [assume_set](#method.assume_set) leaks
+    /// the value, debug builds catch that when the slot is dropped.
+    pub unsafe fn raw_mut(&mut self) -> &mut MaybeUninit<T> {
+        &mut self.value
+    }
+
+    /// Change the state to `Set` without writing a value.
+    ///
+    /// # Safety
+    ///
+    /// The storage must have been initialized through [raw_mut](#method.raw_mut).
+    pub unsafe fn assume_set(self) -> Slot<T, Set> {
+        let this = ManuallyDrop::new(self);
+        Slot {
+            value: ptr::read(&this.value),
+            _state: PhantomData,
+        }
+    }
+
+    /// In debug builds, assert that an unset slot still holds the poison pattern.
+    fn check_poison(&self) {
+        if cfg!(debug_assertions) && !S::IS_SET && padding_free::<T>() {
+            // the bytes were initialized by `poisoned` and kept by every move since, anything
+            // written over them is the bug this is looking for
+            let size = mem::size_of::<T>();
+            let bytes = unsafe { slice::from_raw_parts(self.value.as_ptr().cast::<u8>(), size) };
+            assert!(
+                bytes.iter().all(|&byte| byte == POISON),
+                "an unset slot was written to without changing its state",
+            );
+        }
+    }
+}
+
+/// Clones the value if there is one.
+impl<T: Clone, S: FieldState> Clone for Slot<T, S> {
+    fn clone(&self) -> Self {
+        Slot::from_option(self.as_option().cloned())
+    }
+}
+
+/// Shows the value, or `<unset>` if there is none. The state decides which, the memory of an
+/// unset slot is never looked at.
+impl<T: fmt::Debug, S: FieldState> fmt::Debug for Slot<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_option() {
+            Some(value) => value.fmt(f),
+            None => f.write_str("<unset>"),
+        }
+    }
+}
+
+impl<T, S: FieldState> Drop for Slot<T, S> {
+    fn drop(&mut self) {
+        self.check_poison();
+        if S::IS_SET {
+            unsafe { self.value.assume_init_drop(); }
+        }
+    }
+}
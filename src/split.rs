@@ -0,0 +1,82 @@
+//! Producing the fields on different threads. `split()` takes a builder apart into one token per
+//! field, each token holds its field's slot and state and has the field's setter. The tokens are
+//! `Send`, so each can be filled wherever its value is computed, and
+//! [ItemBuilder::merge](../struct.ItemBuilder.html#method.merge) puts them back together:
+//!
+//! ```ignore
+//! let (a, b) = ItemBuilder::new().split();
+//! let a = thread::spawn(move || a.set(fetch_name()));
+//! let b = b.set(compute_values());
+//! let item = ItemBuilder::merge(a.join()?, b).construct();
+//! ```
+//!
+//! `merge` takes every token by value, a token that is dropped or kept elsewhere leaves nothing to
+//! merge with, and the merged builder has the states of the tokens, so `construct()` still only
+//! compiles once every token has been set.
+
+use crate::slot::Slot;
+use crate::{FieldState, ItemBuilder, Set};
+
+/// The field `a` of a split builder.
+#[must_use = "the token has to be merged back into the builder"]
+pub struct AToken<S: FieldState> {
+    a: Slot<String, S>,
+}
+
+/// The field `b` of a split builder.
+#[must_use = "the token has to be merged back into the builder"]
+pub struct BToken<S: FieldState> {
+    b: Slot<Vec<i32>, S>,
+}
+
+impl<S: FieldState> AToken<S> {
+    pub fn set(self, a: String) -> AToken<Set> {
+        AToken { a: self.a.write(a) }
+    }
+}
+
+impl<S: FieldState> BToken<S> {
+    pub fn set(self, b: Vec<i32>) -> BToken<Set> {
+        BToken { b: self.b.write(b) }
+    }
+}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Take the builder apart into a token per field, keeping what has been set.
+    pub fn split(self) -> (AToken<A>, BToken<B>) {
+        (AToken { a: self.a }, BToken { b: self.b })
+    }
+
+    /// Put the tokens of a split builder back together.
+    pub fn merge(a: AToken<A>, b: BToken<B>) -> Self {
+        ItemBuilder { a: a.a, b: b.b }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn fields_from_threads() {
+        let (a, b) = ItemBuilder::new().split();
+        let a = thread::spawn(move || a.set("a".into()));
+        let b = thread::spawn(move || b.set(vec![1]));
+        let builder = ItemBuilder::merge(a.join().unwrap(), b.join().unwrap());
+        assert_eq!(builder.construct(), Item { a: "a".into(), b: vec![1] });
+    }
+
+    #[test]
+    fn set_fields_stay_set() {
+        let (a, b) = ItemBuilder::new().a("kept".into()).split();
+        let builder = ItemBuilder::merge(a, b.set(vec![]));
+        assert_eq!(builder.construct().a, "kept");
+
+        // a token that wasn't set merges back unset
+        let (a, b) = ItemBuilder::new().split();
+        assert_eq!(ItemBuilder::merge(a.set("a".into()), b).state_bits(), 0b01);
+    }
+}
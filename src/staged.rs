@@ -0,0 +1,167 @@
+//! A builder whose fields have to be set in stages: the connection first, then TLS, then the
+//! handshake. The setters of a stage only exist while the stages before it are complete and the
+//! ones after it are untouched, so the order is checked by the same states that check
+//! completeness. Within a stage the fields can be set in any order and set again.
+//!
+//! ```ignore
+//! let session = SessionBuilder::new()
+//!     .port(443).host("example.com".into())
+//!     .server_name("example.com".into()).verify(true)
+//!     .protocols(vec!["h2".into()])
+//!     .construct();
+//! // SessionBuilder::new().host(h).server_name(n); // error: the port isn't set
+//! // SessionBuilder::new().host(h).port(443).server_name(n).host(h); // error: TLS has started
+//! ```
+
+use crate::slot::Slot;
+use crate::{FieldState, Set, Unset};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub host: String,
+    pub port: u16,
+    pub server_name: String,
+    pub verify: bool,
+    pub protocols: Vec<String>,
+}
+
+/// `H` and `P` are the connection stage, `N` and `V` the TLS stage and `A` the handshake stage.
+#[must_use = "builder must be used to construct a value"]
+pub struct SessionBuilder<H, P, N, V, A>
+where
+    H: FieldState,
+    P: FieldState,
+    N: FieldState,
+    V: FieldState,
+    A: FieldState,
+{
+    host: Slot<String, H>,
+    port: Slot<u16, P>,
+    server_name: Slot<String, N>,
+    verify: Slot<bool, V>,
+    protocols: Slot<Vec<String>, A>,
+}
+
+impl SessionBuilder<Unset, Unset, Unset, Unset, Unset> {
+    pub fn new() -> Self {
+        SessionBuilder {
+            host: Slot::empty(),
+            port: Slot::empty(),
+            server_name: Slot::empty(),
+            verify: Slot::empty(),
+            protocols: Slot::empty(),
+        }
+    }
+}
+
+impl Default for SessionBuilder<Unset, Unset, Unset, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The connection stage, until TLS is started.
+impl<H: FieldState, P: FieldState> SessionBuilder<H, P, Unset, Unset, Unset> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn host(self, host: String) -> SessionBuilder<Set, P, Unset, Unset, Unset> {
+        SessionBuilder {
+            host: self.host.write(host),
+            port: self.port,
+            server_name: self.server_name,
+            verify: self.verify,
+            protocols: self.protocols,
+        }
+    }
+
+    /// Same as [host](#method.host)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn port(self, port: u16) -> SessionBuilder<H, Set, Unset, Unset, Unset> {
+        SessionBuilder {
+            host: self.host,
+            port: self.port.write(port),
+            server_name: self.server_name,
+            verify: self.verify,
+            protocols: self.protocols,
+        }
+    }
+}
+
+/// The TLS stage, once the connection is complete and until the handshake is started.
+impl<N: FieldState, V: FieldState> SessionBuilder<Set, Set, N, V, Unset> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn server_name(self, server_name: String) -> SessionBuilder<Set, Set, Set, V, Unset> {
+        SessionBuilder {
+            host: self.host,
+            port: self.port,
+            server_name: self.server_name.write(server_name),
+            verify: self.verify,
+            protocols: self.protocols,
+        }
+    }
+
+    /// Same as [server_name](#method.server_name)
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn verify(self, verify: bool) -> SessionBuilder<Set, Set, N, Set, Unset> {
+        SessionBuilder {
+            host: self.host,
+            port: self.port,
+            server_name: self.server_name,
+            verify: self.verify.write(verify),
+            protocols: self.protocols,
+        }
+    }
+}
+
+/// The handshake stage, once TLS is complete.
+impl<A: FieldState> SessionBuilder<Set, Set, Set, Set, A> {
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn protocols(self, protocols: Vec<String>) -> SessionBuilder<Set, Set, Set, Set, Set> {
+        SessionBuilder {
+            host: self.host,
+            port: self.port,
+            server_name: self.server_name,
+            verify: self.verify,
+            protocols: self.protocols.write(protocols),
+        }
+    }
+}
+
+impl SessionBuilder<Set, Set, Set, Set, Set> {
+    pub fn construct(self) -> Session {
+        Session {
+            host: self.host.into_inner(),
+            port: self.port.into_inner(),
+            server_name: self.server_name.into_inner(),
+            verify: self.verify.into_inner(),
+            protocols: self.protocols.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_in_order() {
+        let session = SessionBuilder::new()
+            .port(443)
+            .host("example.com".into())
+            .verify(true)
+            .server_name("example.com".into())
+            .protocols(vec!["h2".into()])
+            .construct();
+        assert_eq!(session.port, 443);
+        assert_eq!(session.server_name, "example.com");
+        assert_eq!(session.protocols, ["h2"]);
+    }
+
+    #[test]
+    fn fields_can_be_set_again_within_their_stage() {
+        let builder = SessionBuilder::new().host("a".into()).port(1).host("b".into());
+        let builder = builder.server_name("a".into()).verify(false).server_name("b".into());
+        let session = builder.protocols(vec![]).protocols(vec!["http/1.1".into()]).construct();
+        assert_eq!((session.host.as_str(), session.server_name.as_str()), ("b", "b"));
+        assert_eq!(session.protocols, ["http/1.1"]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Builders as arguments and return values. The states are ordinary types, so they work in
+//! `impl Trait` position just like anywhere else:
+//!
+//! ```ignore
+//! // only accepts builders where `a` hasn't been set yet, so nothing gets overwritten
+//! fn configure(builder: ItemBuilder<impl IsUnset, Set>) -> CompleteItemBuilder {
+//!     builder.a("configured".into())
+//! }
+//! ```
+//!
+//! `IsUnset` narrows a state parameter down to `Unset` while keeping it generic, `IsSet` down to
+//! `Set`, and `FieldState` accepts both states. `ASet` and `BSet` are what `construct()` is
+//! bounded by, generic code that constructs needs them too. The aliases name the states that come
+//! up the most, like `ItemBuilderWithA` for a builder waiting for `b` in a struct field. Derived
+//! builders get the same with `#[builder(aliases)]`.
+//!
+//! `examples/states_in_signatures.rs` puts these together in a program of its own.
+
+pub use crate::{ASet, BSet, IsSet, IsUnset};
+
+use crate::{ItemBuilder, Set, Unset};
+
+/// A builder that nothing has been set on yet.
+pub type EmptyItemBuilder = ItemBuilder<Unset, Unset>;
+/// A builder ready for `construct()`.
+pub type CompleteItemBuilder = ItemBuilder<Set, Set>;
+/// A builder with only `a` set, e.g. waiting for `b` in a struct field.
+pub type ItemBuilderWithA = ItemBuilder<Set, Unset>;
+/// A builder with only `b` set.
+pub type ItemBuilderWithB = ItemBuilder<Unset, Set>;
+
+// builders must not be less thread-safe than their fields, whatever their state is. slots
+// guarantee this for any field type, these are the states spelled out for `ItemBuilder`
+const _: fn() = || {
+    fn auto_traits<T: Send + Sync + Unpin>() {}
+    auto_traits::<ItemBuilder<Unset, Unset>>();
+    auto_traits::<ItemBuilder<Set, Unset>>();
+    auto_traits::<ItemBuilder<Unset, Set>>();
+    auto_traits::<ItemBuilder<Set, Set>>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldState, Item};
+
+    fn configure(builder: ItemBuilder<impl IsUnset, Set>) -> CompleteItemBuilder {
+        builder.a("configured".into())
+    }
+
+    /// Something that contributes fields, returning builders from trait methods.
+    trait Stage {
+        fn apply<A, B>(&self, builder: ItemBuilder<A, B>) -> ItemBuilder<A, Set>
+        where
+            A: FieldState,
+            B: IsUnset;
+    }
+
+    struct Numbers(Vec<i32>);
+
+    impl Stage for Numbers {
+        fn apply<A, B>(&self, builder: ItemBuilder<A, B>) -> ItemBuilder<A, Set>
+        where
+            A: FieldState,
+            B: IsUnset,
+        {
+            builder.b(self.0.clone())
+        }
+    }
+
+    /// Builders stored in a struct, with the state spelled through the aliases.
+    struct Pending {
+        fresh: EmptyItemBuilder,
+        named: ItemBuilderWithA,
+        numbered: ItemBuilderWithB,
+        ready: CompleteItemBuilder,
+    }
+
+    #[test]
+    fn across_threads() {
+        let builder = ItemBuilder::new().b(vec![1]);
+        let builder = std::thread::spawn(move || builder.a("sent".into())).join().unwrap();
+        assert_eq!(builder.construct().a, "sent");
+    }
+
+    #[test]
+    fn arguments_and_returns() {
+        let item = configure(Numbers(vec![1]).apply(ItemBuilder::new())).construct();
+        assert_eq!(item, Item { a: "configured".into(), b: vec![1] });
+    }
+
+    /// Constructs whatever states it is given, as long as the compiler can prove they are set.
+    fn finish<A: ASet, B: BSet>(builder: ItemBuilder<A, B>) -> Item {
+        builder.construct()
+    }
+
+    #[test]
+    fn generic_construct() {
+        assert_eq!(finish(ItemBuilder::new().b(vec![]).a("a".into())).a, "a");
+    }
+
+    #[test]
+    fn stored_builders() {
+        let pending = Pending {
+            fresh: ItemBuilder::new(),
+            named: ItemBuilder::new().a("named".into()),
+            numbered: ItemBuilder::new().b(vec![3]),
+            ready: ItemBuilder::new().a("ready".into()).b(vec![]),
+        };
+        assert_eq!(pending.named.b(vec![]).construct().a, "named");
+        assert_eq!(configure(pending.numbered).construct().b, vec![3]);
+        assert_eq!(pending.ready.construct().a, "ready");
+        let item = configure(Numbers(vec![2]).apply(pending.fresh)).construct();
+        assert_eq!(item.b, vec![2]);
+    }
+}
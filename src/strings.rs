@@ -0,0 +1,50 @@
+//! String fields without allocations the caller didn't ask for. `a` takes a `String`, so the
+//! caller converts at the call, and a string that is already owned but wrapped in a `Cow` has to
+//! be unwrapped by hand to not be copied. `a_str` takes anything that converts into a `Cow<str>`:
+//! owned strings, a `String` or a `Cow::Owned`, are moved into the field as they are, borrowed
+//! ones are copied once, which a `String` field can't avoid.
+//!
+//! ```ignore
+//! let item = ItemBuilder::new().a_str("literal").b(vec![]).construct();
+//! ```
+//!
+//! A field that should keep borrowed data has to be a `Cow` itself. Derived builders take care of
+//! that with `#[builder(into)]`: the setter of a `Cow<'a, str>` field takes any
+//! `impl Into<Cow<'a, str>>`, so a `&'static str` is stored borrowed and nothing is allocated.
+
+use std::borrow::Cow;
+
+use crate::{FieldState, ItemBuilder, Set};
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Set `a` from a `&str`, `String` or `Cow<str>`, only copying borrowed strings.
+    #[must_use = "setter returns a new builder; did you mean to chain?"]
+    pub fn a_str<'s>(self, a: impl Into<Cow<'s, str>>) -> ItemBuilder<Set, B> {
+        self.a(a.into().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_strings_are_moved() {
+        let owned = String::from("owned");
+        let buffer = owned.as_ptr();
+        let item = ItemBuilder::new().a_str(owned).b(vec![]).construct();
+        assert_eq!(item.a.as_ptr(), buffer);
+
+        let cow: Cow<str> = Cow::Owned(String::from("cow"));
+        let buffer = cow.as_ptr();
+        assert_eq!(ItemBuilder::new().a_str(cow).b(vec![]).construct().a.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn borrowed_strings_are_copied() {
+        let borrowed = String::from("borrowed");
+        let item = ItemBuilder::new().a_str(&borrowed).b(vec![]).construct();
+        assert_eq!(item.a, borrowed);
+        assert_eq!(ItemBuilder::new().a_str("literal").b(vec![]).construct().a, "literal");
+    }
+}
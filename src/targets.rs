@@ -0,0 +1,73 @@
+//! One builder, several targets. A complete `ItemBuilder` hands its fields to any type that
+//! implements [FromBuilderFields](trait.FromBuilderFields.html) for them, so a second
+//! representation of the same fields doesn't need a second builder:
+//!
+//! ```ignore
+//! let compact: CompactItem = ItemBuilder::new().a("a".into()).b(vec![1]).construct_into();
+//! let (a, b): ItemFields = ItemBuilder::new().a("a".into()).b(vec![1]).construct_into();
+//! ```
+//!
+//! The fields are handed over as a tuple in declaration order. `construct()` stays the finisher
+//! for `Item`, `construct_into::<Item>()` is the same thing spelled generically.
+
+use crate::{Item, ItemBuilder, Set};
+
+/// A type that can be assembled from the fields of a complete builder, `F` being the fields.
+pub trait FromBuilderFields<F>: Sized {
+    fn from_fields(fields: F) -> Self;
+}
+
+/// The fields of `ItemBuilder`, `a` and `b`.
+pub type ItemFields = (String, Vec<i32>);
+
+impl FromBuilderFields<ItemFields> for ItemFields {
+    fn from_fields(fields: ItemFields) -> Self {
+        fields
+    }
+}
+
+impl FromBuilderFields<ItemFields> for Item {
+    fn from_fields((a, b): ItemFields) -> Self {
+        Item { a, b }
+    }
+}
+
+/// `Item` without spare capacity, for items that are kept around but never changed again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactItem {
+    pub a: Box<str>,
+    pub b: Box<[i32]>,
+}
+
+impl FromBuilderFields<ItemFields> for CompactItem {
+    fn from_fields((a, b): ItemFields) -> Self {
+        CompactItem { a: a.into_boxed_str(), b: b.into_boxed_slice() }
+    }
+}
+
+impl ItemBuilder<Set, Set> {
+    /// Consume the builder and assemble any target from its fields.
+    pub fn construct_into<T: FromBuilderFields<ItemFields>>(self) -> T {
+        T::from_fields((self.a.into_inner(), self.b.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete() -> ItemBuilder<Set, Set> {
+        ItemBuilder::new().a(String::with_capacity(16) + "a").b(Vec::with_capacity(16))
+    }
+
+    #[test]
+    fn several_targets() {
+        assert_eq!(complete().construct_into::<Item>(), complete().construct());
+
+        let compact: CompactItem = complete().b(vec![1]).construct_into();
+        assert_eq!(compact, CompactItem { a: "a".into(), b: vec![1].into() });
+
+        let (a, b): ItemFields = complete().construct_into();
+        assert_eq!((a.as_str(), b.capacity()), ("a", 16));
+    }
+}
@@ -0,0 +1,105 @@
+//! Builders that have to be finished. A `BuildTicket` is handed out together with the builder and
+//! the only thing that accepts it is `construct_with_ticket`. Rust can't forbid dropping a value,
+//! and the compiler only warns about a ticket that is never used at all: one that is redeemed on
+//! some path is dropped silently on the others. What the ticket does is give abandoning a builder
+//! a place in the code. It can't be created, copied or consumed anywhere else, so a function that
+//! starts a builder and hands it on has to hand the ticket on too, and a path that returns
+//! without redeeming it is the path that abandons the builder:
+//!
+//! ```ignore
+//! let (builder, ticket) = ItemBuilder::new_with_ticket();
+//! let b = match b {
+//!     Some(b) => b,
+//!     None => return None, // no warning, but the ticket goes out of scope unredeemed right here
+//! };
+//! Some(builder.a(a).b(b).construct_with_ticket(ticket))
+//! ```
+//!
+//! `ItemBuilder` still has the plain `construct()` next to it, so the ticket is a convention
+//! there. Derived builders with `#[builder(require_ticket)]` only come with a ticket and only
+//! `construct_with_ticket` finishes them, skipping the ticket doesn't compile.
+//!
+//! Tickets don't need `std`.
+
+#[cfg(feature = "std")]
+use crate::{Item, ItemBuilder, Set, Unset};
+
+/// Proof that a builder was started, to be redeemed when constructing. The private field keeps
+/// anyone outside of this module from making one.
+#[must_use = "the ticket has to be passed to `construct_with_ticket`"]
+#[derive(Debug)]
+pub struct BuildTicket {
+    _private: (),
+}
+
+impl BuildTicket {
+    /// The ticket handed out with a derived builder, not meant to be called by hand.
+    #[doc(hidden)]
+    pub const fn __derived() -> Self {
+        BuildTicket { _private: () }
+    }
+
+    /// Redeem the ticket. Only the terminal methods of builders call this.
+    #[doc(hidden)]
+    pub fn __redeem(self) {
+        let BuildTicket { _private: () } = self;
+    }
+}
+
+#[cfg(feature = "std")]
+impl ItemBuilder<Unset, Unset> {
+    /// Create a builder together with the ticket needed to construct the item.
+    pub fn new_with_ticket() -> (Self, BuildTicket) {
+        (Self::new(), BuildTicket { _private: () })
+    }
+}
+
+#[cfg(feature = "std")]
+impl ItemBuilder<Set, Set> {
+    /// Construct the item, redeeming the ticket that came with the builder.
+    pub fn construct_with_ticket(self, ticket: BuildTicket) -> Item {
+        ticket.__redeem();
+        self.construct()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redeem() {
+        let (builder, ticket) = ItemBuilder::new_with_ticket();
+        let item = builder.a("a".into()).b(vec![1]).construct_with_ticket(ticket);
+        assert_eq!(item.a, "a");
+    }
+
+    /// What abandoning looks like: the ticket has to travel with the builder, and the early
+    /// return is the one path where it goes out of scope without reaching
+    /// `construct_with_ticket`.
+    #[test]
+    fn abandonment() {
+        fn finish(
+            builder: ItemBuilder<Set, Unset>,
+            ticket: BuildTicket,
+            b: Option<Vec<i32>>,
+        ) -> Option<Item> {
+            let b = b?; // abandons the builder, the ticket is dropped unredeemed
+            Some(builder.b(b).construct_with_ticket(ticket))
+        }
+
+        let start = || {
+            let (builder, ticket) = ItemBuilder::new_with_ticket();
+            (builder.a("a".into()), ticket)
+        };
+        let (builder, ticket) = start();
+        assert_eq!(finish(builder, ticket, Some(vec![1])).unwrap().b, [1]);
+        let (builder, ticket) = start();
+        assert!(finish(builder, ticket, None).is_none());
+    }
+
+    #[test]
+    fn tickets_are_zero_sized() {
+        assert_eq!(std::mem::size_of::<BuildTicket>(), 0);
+    }
+}
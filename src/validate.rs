@@ -0,0 +1,180 @@
+//! Setters checking the value before it is set. A validator is a function from the value to
+//! `Result<(), String>`, the error being the reason the value was rejected. A few common ones are
+//! in [rules](rules/index.html), anything else can be a closure:
+//!
+//! ```ignore
+//! let builder = ItemBuilder::new().validated_a(name, rules::non_empty)?;
+//! let builder = builder.validated_b(values, |b| match b.len() {
+//!     0..=7 => Ok(()),
+//!     _ => Err("too long".into()),
+//! })?;
+//! ```
+//!
+//! Invariants spanning several fields are checked by
+//! [construct_validated](../struct.ItemBuilder.html#method.construct_validated) once all of them
+//! are set, right before the item is assembled.
+//!
+//! The validator only borrows the value and runs before the slot is touched. If it rejects the
+//! value, the value is dropped and the builder is handed back unchanged in the error. If it
+//! panics, the value and the builder are dropped during unwinding like any other locals, there is
+//! no state in between for anything to leak from or be dropped twice.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{FieldState, Item, ItemBuilder, Set};
+
+/// The validators for the fields of `Item`.
+pub mod rules {
+    use std::ops::RangeInclusive;
+
+    /// Rejects empty strings.
+    pub fn non_empty<T: AsRef<str> + ?Sized>(value: &T) -> Result<(), String> {
+        if value.as_ref().is_empty() {
+            Err("must not be empty".into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rejects lists with an element outside of `range`.
+    pub fn all_in(range: RangeInclusive<i32>) -> impl Fn(&Vec<i32>) -> Result<(), String> {
+        move |values| match values.iter().find(|value| !range.contains(value)) {
+            Some(value) => Err(format!("{} is out of range {:?}", value, range)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A value was rejected by the validator passed to its setter. The builder is handed back
+/// unchanged, so the caller can try again with another value.
+pub struct ValidationError<Bld> {
+    pub field: &'static str,
+    pub reason: String,
+    builder: Bld,
+}
+
+impl<Bld> ValidationError<Bld> {
+    /// The builder the setter was called on.
+    pub fn into_builder(self) -> Bld {
+        self.builder
+    }
+}
+
+impl<Bld> fmt::Debug for ValidationError<Bld> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ValidationError")
+            .field("field", &self.field)
+            .field("reason", &self.reason)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Bld> fmt::Display for ValidationError<Bld> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid `{}`: {}", self.field, self.reason)
+    }
+}
+
+impl<Bld> Error for ValidationError<Bld> {}
+
+impl<A: FieldState, B: FieldState> ItemBuilder<A, B> {
+    /// Set `a` if `validator` accepts it. A previous value is kept if it doesn't.
+    pub fn validated_a<F>(
+        self,
+        a: String,
+        validator: F,
+    ) -> Result<ItemBuilder<Set, B>, ValidationError<Self>>
+    where
+        F: FnOnce(&String) -> Result<(), String>,
+    {
+        match validator(&a) {
+            Ok(()) => Ok(self.a(a)),
+            Err(reason) => Err(ValidationError { field: "a", reason, builder: self }),
+        }
+    }
+
+    /// Same as [validated_a](#method.validated_a)
+    pub fn validated_b<F>(
+        self,
+        b: Vec<i32>,
+        validator: F,
+    ) -> Result<ItemBuilder<A, Set>, ValidationError<Self>>
+    where
+        F: FnOnce(&Vec<i32>) -> Result<(), String>,
+    {
+        match validator(&b) {
+            Ok(()) => Ok(self.b(b)),
+            Err(reason) => Err(ValidationError { field: "b", reason, builder: self }),
+        }
+    }
+}
+
+impl ItemBuilder<Set, Set> {
+    /// Construct the item if `check` accepts the combination of fields, e.g.
+    /// `|a, b| if b.len() <= a.len() { Ok(()) } else { Err("b is too long") }`. `check` borrows
+    /// the fields from the slots, a rejection hands back the builder with its fields untouched.
+    pub fn construct_validated<E, F>(self, check: F) -> Result<Item, (Self, E)>
+    where
+        F: FnOnce(&str, &[i32]) -> Result<(), E>,
+    {
+        match check(self.a.get().as_str(), self.b.get().as_slice()) {
+            Ok(()) => Ok(self.construct()),
+            Err(err) => Err((self, err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+
+    #[test]
+    fn accepted_values_are_set() {
+        let builder = ItemBuilder::new().validated_a("a".into(), rules::non_empty).unwrap();
+        let builder = builder.validated_b(vec![1, 2], rules::all_in(0..=9)).unwrap();
+        assert_eq!(builder.construct(), Item { a: "a".into(), b: vec![1, 2] });
+    }
+
+    #[test]
+    fn rejected_values_hand_back_the_builder() {
+        let builder = ItemBuilder::new().a("kept".into());
+        let err = builder.validated_a(String::new(), rules::non_empty).unwrap_err();
+        assert_eq!(err.field, "a");
+        assert_eq!(err.to_string(), "invalid `a`: must not be empty");
+
+        let err = err.into_builder().validated_b(vec![1, 10], rules::all_in(0..=9)).unwrap_err();
+        assert_eq!(err.reason, "10 is out of range 0..=9");
+        let item = err.into_builder().b(vec![]).construct();
+        assert_eq!(item.a, "kept");
+    }
+
+    #[test]
+    fn panicking_validator() {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let builder = ItemBuilder::new().a("dropped".into());
+            builder.validated_b(vec![1], |_| panic!("validator broke"))
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cross_field_check() {
+        fn shorter(a: &str, b: &[i32]) -> Result<(), &'static str> {
+            if b.len() <= a.len() {
+                Ok(())
+            } else {
+                Err("b is longer than a")
+            }
+        }
+
+        let builder = ItemBuilder::new().a("a".into()).b(vec![1, 2]);
+        let (builder, err) = builder.construct_validated(shorter).unwrap_err();
+        assert_eq!(err, "b is longer than a");
+
+        let item = builder.a("ab".into()).construct_validated(shorter).unwrap();
+        assert_eq!(item, Item { a: "ab".into(), b: vec![1, 2] });
+    }
+}
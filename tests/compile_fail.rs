@@ -0,0 +1,29 @@
+//! The compile-time contract: the programs in `compile_fail/` must be rejected by the compiler,
+//! the ones in `pass/` must build and run. `TRYBUILD=overwrite cargo test --test compile_fail`
+//! regenerates the expected errors after a compiler update changed their wording.
+#![cfg(feature = "std")]
+
+#[test]
+fn typestate() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/*.rs");
+    cases.pass("tests/pass/*.rs");
+}
+
+/// The auto traits of the slots, whose errors name the storage of the slots in use.
+#[test]
+fn slot() {
+    let cases = trybuild::TestCases::new();
+    if cfg!(feature = "forbid-unsafe") {
+        cases.compile_fail("tests/compile_fail/safe_slot/*.rs");
+    } else {
+        cases.compile_fail("tests/compile_fail/slot/*.rs");
+    }
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/derive/*.rs");
+}
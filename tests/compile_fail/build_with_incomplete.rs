@@ -0,0 +1,5 @@
+use typestuff::Item;
+
+fn main() {
+    Item::build_with(|b| b.a("forgot b".into()));
+}
@@ -0,0 +1,5 @@
+use typestuff::ItemBuilder;
+
+fn main() {
+    ItemBuilder::new().construct();
+}
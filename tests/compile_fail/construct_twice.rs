@@ -0,0 +1,7 @@
+use typestuff::ItemBuilder;
+
+fn main() {
+    let builder = ItemBuilder::new().a("a".into()).b(vec![]);
+    let _ = builder.construct();
+    let _ = builder.construct();
+}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(aliases)]
+struct Message<P> {
+    payload: P,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Server {
+    #[builder(into, as_ref = str)]
+    host: String,
+    #[builder(as_ref)]
+    name: String,
+    #[builder(shared, as_ref = str)]
+    label: String,
+}
+
+fn main() {}
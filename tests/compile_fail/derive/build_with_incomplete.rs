@@ -0,0 +1,13 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(build_with)]
+struct Server {
+    host: String,
+    #[builder(default)]
+    port: u16,
+}
+
+fn main() {
+    Server::build_with(|b| b.port(80));
+}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(require_ticket, build_with)]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {}
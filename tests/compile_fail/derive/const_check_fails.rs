@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(const_check = "typestuff::policy::secrets_are_sensitive")]
+struct Login {
+    user: String,
+    password: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(const_check = "typestuff::policy::secrets_are_sensitive")]
+struct Login<T> {
+    user: T,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    port: u16,
+    #[builder(skip, default)]
+    cache: Vec<u8>,
+}
+
+fn main() {}
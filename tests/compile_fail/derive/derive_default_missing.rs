@@ -0,0 +1,25 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(derive_default)]
+struct Tuning {
+    workers: u32,
+    #[builder(default)]
+    verbose: bool,
+    timeout: u64,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(derive_default, require_ticket)]
+struct Transfer {
+    #[builder(default)]
+    amount: u64,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(derive_default)]
+enum Shape {
+    Circle { radius: u32 },
+}
+
+fn main() {}
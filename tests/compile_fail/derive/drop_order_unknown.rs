@@ -0,0 +1,17 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(drop_order = "last")]
+struct Scoped {
+    guard: String,
+    resource: String,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(drop_order = reverse)]
+struct Pair {
+    left: String,
+    right: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    #[builder(rename = with_port)]
+    #[builder(rename = set_port)]
+    port: u16,
+}
+
+fn main() {}
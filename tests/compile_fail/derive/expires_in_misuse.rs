@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(build_with, expires_in = Duration::from_secs(60))]
+struct SignedUrl {
+    url: String,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(expires_in)]
+struct Token {
+    secret: String,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(expires_in = Duration::from_secs(60))]
+struct Credentials {
+    user: String,
+}
+
+fn main() {
+    // the terminal is fallible, the value has to be taken out of the `Result`
+    let _: Credentials = Credentials::builder().user("u".into()).construct();
+}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(features(clone, display))]
+struct Pair {
+    a: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(strict = true)]
+struct Config {
+    #[builder(nested = false)]
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Server {
+    #[builder(default, from_str_default)]
+    port: u16,
+    #[builder(skip, from_str_default)]
+    hits: u32,
+    #[builder(from_str_default = "PORT")]
+    backlog: u32,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(require_ticket)]
+struct Transfer {
+    #[builder(from_str_default)]
+    amount: u64,
+}
+
+fn main() {}
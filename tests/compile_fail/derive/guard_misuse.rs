@@ -0,0 +1,25 @@
+use typestuff::TypesafeBuilder;
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(preset = UploadDefaults(tags))]
+struct Upload {
+    #[builder(guard = enabled)]
+    compression: u8,
+    #[builder(guard = "enabled", push = tag)]
+    tags: Vec<String>,
+    #[builder(skip, guard = "enabled")]
+    hits: u32,
+}
+
+#[derive(PartialEq, TypesafeBuilder)]
+#[builder(generate_usage_test)]
+struct Export {
+    #[builder(guard = "enabled")]
+    key: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    #[builder(into(String))]
+    host: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    #[builder = "default"]
+    host: String,
+    #[builder(rename = 1, default(8080), "into")]
+    port: u16,
+}
+
+fn main() {}
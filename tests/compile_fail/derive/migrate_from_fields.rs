@@ -0,0 +1,17 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct ItemV1 {
+    a: String,
+    b: Vec<i32>,
+}
+
+// `fields(...)` has to follow the old builder, `b` comes before `a` here
+#[derive(TypesafeBuilder)]
+#[builder(migrate_from(ItemV1Builder, fields(b, a)))]
+struct ItemV2 {
+    a: String,
+    b: Vec<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct ItemV1 {
+    a: String,
+    b: Vec<i32>,
+    note: String,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(migrate_from(
+    ItemV1Builder,
+    fields(a, b, note),
+    map(old = "c", new = "values"),
+    map(old = "b", new = "items"),
+    transform(field = "d", with = "str::to_uppercase"),
+    strict,
+))]
+struct ItemV2 {
+    a: String,
+    values: Vec<i32>,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(migrate_from(ItemV1Builder))]
+struct ItemV3 {
+    a: String,
+}
+
+fn main() {}
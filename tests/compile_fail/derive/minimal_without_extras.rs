@@ -0,0 +1,15 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(minimal)]
+struct Pair {
+    a: u8,
+    b: u8,
+}
+
+fn main() {
+    let builder = Pair::builder().a(1);
+    let _ = builder.clone();
+    let _ = builder.unset_a();
+    let _ = Pair::builder().b(2).missing_fields();
+}
@@ -0,0 +1,17 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(TypesafeBuilder)]
+struct Deployment {
+    #[builder(nested)]
+    server: Server,
+}
+
+fn main() {
+    Deployment::builder().server_with(|server| server.port(80)).construct();
+}
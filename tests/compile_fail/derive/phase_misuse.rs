@@ -0,0 +1,23 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(into_builder)]
+struct Job<T> {
+    name: T,
+    #[builder(phase = 3)]
+    retries: u32,
+    #[builder(phase = 2)]
+    connection: String,
+    #[builder(skip, phase = 2)]
+    attempts: u32,
+}
+
+#[derive(TypesafeBuilder)]
+enum Request {
+    Get {
+        #[builder(phase = 2)]
+        url: String,
+    },
+}
+
+fn main() {}
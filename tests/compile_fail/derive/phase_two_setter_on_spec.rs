@@ -0,0 +1,14 @@
+use std::rc::Rc;
+
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Job {
+    name: String,
+    #[builder(phase = 2)]
+    connection: Rc<String>,
+}
+
+fn main() {
+    Job::builder().name("nightly".into()).connection(Rc::new("db".into()));
+}
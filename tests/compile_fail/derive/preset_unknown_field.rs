@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(preset = Defaults(host, prot))]
+struct Config {
+    host: String,
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    #[builder(push)]
+    tags: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+#![deny(deprecated)]
+
+// The same type once with `minimal` and once with everything, so the two reports can be compared.
+
+mod minimal {
+    use typestuff::TypesafeBuilder;
+
+    #[derive(TypesafeBuilder)]
+    #[builder(minimal, report)]
+    pub struct Server {
+        host: String,
+        port: u16,
+    }
+}
+
+mod full {
+    use typestuff::TypesafeBuilder;
+
+    #[derive(TypesafeBuilder)]
+    #[builder(report)]
+    pub struct Server {
+        host: String,
+        port: u16,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use typestuff::TypesafeBuilder;
+
+// every misuse is reported, not only the first one
+#[derive(TypesafeBuilder)]
+#[builder(strict, strict, preset = Defaults(nope))]
+struct Config {
+    #[builder(into, into)]
+    host: String,
+    #[builder(skip, nested)]
+    port: u16,
+    #[builder(unknown)]
+    tags: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(require_ticket)]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {
+    let (builder, _ticket) = Transfer::builder();
+    builder.amount(5).construct();
+}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(require_ticket, into_builder)]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {}
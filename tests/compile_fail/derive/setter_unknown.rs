@@ -0,0 +1,13 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Batch {
+    #[builder(setter(with_len))]
+    lines: Vec<String>,
+    #[builder(setter)]
+    text: String,
+    #[builder(skip, setter(with_capacity))]
+    scratch: Vec<u8>,
+}
+
+fn main() {}
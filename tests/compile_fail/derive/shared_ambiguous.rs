@@ -0,0 +1,18 @@
+#![deny(deprecated)]
+
+use typestuff::TypesafeBuilder;
+
+mod geometry {
+    #[derive(Clone)]
+    pub struct Arc<T>(pub T);
+}
+
+#[derive(TypesafeBuilder)]
+struct Drawing {
+    #[builder(shared)]
+    outline: geometry::Arc<u8>,
+    #[builder(shared = as_is)]
+    stored: std::sync::Arc<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Lookup {
+    #[builder(shared, into)]
+    name: String,
+    #[builder(shared, push = entry)]
+    table: Vec<i32>,
+    #[builder(skip, shared)]
+    hits: u64,
+    #[builder(shared = copy)]
+    cache: Vec<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    port: u16,
+    #[builder(skip, into, push = tag, rename = labels)]
+    tags: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(strict)]
+struct Limits {
+    max: u32,
+}
+
+fn main() {
+    Limits::builder().max(1).max(2).construct();
+}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(into_builder, require_ticket, aliases, diff)]
+enum Request {
+    Get { url: String },
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Server {
+    #[builder(template = seconds)]
+    timeout: Duration,
+    #[builder(template)]
+    root: String,
+    #[builder(skip, template = duration)]
+    uptime: Duration,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Port(u16);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+struct Config {
+    #[builder(defualt)]
+    port: u16,
+}
+
+fn main() {}
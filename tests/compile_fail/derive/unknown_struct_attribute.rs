@@ -0,0 +1,9 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(stirct)]
+struct Config {
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+#[builder(generate_usage_test)]
+struct Wrapper<T> {
+    value: T,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(generate_usage_test)]
+enum Shape {
+    Circle { radius: u32 },
+}
+
+#[derive(TypesafeBuilder)]
+struct Counter {
+    #[builder(test_value)]
+    count: u32,
+    #[builder(skip, test_value = 1)]
+    hits: u32,
+}
+
+fn main() {}
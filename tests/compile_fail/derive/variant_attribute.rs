@@ -0,0 +1,10 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+enum Request {
+    #[builder(default)]
+    Get { url: String },
+    Post(Vec<u8>),
+}
+
+fn main() {}
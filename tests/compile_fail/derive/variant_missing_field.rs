@@ -0,0 +1,11 @@
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+enum Request {
+    Get { url: String },
+    Post { url: String, body: Vec<u8> },
+}
+
+fn main() {
+    Request::builder().post().url("/".into()).construct();
+}
@@ -0,0 +1,8 @@
+use typestuff::ItemBuilder;
+
+fn main() {
+    let builder = ItemBuilder::new().a("a".into());
+    println!("{}", builder.get_a());
+    // `b` hasn't been set, there is nothing to read
+    println!("{:?}", builder.get_b());
+}
@@ -0,0 +1,8 @@
+use typestuff::groups::{AlertBuilder, ConnectionBuilder};
+
+fn main() {
+    // a username without its password
+    ConnectionBuilder::new().host("h".into()).username("u".into()).construct();
+    // neither of the two
+    AlertBuilder::new().construct();
+}
@@ -0,0 +1,5 @@
+use typestuff::config::ConfigBuilder;
+
+fn main() {
+    let _: Option<ConfigBuilder<u32, u8>> = None;
+}
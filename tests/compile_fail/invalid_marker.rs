@@ -0,0 +1,5 @@
+use typestuff::ItemBuilder;
+
+fn main() {
+    let _: Option<ItemBuilder<u8, String>> = None;
+}
@@ -0,0 +1,5 @@
+typestuff::builder!(struct Config { host: String, port: u16 } => ConfigBuilder);
+
+fn main() {
+    Config::builder().host("localhost".into()).construct();
+}
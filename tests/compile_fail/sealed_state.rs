@@ -0,0 +1,9 @@
+use typestuff::FieldState;
+
+enum Maybe {}
+
+impl FieldState for Maybe {
+    const IS_SET: bool = true;
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use std::rc::Rc;
+use std::thread;
+
+use typestuff::slot::Slot;
+use typestuff::Unset;
+
+fn main() {
+    // nothing is stored yet, but the slot is for a type that can't leave its thread
+    let slot: Slot<Rc<()>, Unset> = Slot::empty();
+    thread::spawn(move || drop(slot));
+}
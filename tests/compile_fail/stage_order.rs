@@ -0,0 +1,8 @@
+use typestuff::staged::SessionBuilder;
+
+fn main() {
+    // TLS before the connection is complete
+    SessionBuilder::new().host("h".into()).server_name("n".into());
+    // back to the connection once TLS has started
+    SessionBuilder::new().host("h".into()).port(1).verify(true).host("h".into());
+}
@@ -0,0 +1,17 @@
+use typestuff::states::IsUnset;
+use typestuff::{ItemBuilder, Set, Unset};
+
+// expects `a` to be the field that has been set
+fn finish(builder: ItemBuilder<Set, Unset>) -> ItemBuilder<Set, Set> {
+    builder.b(vec![])
+}
+
+// only accepts builders where `a` hasn't been set yet
+fn fill_a(builder: ItemBuilder<impl IsUnset, Set>) -> ItemBuilder<Set, Set> {
+    builder.a("a".into())
+}
+
+fn main() {
+    finish(ItemBuilder::new().b(vec![]));
+    fill_a(ItemBuilder::new().a("a".into()).b(vec![]));
+}
@@ -0,0 +1,1064 @@
+//! The derived builders, used the way a downstream crate would use them.
+#![cfg(feature = "derive")]
+
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::sync::Arc as Shared;
+use std::time::{Duration, SystemTime};
+
+use typestuff::diff::FieldDiff;
+use typestuff::error::{BuildError, ErrorKind};
+use typestuff::laws::{check_laws, Buildable};
+use typestuff::reflection::missing_fields;
+use typestuff::{Set, TypesafeBuilder, Unset};
+
+/// A doc comment and a derive of its own, neither may confuse the macro.
+#[derive(Debug, TypesafeBuilder)]
+pub struct Config {
+    pub host: String,
+    #[doc = "the port"]
+    port: u16,
+    tags: HashMap<String, Vec<u8>>,
+    map: fn(u8) -> u8,
+}
+
+#[derive(TypesafeBuilder)]
+struct Tracked {
+    first: Rc<()>,
+    second: Rc<()>,
+}
+
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+struct Empty {}
+
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(into_builder, aliases, build_with, preset = ServerDefaults(host, port))]
+struct Server {
+    #[builder(into)]
+    host: String,
+    #[builder(default = 8080)]
+    port: u16,
+    #[builder(default, into)]
+    tags: Vec<String>,
+}
+
+/// Generic over a payload with a bound and a default, borrowing its topic.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+struct Message<'a, P: Clone = String>
+where
+    P: std::fmt::Debug,
+{
+    topic: &'a str,
+    payload: P,
+    #[builder(default)]
+    retries: Option<u8>,
+}
+
+/// Keeps borrowed text borrowed.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+struct Label<'a> {
+    #[builder(into)]
+    text: Cow<'a, str>,
+}
+
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+struct Window<T, const N: usize> {
+    values: [T; N],
+}
+
+#[derive(Debug, TypesafeBuilder)]
+struct Request {
+    #[builder(push = header)]
+    headers: HashMap<String, String>,
+    #[builder(default, push = arg)]
+    args: Vec<u8>,
+}
+
+#[derive(Debug, TypesafeBuilder)]
+#[builder(strict)]
+struct Limits {
+    max: u32,
+    #[builder(default, push = tag)]
+    tags: Vec<String>,
+}
+
+/// One builder per variant, picked by `Call::builder()`.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+enum Call<B> {
+    Get {
+        #[builder(into)]
+        url: String,
+    },
+    Post {
+        #[builder(into)]
+        url: String,
+        body: B,
+        #[builder(default)]
+        retries: u8,
+    },
+    Ping,
+    /// A variant whose name is a keyword in snake case.
+    Type,
+}
+
+/// Configured through the builders of its fields.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+struct Deployment {
+    name: String,
+    #[builder(nested)]
+    server: Server,
+    #[builder(nested, default = Call::Ping)]
+    check: Call<()>,
+}
+
+/// Renamed setters and fields the builder leaves out.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(into_builder)]
+struct Endpoint {
+    #[builder(rename = "with_port")]
+    port: u16,
+    #[builder(rename = path, into)]
+    route: String,
+    #[builder(skip)]
+    cache: Vec<u8>,
+    #[builder(skip, default = 3)]
+    retries: u8,
+}
+
+/// Only constructed with the ticket its builder came with.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(require_ticket, generate_usage_test)]
+struct Transfer {
+    amount: u64,
+    #[builder(default)]
+    memo: String,
+}
+
+/// Checked against the secrets policy while compiling.
+#[derive(TypesafeBuilder)]
+#[builder(const_check = "typestuff::policy::secrets_are_sensitive")]
+struct Login {
+    user: String,
+    #[builder(sensitive)]
+    password: String,
+}
+
+/// Built in two phases, the connection can't leave the thread it was opened on.
+#[derive(Debug, TypesafeBuilder)]
+struct Task {
+    name: String,
+    #[builder(default = 3)]
+    retries: u32,
+    #[builder(phase = 2)]
+    connection: Rc<String>,
+    #[builder(skip)]
+    attempts: u32,
+}
+
+fn double(x: u8) -> u8 {
+    x * 2
+}
+
+#[test]
+fn construct_in_any_order() {
+    let config = Config::builder()
+        .map(double)
+        .tags(HashMap::new())
+        .port(80)
+        .host("localhost".into())
+        .construct();
+    assert_eq!(config.host, "localhost");
+    assert_eq!(config.port, 80);
+    assert!(config.tags.is_empty());
+    assert_eq!((config.map)(2), 4);
+
+    let config = ConfigBuilder::default().host("a".into()).host("b".into()).port(1);
+    let config = config.tags(HashMap::new()).map(double).construct();
+    assert_eq!(config.host, "b");
+}
+
+#[test]
+fn drops_what_has_been_set() {
+    let (first, second) = (Rc::new(()), Rc::new(()));
+
+    drop(TrackedBuilder::new().first(first.clone()));
+    assert_eq!(Rc::strong_count(&first), 1);
+
+    let builder = Tracked::builder().second(second.clone()).second(second.clone());
+    assert_eq!(Rc::strong_count(&second), 2);
+    drop(builder);
+    assert_eq!(Rc::strong_count(&second), 1);
+
+    let tracked = Tracked::builder().first(first.clone()).second(second.clone()).construct();
+    assert!(Rc::ptr_eq(&tracked.first, &first) && Rc::ptr_eq(&tracked.second, &second));
+    drop(tracked);
+    assert_eq!(Rc::strong_count(&first), 1);
+}
+
+#[test]
+fn optional_fields() {
+    let server = Server::builder().host("a").construct();
+    assert_eq!(server, Server { host: "a".into(), port: 8080, tags: vec![] });
+
+    let server = Server::builder().tags(vec!["t".into()]).port(1).host("b").construct();
+    assert_eq!(server, Server { host: "b".into(), port: 1, tags: vec!["t".into()] });
+}
+
+#[test]
+fn no_fields() {
+    assert_eq!(Empty::builder().construct(), Empty {});
+}
+
+#[test]
+fn generic_structs() {
+    let topic = String::from("news");
+    let message = Message::builder().payload(vec![1]).topic(&topic).construct();
+    assert_eq!(message, Message { topic: "news", payload: vec![1], retries: None });
+
+    let message: Message = MessageBuilder::new().topic(&topic).payload("text".into()).construct();
+    assert_eq!(message.payload, "text");
+
+    let window = Window::builder().values([1u8, 2, 3]).construct();
+    assert_eq!(window, Window { values: [1, 2, 3] });
+}
+
+#[test]
+fn borrowed_strings() {
+    let label = Label::builder().text("static").construct();
+    assert!(matches!(label.text, Cow::Borrowed("static")));
+
+    let owned = String::from("owned");
+    let buffer = owned.as_ptr();
+    let label = Label::builder().text(owned).construct();
+    assert!(matches!(&label.text, Cow::Owned(text) if text.as_ptr() == buffer));
+}
+
+#[test]
+fn into_setters() {
+    let server = Server::builder().host(String::from("a")).tags(["t".to_string()]).construct();
+    assert_eq!(server, Server { host: "a".into(), port: 8080, tags: vec!["t".into()] });
+}
+
+#[test]
+fn push_setters() {
+    let request = Request::builder().header(("a".into(), "1".into())).arg(1).arg(2).construct();
+    assert_eq!(request.headers.len(), 1);
+    assert_eq!(request.args, [1, 2]);
+
+    // the first push sets the field, a later whole-value setter replaces what was pushed
+    let request = Request::builder().arg(1).args(vec![]).header(("b".into(), "2".into()));
+    let request = request.header(("c".into(), "3".into())).construct();
+    assert!(request.args.is_empty());
+    assert_eq!(request.headers["c"], "3");
+}
+
+#[test]
+fn unset_fields() {
+    let value = Rc::new(());
+    let builder = Tracked::builder().first(value.clone()).second(value.clone()).unset_first();
+    assert_eq!(Rc::strong_count(&value), 2);
+    let tracked = builder.unset_second().second(value.clone()).first(value.clone()).construct();
+    assert_eq!(Rc::strong_count(&value), 3);
+    drop(tracked);
+
+    let server = Server::builder().port(1).unset_port().host("a").construct();
+    assert_eq!(server.port, 8080);
+}
+
+#[test]
+fn strict_builder() {
+    let limits = Limits::builder().tag("a".into()).tag("b".into()).max(1).construct();
+    assert_eq!((limits.max, limits.tags.len()), (1, 2));
+
+    // setting twice is a compile error, going through `unset_` first is fine
+    let limits = Limits::builder().max(1).unset_max().max(2).construct();
+    assert_eq!(limits.max, 2);
+}
+
+#[test]
+fn enum_variants() {
+    let call: Call<()> = Call::builder().get().url("/").construct();
+    assert_eq!(call, Call::Get { url: "/".into() });
+
+    let call = Call::builder().post().body(vec![1]).url("/items").construct();
+    assert_eq!(call, Call::Post { url: "/items".into(), body: vec![1], retries: 0 });
+
+    let call = CallPostBuilder::new().url("/").retries(2).body("text").unset_url().url("/a");
+    assert_eq!(call.construct(), Call::Post { url: "/a".into(), body: "text", retries: 2 });
+
+    assert_eq!(Call::<()>::builder().ping().construct(), Call::Ping);
+    assert_eq!(Call::<()>::builder().r#type().construct(), Call::Type);
+}
+
+#[test]
+fn nested_builders() {
+    let deployment = Deployment::builder()
+        .server_with(|server| server.host("localhost").port(80))
+        .name("web".into())
+        .construct();
+    assert_eq!(deployment.server, Server { host: "localhost".into(), port: 80, tags: vec![] });
+    assert_eq!(deployment.check, Call::Ping);
+
+    // a sub-builder finished elsewhere, and a variant picked by the builder of an enum
+    let server = Server::builder().host("db");
+    let deployment = Deployment::builder().name("db".into()).server_with(|_| server);
+    let deployment = deployment.check_with(|call| call.get().url("/health")).construct();
+    assert_eq!(deployment.check, Call::Get { url: "/health".into() });
+}
+
+#[test]
+fn forked_builders() {
+    let value = Rc::new(());
+    let base = Tracked::builder().first(value.clone());
+    let fork = base.clone();
+    assert_eq!(Rc::strong_count(&value), 3);
+
+    // the unset field stays unset in both, each can be finished differently
+    let (first, second) = (Rc::new(()), Rc::new(()));
+    let left = base.second(first.clone()).construct();
+    let right = fork.second(second.clone()).construct();
+    assert!(Rc::ptr_eq(&left.second, &first) && Rc::ptr_eq(&right.second, &second));
+    drop((left, right));
+    assert_eq!(Rc::strong_count(&value), 1);
+
+    let call = Call::builder().post().url("/").retries(1);
+    let call = call.clone().body(1).construct() == call.body(2).construct();
+    assert!(!call);
+}
+
+#[test]
+fn debug_shows_set_fields() {
+    let builder = Server::builder().port(80).tags(vec!["t".into()]);
+    let expected = r#"ServerBuilder { host: <unset>, port: 80, tags: ["t"] }"#;
+    assert_eq!(format!("{:?}", builder), expected);
+
+    let builder = Call::<()>::builder().get();
+    assert_eq!(format!("{:?}", builder.url("/")), r#"CallGetBuilder { url: "/" }"#);
+}
+
+// the field table is the one of the hand-written builders, policies run on it the same way
+typestuff::const_check!(ServerBuilder::FIELDS, typestuff::policy::secrets_are_sensitive);
+
+#[test]
+fn introspection() {
+    let names: Vec<_> = ServerBuilder::FIELDS.iter().map(|field| field.name).collect();
+    assert_eq!(names, ["host", "port", "tags"]);
+    let [host, port, _] = ServerBuilder::FIELDS else { panic!() };
+    assert!(host.required && !host.has_default && !port.required && port.has_default);
+    let types: Vec<_> = ConfigBuilder::FIELDS.iter().map(|field| field.type_name).collect();
+    assert_eq!(types, ["String", "u16", "HashMap<String, Vec<u8>>", "fn(u8) -> u8"]);
+
+    let states = ServerBuilder::<Unset, Set, Unset>::STATES;
+    assert_eq!(missing_fields(ServerBuilder::FIELDS, states), ["host"]);
+
+    let builder = Server::builder().port(80);
+    assert_eq!(builder.set_fields().collect::<Vec<_>>(), ["port"]);
+    assert_eq!(builder.missing_fields().collect::<Vec<_>>(), ["host"]);
+    assert!(!builder.is_complete());
+
+    // optional fields are never missing
+    let builder = builder.host("a");
+    assert_eq!(builder.missing_fields().count(), 0);
+    assert!(builder.is_complete() && Call::<()>::builder().ping().is_complete());
+}
+
+#[test]
+fn round_trip() {
+    let server = Server::builder().host("a").tags(vec!["t".into()]).construct();
+    let server = server.into_builder().port(1).unset_tags().construct();
+    assert_eq!(server, Server { host: "a".into(), port: 1, tags: vec![] });
+
+    let builder: ServerBuilder<_, _, _> = server.into();
+    assert_eq!(builder.set_fields().count(), 3);
+}
+
+#[test]
+fn rename_and_skip() {
+    let endpoint = Endpoint::builder().path("/a").with_port(1).unset_with_port().with_port(2);
+    let names: Vec<_> = EndpointBuilder::FIELDS.iter().map(|field| field.name).collect();
+    assert_eq!(names, ["port", "route"]);
+    assert_eq!(format!("{:?}", endpoint), r#"EndpointBuilder { port: 2, route: "/a" }"#);
+
+    let endpoint = endpoint.construct();
+    assert_eq!(endpoint, Endpoint { port: 2, route: "/a".into(), cache: vec![], retries: 3 });
+
+    // skipped fields start over from their defaults
+    let endpoint = Endpoint { cache: vec![1], retries: 0, ..endpoint };
+    let endpoint = endpoint.into_builder().path("/b").construct();
+    assert_eq!((endpoint.route.as_str(), endpoint.cache, endpoint.retries), ("/b", vec![], 3));
+}
+
+#[test]
+fn presets() {
+    let base = || ServerDefaults { host: "0.0.0.0".into(), port: 80 };
+    let server = Server::builder().apply(base()).port(8080).construct();
+    assert_eq!((server.host.as_str(), server.port), ("0.0.0.0", 8080));
+
+    // applied last, the preset overrides what was set before and leaves the rest alone
+    let server = Server::builder().tags(vec!["t".into()]).host("a").apply(base()).construct();
+    assert_eq!(server, Server { host: "0.0.0.0".into(), port: 80, tags: vec!["t".into()] });
+}
+
+/// The setter templates of `typestuff::presets`, on a strict builder so they keep its bounds.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(strict)]
+struct Listener {
+    #[builder(template = duration)]
+    timeout: Duration,
+    #[builder(default, template = path)]
+    root: PathBuf,
+    #[builder(rename = listen, template = addr)]
+    addr: SocketAddr,
+}
+
+#[test]
+fn templates() {
+    let listener = Listener::builder().timeout_secs(2).try_listen("127.0.0.1:80").unwrap();
+    let listener = listener.root_path("/srv").construct();
+    assert_eq!(
+        listener,
+        Listener {
+            timeout: Duration::from_secs(2),
+            root: "/srv".into(),
+            addr: SocketAddr::from(([127, 0, 0, 1], 80)),
+        },
+    );
+    let listener = Listener::builder().timeout_ms(5).try_listen("[::1]:80").unwrap().construct();
+    assert_eq!((listener.timeout, listener.root), (Duration::from_millis(5), PathBuf::new()));
+
+    // the error names the field, not the setter
+    let err = Listener::builder().try_listen("localhost").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+    assert_eq!(err.fields(), ["addr"]);
+}
+
+/// A half-done builder kept in a struct, named through the aliases.
+struct Staged {
+    host_only: ServerBuilderWithHost,
+    no_tags: ServerBuilderWithoutTags,
+}
+
+#[test]
+fn aliases() {
+    fn finish(builder: ServerBuilderWithoutTags) -> CompleteServerBuilder {
+        builder.tags(vec!["t".into()])
+    }
+
+    let empty: EmptyServerBuilder = Server::builder();
+    let no_tags = Server::builder().port(1).host("b");
+    let staged = Staged { host_only: empty.host("a"), no_tags };
+    assert_eq!(staged.host_only.construct().port, 8080);
+    assert_eq!(finish(staged.no_tags).construct().tags, ["t"]);
+}
+
+#[test]
+fn tickets() {
+    let (builder, ticket) = Transfer::builder();
+    let transfer = builder.amount(5).construct_with_ticket(ticket);
+    assert_eq!(transfer, Transfer { amount: 5, memo: String::new() });
+
+    let (builder, ticket) = TransferBuilder::new_with_ticket();
+    assert!(!builder.is_complete());
+    let transfer = builder.memo("rent".into()).amount(1).construct_with_ticket(ticket);
+    assert_eq!(transfer.memo, "rent");
+}
+
+#[test]
+fn sensitive_fields() {
+    let flags: Vec<_> = LoginBuilder::FIELDS.iter().map(|field| field.sensitive).collect();
+    assert_eq!(flags, [false, true]);
+    let login = Login::builder().password("hunter2".into()).user("root".into()).construct();
+    assert_eq!((login.user.as_str(), login.password.as_str()), ("root", "hunter2"));
+}
+
+#[test]
+fn build_with() {
+    let server = Server::build_with(|b| b.host("a").tags(vec!["t".into()]));
+    assert_eq!(server, Server { host: "a".into(), port: 8080, tags: vec!["t".into()] });
+    assert_eq!(Server::build_with(|b| b.port(1).host("b")).port, 1);
+}
+
+#[test]
+fn two_phases() {
+    fn assert_send<T: Send + 'static>(_: &T) {}
+
+    assert_send(&Task::builder().retries(1));
+    let spec: TaskSpec = Task::builder().name("nightly".into()).construct();
+    assert_send(&spec);
+
+    let task = std::thread::spawn(move || {
+        let builder = TaskBuilder::from_spec(spec);
+        assert_eq!(builder.missing_fields().collect::<Vec<_>>(), ["connection"]);
+        let task = builder.connection(Rc::new("db".into())).construct();
+        (task.name, task.retries, task.connection.len(), task.attempts)
+    });
+    assert_eq!(task.join().unwrap(), ("nightly".into(), 3, 2, 0));
+}
+
+/// The previous version of `ItemV2`, `note` has no field to move into.
+#[derive(TypesafeBuilder)]
+struct ItemV1 {
+    a: String,
+    b: Vec<i32>,
+    #[builder(default)]
+    note: String,
+}
+
+fn upgrade_a(a: String) -> String {
+    format!("v2:{}", a)
+}
+
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(migrate_from(
+    ItemV1Builder,
+    fields(a, b, note),
+    map(old = "b", new = "values"),
+    transform(field = "a", with = "upgrade_a"),
+))]
+struct ItemV2 {
+    label: String,
+    a: String,
+    values: Vec<i32>,
+}
+
+#[test]
+fn migrate_from() {
+    let v1 = ItemV1::builder().a("a".into()).b(vec![1, 2]).note("gone".into());
+    let item = ItemV2Builder::from_item_v1(v1).label("new".into()).construct();
+    assert_eq!(item, ItemV2 { label: "new".into(), a: "v2:a".into(), values: vec![1, 2] });
+
+    // the unset fields stay unset, `label` is new and starts out unset as well
+    let v2: ItemV2Builder<Unset, Unset, Set> =
+        ItemV2Builder::from_item_v1(ItemV1::builder().b(vec![3]));
+    assert_eq!(v2.missing_fields().collect::<Vec<_>>(), ["label", "a"]);
+    let item = v2.a("set in v2".into()).label("new".into()).construct();
+    assert_eq!((item.a.as_str(), item.values), ("set in v2", vec![3]));
+
+    // the old builder keeps working for the values still around
+    let old = ItemV1::builder().a("a".into()).b(vec![]).construct();
+    assert_eq!((old.a, old.b, old.note), ("a".into(), vec![], String::new()));
+}
+
+/// A large table shared between many builders instead of being copied into each of them.
+#[derive(Debug, Clone, PartialEq, TypesafeBuilder)]
+#[builder(into_builder)]
+struct Lookup {
+    name: String,
+    #[builder(shared)]
+    table: Vec<i32>,
+}
+
+/// The same with a target that keeps sharing the table.
+#[derive(TypesafeBuilder)]
+struct SharedLookup {
+    name: String,
+    #[builder(shared)]
+    table: Arc<Vec<i32>>,
+}
+
+#[test]
+fn shared() {
+    // a table only this builder holds is moved out, not copied
+    let table = vec![1, 2, 3];
+    let ptr = table.as_ptr();
+    let lookup = Lookup::builder().name("unique".into()).table(table).construct();
+    assert_eq!(lookup.table.as_ptr(), ptr);
+
+    // one that is still shared is cloned, and the builder's reference is gone after
+    let table = Arc::new(vec![1, 2, 3]);
+    let lookup = Lookup::builder().name("shared".into()).table(Arc::clone(&table)).construct();
+    assert_eq!(lookup.table, *table);
+    assert_ne!(lookup.table.as_ptr(), table.as_ptr());
+    assert_eq!(Arc::strong_count(&table), 1);
+
+    let lookup = lookup.into_builder().name("again".into()).construct();
+    assert_eq!(lookup, Lookup { name: "again".into(), table: vec![1, 2, 3] });
+}
+
+/// Shared fields declared as `Arc`s in ways the derive can't tell from the path alone.
+type SharedTable = Arc<Vec<i32>>;
+
+#[derive(TypesafeBuilder)]
+struct Tables {
+    #[builder(shared = as_is)]
+    aliased: SharedTable,
+    #[builder(shared)]
+    qualified: ::std::sync::Arc<Vec<i32>>,
+    #[builder(shared = as_is)]
+    renamed: Shared<Vec<i32>>,
+    #[builder(shared = wrap)]
+    wrapped: SharedTable,
+}
+
+#[test]
+fn shared_arc_paths() {
+    let table = Arc::new(vec![1, 2, 3]);
+    let tables = Tables::builder()
+        .aliased(Arc::clone(&table))
+        .qualified(Arc::clone(&table))
+        .renamed(Arc::clone(&table))
+        .wrapped(Arc::clone(&table))
+        .construct();
+    let fields = [&tables.aliased, &tables.qualified, &tables.renamed, &tables.wrapped];
+    assert!(fields.iter().all(|field| Arc::ptr_eq(field, &table)));
+    drop(tables);
+
+    // the fields stored as they are take the table itself, like any `Arc` field
+    let tables = Tables::builder()
+        .aliased(vec![1])
+        .qualified(vec![2])
+        .renamed(vec![3])
+        .wrapped(Arc::new(vec![4]))
+        .construct();
+    let fields = [&tables.aliased, &tables.qualified, &tables.renamed, &tables.wrapped];
+    assert_eq!(fields.map(|field| field[0]), [1, 2, 3, 4]);
+
+    // a fork of the builder clones the table's `Arc` as it is, the wrapped one the `Arc` around it
+    let builder = Tables::builder().aliased(Arc::clone(&table)).wrapped(Arc::clone(&table));
+    let fork = builder.clone();
+    assert_eq!(Arc::strong_count(&table), 4);
+    drop((builder, fork));
+    assert_eq!(Arc::strong_count(&table), 1);
+}
+
+#[test]
+fn shared_between_builders() {
+    let table = Arc::new((0..1000).collect::<Vec<_>>());
+    let builder = Lookup::builder().table(Arc::clone(&table));
+    // cloning the builder clones the `Arc`, not the table
+    let builders: Vec<_> = (0..100).map(|i| builder.clone().name(i.to_string())).collect();
+    assert_eq!(Arc::strong_count(&table), 102);
+    drop((builder, builders));
+    assert_eq!(Arc::strong_count(&table), 1);
+
+    let lookups: Vec<_> = (0..100)
+        .map(|i| SharedLookup::builder().name(i.to_string()).table(Arc::clone(&table)))
+        .map(SharedLookupBuilder::construct)
+        .collect();
+    assert!(lookups.iter().all(|lookup| Arc::ptr_eq(&lookup.table, &table)));
+    assert_eq!(lookups[7].name, "7");
+    let lookup = SharedLookup::builder().name("owned".into()).table(vec![1]).construct();
+    assert_eq!(*lookup.table, [1]);
+}
+
+/// Records its name in the log when dropped.
+#[derive(Debug)]
+struct Recorder<'l>(&'static str, &'l RefCell<Vec<&'static str>>);
+
+impl Drop for Recorder<'_> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+#[derive(TypesafeBuilder)]
+struct Pair<'l> {
+    left: Recorder<'l>,
+    right: Recorder<'l>,
+}
+
+/// The resource has to go before the guard protecting it.
+#[derive(TypesafeBuilder)]
+#[builder(drop_order = "reverse")]
+struct Scoped<'l> {
+    guard: Recorder<'l>,
+    resource: Recorder<'l>,
+}
+
+#[test]
+fn drop_order() {
+    let log = RefCell::new(Vec::new());
+    drop(Pair::builder().right(Recorder("right", &log)).left(Recorder("left", &log)));
+    assert_eq!(*log.borrow(), ["left", "right"]);
+
+    log.borrow_mut().clear();
+    drop(Scoped::builder().guard(Recorder("guard", &log)).resource(Recorder("resource", &log)));
+    assert_eq!(*log.borrow(), ["resource", "guard"]);
+
+    // fields that aren't set have nothing to drop, constructing hands the order to the target
+    log.borrow_mut().clear();
+    drop(Scoped::builder().guard(Recorder("guard", &log)));
+    let scoped = Scoped::builder().resource(Recorder("resource", &log));
+    let scoped = scoped.guard(Recorder("guard", &log)).construct();
+    assert_eq!((scoped.guard.0, scoped.resource.0), ("guard", "resource"));
+    drop(scoped);
+    assert_eq!(*log.borrow(), ["guard", "guard", "resource"]);
+
+    let pair = Pair::builder().right(Recorder("right", &log)).left(Recorder("left", &log));
+    let pair = pair.construct();
+    assert_eq!((pair.left.0, pair.right.0), ("left", "right"));
+}
+
+/// Signed URLs mustn't stay usable, their builders expire a minute after they are started.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(into_builder, expires_in = Duration::from_secs(60), generate_usage_test)]
+struct SignedUrl {
+    #[builder(into)]
+    url: String,
+    #[builder(default)]
+    signature: Vec<u8>,
+}
+
+#[test]
+fn expires_in() {
+    let builder = SignedUrl::builder().url("https://a");
+    assert!(!builder.is_expired());
+    let url = builder.clone().construct().unwrap();
+    assert_eq!(url, SignedUrl { url: "https://a".into(), signature: vec![] });
+    assert_eq!(url.into_builder().signature(vec![1]).construct().unwrap().signature, [1]);
+
+    let deadline = builder.deadline().unwrap();
+    let err = builder.construct_with_clock(&|| deadline).unwrap_err();
+    assert_eq!(err, BuildError::Expired { deadline });
+}
+
+#[test]
+fn expires_in_injected_clock() {
+    let now = Cell::new(SystemTime::UNIX_EPOCH);
+    let clock = || now.get();
+    let builder = SignedUrlBuilder::new_with_clock(&clock).url("https://a");
+    let deadline = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+    assert_eq!(builder.deadline(), Some(deadline));
+
+    // clones expire together with the original
+    now.set(deadline - Duration::from_nanos(1));
+    assert!(builder.clone().construct_with_clock(&clock).is_ok());
+    now.set(deadline);
+    assert_eq!(builder.construct_with_clock(&clock).unwrap_err().kind(), ErrorKind::Expired);
+}
+
+/// Collections whose final size is known up front, filled without reallocating.
+#[derive(TypesafeBuilder)]
+struct Batch {
+    #[builder(setter(with_capacity), push = line)]
+    lines: Vec<String>,
+    #[builder(default, setter(with_capacity))]
+    text: String,
+    #[builder(default, setter(with_capacity), push = entry)]
+    index: HashMap<u32, usize>,
+}
+
+#[test]
+fn with_capacity() {
+    let mut builder = Batch::builder().lines_with_capacity(64).index_with_capacity(64);
+    for i in 0..64 {
+        builder = builder.line(i.to_string()).entry((i, i as usize));
+    }
+    let batch = builder.construct();
+    assert_eq!((batch.lines.len(), batch.lines.capacity()), (64, 64));
+    assert_eq!(batch.index[&63], 63);
+    assert!(batch.index.capacity() >= 64);
+    assert_eq!(batch.text.capacity(), 0);
+
+    // the setter of the field replaces the reservation like any other value
+    let batch = Batch::builder().lines_with_capacity(1024).lines(vec![]).text_with_capacity(8);
+    let batch = batch.construct();
+    assert_eq!((batch.lines.capacity(), batch.text.capacity()), (0, 8));
+}
+
+/// Only the setters and `construct()`.
+#[derive(TypesafeBuilder)]
+#[builder(minimal)]
+struct Bare {
+    name: String,
+    #[builder(default)]
+    retries: u8,
+}
+
+/// `Clone` and `Debug`, nothing else.
+#[derive(TypesafeBuilder)]
+#[builder(features(clone, debug))]
+struct Picked {
+    name: String,
+}
+
+#[test]
+fn features() {
+    let bare = Bare::builder().name("a".to_string()).retries(3).construct();
+    assert_eq!((bare.name.as_str(), bare.retries), ("a", 3));
+
+    let builder = Picked::builder().name("b".to_string());
+    assert_eq!(format!("{:?}", builder.clone()), format!("{:?}", builder));
+    assert_eq!(builder.construct().name, "b");
+}
+
+/// The fields of a `Lookup` that have been given so far.
+#[derive(Debug, Clone, PartialEq)]
+struct LookupPartial {
+    name: Option<String>,
+    table: Option<Vec<i32>>,
+}
+
+/// The laws hold for derived builders too, `into_builder` and `Clone` are generated.
+impl Buildable for Lookup {
+    type Builder = LookupBuilder<Set, Set>;
+    type Partial = LookupPartial;
+
+    fn into_builder(self) -> Self::Builder {
+        Lookup::into_builder(self)
+    }
+
+    fn construct(builder: Self::Builder) -> Self {
+        builder.construct()
+    }
+
+    fn into_partial(builder: Self::Builder) -> Self::Partial {
+        let Lookup { name, table } = builder.construct();
+        LookupPartial { name: Some(name), table: Some(table) }
+    }
+
+    fn try_from_partial(partial: Self::Partial) -> Result<Self, BuildError> {
+        match partial {
+            LookupPartial { name: Some(name), table: Some(table) } => {
+                Ok(Lookup::builder().name(name).table(table).construct())
+            }
+            LookupPartial { name, table } => {
+                let missing = [("name", name.is_none()), ("table", table.is_none())];
+                let missing = missing.iter().filter(|(_, missing)| *missing);
+                Err(BuildError::MissingFields(missing.map(|(field, _)| *field).collect()))
+            }
+        }
+    }
+}
+
+#[test]
+fn laws() {
+    check_laws(100, |seed| Lookup { name: seed.string(), table: seed.numbers() });
+    check_laws(1, |_| Lookup { name: String::new(), table: vec![] });
+
+    let partial = LookupPartial { name: None, table: Some(vec![]) };
+    let err = Lookup::try_from_partial(partial).unwrap_err();
+    assert_eq!(err, BuildError::MissingFields(vec!["name"]));
+}
+
+/// Two fields of the same type, set the wrong way round the types would still line up.
+#[derive(PartialEq, TypesafeBuilder)]
+#[builder(generate_usage_test)]
+struct Address {
+    #[builder(test_value = "example.com".to_string())]
+    host: String,
+    #[builder(into, test_value = "/index.html")]
+    path: String,
+    #[builder(default)]
+    port: u16,
+    #[builder(skip)]
+    hits: u32,
+}
+
+/// `construct()` mixes up `host` and `path`, the bug the usage test is there to catch.
+#[derive(PartialEq, TypesafeBuilder)]
+#[builder(generate_usage_test, __swap_fields(host, path))]
+struct Swapped {
+    #[builder(test_value = "example.com".to_string())]
+    host: String,
+    #[builder(test_value = "/index.html".to_string())]
+    path: String,
+}
+
+#[test]
+fn usage_test_catches_swapped_fields() {
+    address_builder_sets_every_field();
+    let address = Address::builder().host("a".into()).path("/").construct();
+    assert_eq!((address.host.as_str(), address.path.as_str(), address.port), ("a", "/", 0));
+    assert_eq!(address.hits, 0);
+
+    let err = std::panic::catch_unwind(swapped_builder_sets_every_field).unwrap_err();
+    let message = err.downcast_ref::<&str>().unwrap();
+    assert!(
+        message.contains("a setter of `SwappedBuilder` wrote to the wrong field"),
+        "{}",
+        message
+    );
+    let swapped = Swapped::builder().host("a".into()).path("b".into()).construct();
+    assert_eq!((swapped.host.as_str(), swapped.path.as_str()), ("b", "a"));
+}
+
+/// Every field has a default, so the struct's `Default` is the empty builder's value.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(derive_default, diff)]
+struct Tuning<T: Default> {
+    #[builder(default)]
+    workers: u32,
+    #[builder(default = Duration::from_secs(30))]
+    timeout: Duration,
+    #[builder(default)]
+    extra: T,
+    #[builder(skip, default = 3)]
+    retries: u8,
+}
+
+#[test]
+fn derive_default() {
+    let tuning = Tuning::<String>::default();
+    assert_eq!(tuning, TuningBuilder::<String, Unset, Unset, Unset>::new().construct());
+    assert_eq!(
+        tuning,
+        Tuning { workers: 0, timeout: Duration::from_secs(30), extra: String::new(), retries: 3 }
+    );
+    let tuning = Tuning::builder().workers(4).extra(vec![1]).construct();
+    assert_eq!(Tuning::default().diff(&tuning).to_string(), "workers: 0 -> 4\nextra: [] -> [1]\n");
+    assert_eq!((tuning.workers, tuning.timeout.as_secs(), tuning.extra), (4, 30, vec![1]));
+}
+
+/// Setters as traits, for forwarding layers that don't name the builder.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(strict, setter_traits)]
+struct Forwarded {
+    port: u16,
+    #[builder(into)]
+    host: String,
+    #[builder(as_ref = str, rename = label)]
+    name: String,
+}
+
+/// An adapter that knows nothing about `ForwardedBuilder` or its states.
+fn forward<Bld, VH, VL>(
+    builder: Bld,
+    host: VH,
+    label: VL,
+) -> <Bld::Out as ForwardedBuilderSetLabel<VL>>::Out
+where
+    Bld: ForwardedBuilderSetHost<VH>,
+    Bld::Out: ForwardedBuilderSetLabel<VL>,
+{
+    builder.set_host(host).set_label(label)
+}
+
+#[test]
+fn setter_traits() {
+    let built = forward(Forwarded::builder().port(80), "localhost", "web").construct();
+    assert_eq!(built, Forwarded { port: 80, host: "localhost".into(), name: "web".into() });
+
+    // the exact type of a plain field, owned values where the setter borrows
+    let builder = ForwardedBuilderSetPort::set_port(Forwarded::builder(), 8080);
+    let built = forward(builder, String::from("example.com"), String::from("api")).construct();
+    assert_eq!(
+        (built.port, built.host.as_str(), built.name.as_str()),
+        (8080, "example.com", "api")
+    );
+}
+
+/// Every field marked `from_str_default` can be overridden through `BUILDER_<FIELD>`.
+#[derive(Debug, PartialEq, TypesafeBuilder)]
+#[builder(generate_usage_test)]
+struct Twelve {
+    #[builder(test_value = "app".to_string())]
+    name: String,
+    #[builder(from_str_default, test_value = 2)]
+    derived_workers: u32,
+    #[builder(from_str_default, shared)]
+    derived_region: String,
+}
+
+// the environment is shared between the test threads, no other test uses these variables
+#[test]
+fn from_str_default() {
+    std::env::remove_var("BUILDER_DERIVED_WORKERS");
+    std::env::set_var("BUILDER_DERIVED_REGION", "eu");
+    let twelve = Twelve::builder().name("app".into()).try_construct().unwrap();
+    assert_eq!((twelve.derived_workers, twelve.derived_region.as_str()), (0, "eu"));
+
+    std::env::set_var("BUILDER_DERIVED_WORKERS", "8");
+    let twelve = Twelve::builder().name("app".into()).try_construct().unwrap();
+    assert_eq!(twelve.derived_workers, 8);
+    let twelve = Twelve::builder().name("app".into()).derived_workers(1).try_construct().unwrap();
+    assert_eq!(twelve.derived_workers, 1);
+    #[cfg(not(feature = "no-panic"))]
+    assert_eq!(Twelve::builder().name("app".into()).construct().derived_workers, 8);
+
+    std::env::set_var("BUILDER_DERIVED_WORKERS", "many");
+    let err = Twelve::builder().name("app".into()).try_construct().unwrap_err();
+    assert_eq!(err.to_string(), "can't parse BUILDER_DERIVED_WORKERS=\"many\"");
+    assert_eq!((err.kind(), err.fields()), (ErrorKind::ParseFailed, vec!["derived_workers"]));
+    std::env::remove_var("BUILDER_DERIVED_WORKERS");
+    std::env::remove_var("BUILDER_DERIVED_REGION");
+}
+
+/// Turned on and off by the test, only `guarded_setters` reads it.
+static UPLOADS: AtomicBool = AtomicBool::new(false);
+
+fn uploads_enabled() -> bool {
+    UPLOADS.load(Ordering::Relaxed)
+}
+
+fn key_allowed(key: &str) -> bool {
+    !key.is_empty()
+}
+
+/// Fields behind a runtime flag, and one the value decides on.
+#[derive(Debug, TypesafeBuilder)]
+struct Upload {
+    path: String,
+    #[builder(guard = "uploads_enabled")]
+    compression: u8,
+    #[builder(into, guard = "key_allowed")]
+    key: String,
+}
+
+#[test]
+fn guarded_setters() {
+    UPLOADS.store(false, Ordering::Relaxed);
+
+    // denied, the error hands back the builder with the path still set
+    let err = Upload::builder().path("out".into()).compression(9).unwrap_err();
+    assert_eq!(err.field, "compression");
+    assert_eq!(err.to_string(), "the field `compression` is disabled");
+    let builder = err.into_builder();
+    assert_eq!(builder.set_fields().collect::<Vec<_>>(), ["path"]);
+
+    // denied by the value, whatever the flag
+    let builder = builder.key("").unwrap_err().into_builder();
+
+    // allowed on the retry once the flag is on
+    UPLOADS.store(true, Ordering::Relaxed);
+    let upload = builder.compression(9).unwrap().key("secret").unwrap().construct();
+    assert_eq!(
+        (upload.path.as_str(), upload.compression, upload.key.as_str()),
+        ("out", 9, "secret")
+    );
+    UPLOADS.store(false, Ordering::Relaxed);
+}
+
+/// Compared field by field before a configuration change is applied.
+#[derive(Debug, TypesafeBuilder)]
+#[builder(diff)]
+struct Connection {
+    host: String,
+    port: u16,
+    #[builder(sensitive)]
+    password: String,
+    #[builder(skip, default = 1)]
+    generation: u32,
+}
+
+#[test]
+fn diff() {
+    let builder = |host: &str, port, password: &str| {
+        Connection::builder().host(host.into()).port(port).password(password.into())
+    };
+    assert!(builder("a", 80, "secret").diff(&builder("a", 80, "secret")).is_empty());
+
+    let diff = builder("a", 80, "secret").diff(&builder("a", 8080, "secret"));
+    assert_eq!(diff.fields, [FieldDiff { field: "port", old: "80".into(), new: "8080".into() }],);
+    let (old, new) =
+        (builder("a", 80, "secret").construct(), builder("a", 8080, "secret").construct());
+    assert_eq!(diff, old.diff(&new));
+
+    let diff = builder("a", 80, "old").diff(&builder("b", 443, "new"));
+    assert_eq!(
+        diff.to_string(),
+        "host: \"a\" -> \"b\"\nport: 80 -> 443\npassword: <redacted> -> <redacted>\n",
+    );
+    let mut newer = builder("a", 80, "old").construct();
+    newer.generation = 2;
+    assert_eq!(
+        old.diff(&newer).to_string(),
+        "password: <redacted> -> <redacted>\ngeneration: 1 -> 2\n"
+    );
+}
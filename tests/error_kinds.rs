@@ -0,0 +1,37 @@
+//! Matching on errors the way a crate depending on this one has to: `ErrorKind` and `BuildError`
+//! are `non_exhaustive`, so outside of this crate every match needs a catch-all arm.
+#![cfg(feature = "std")]
+
+use typestuff::dynamic::{DynItemBuilder, Value};
+use typestuff::error::{BuildError, ErrorKind};
+use typestuff::partial::ItemPartial;
+
+/// Where an alert about `err` would go.
+fn route(err: &BuildError) -> &'static str {
+    match err.kind() {
+        ErrorKind::MissingField | ErrorKind::UnknownField | ErrorKind::TypeMismatch => "caller",
+        ErrorKind::ParseFailed => "deployment",
+        _ => "unknown",
+    }
+}
+
+/// The fields to report for `err`, by variant.
+fn reported(err: &BuildError) -> Vec<String> {
+    match err {
+        BuildError::MissingFields(fields) => fields.iter().map(|field| field.to_string()).collect(),
+        BuildError::UnknownField(field) => vec![field.clone()],
+        _ => err.fields().iter().map(|field| field.to_string()).collect(),
+    }
+}
+
+#[test]
+fn matching_downstream() {
+    let err = ItemPartial::default().try_build().unwrap_err();
+    assert_eq!((route(&err), reported(&err)), ("caller", vec!["a".to_string(), "b".to_string()]));
+
+    let err = DynItemBuilder::new().set("c", Value::Text("c".into())).unwrap_err();
+    assert_eq!((route(&err), reported(&err)), ("caller", vec!["c".to_string()]));
+
+    let err = BuildError::Invalid { field: "a", reason: "empty".into() };
+    assert_eq!((route(&err), reported(&err)), ("unknown", vec!["a".to_string()]));
+}
@@ -0,0 +1,291 @@
+//! Leaks and double drops, counted instead of believed. Values of `Counted` record their drops,
+//! so the generic builders can be checked to drop every value exactly once. `Item`'s fields are a
+//! `String` and a `Vec`, for those the allocator of this test binary counts the allocations that
+//! are alive on the current thread: every path through `ItemBuilder` has to free what it
+//! allocated, a leak leaves the count above zero and a double free takes it below.
+#![cfg(feature = "std")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use typestuff::boxed::BoxedItemBuilder;
+use typestuff::drop_order::{PairBuilder, ScopedBuilder};
+use typestuff::dynamic::DynItemBuilder;
+use typestuff::groups::ConnectionBuilder;
+use typestuff::lazy::LazyItemBuilder;
+use typestuff::pending::AsyncItemBuilder;
+use typestuff::slot::Slot;
+use typestuff::validate::rules;
+use typestuff::{Item, ItemBuilder, Set, Unset};
+
+/// The system allocator, counting the live allocations of each thread. Tests run on threads of
+/// their own, so they don't see each other's allocations.
+struct Counting;
+
+thread_local! {
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+fn count(delta: isize) {
+    // the thread-local is gone while the thread shuts down, nothing is checked that late
+    let _ = LIVE.try_with(|live| live.set(live.get() + delta));
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            count(1);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(-1);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Run `f` and assert that it freed exactly what it allocated.
+fn balanced<F: FnOnce()>(what: &str, f: F) {
+    let before = LIVE.with(Cell::get);
+    f();
+    let after = LIVE.with(Cell::get);
+    assert_eq!(after - before, 0, "{} leaked or freed twice ({:+})", what, after - before);
+}
+
+/// Counts its drops in the cell it borrows.
+struct Counted<'c>(&'c Cell<usize>);
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn a() -> String {
+    String::from("allocated")
+}
+
+fn b() -> Vec<i32> {
+    vec![1, 2, 3]
+}
+
+#[test]
+#[should_panic(expected = "forgotten leaked")]
+fn leaks_are_caught() {
+    balanced("forgotten", || std::mem::forget(a()));
+}
+
+#[test]
+fn item_builder_drops() {
+    balanced("empty builder", || drop(ItemBuilder::new()));
+    balanced("a set", || drop(ItemBuilder::new().a(a())));
+    balanced("b set", || drop(ItemBuilder::new().b(b())));
+    balanced("both set", || drop(ItemBuilder::new().a(a()).b(b())));
+    balanced("a set again", || drop(ItemBuilder::new().a(a()).a(a())));
+    balanced("b set again", || drop(ItemBuilder::new().b(b()).b(b())));
+    balanced("unset", || drop(ItemBuilder::new().a(a()).b(b()).unset_a().unset_b()));
+    balanced("clone", || {
+        let builder = ItemBuilder::new().a(a());
+        drop(builder.clone());
+        drop(builder);
+    });
+}
+
+#[test]
+fn item_builder_constructs() {
+    balanced("construct", || drop(ItemBuilder::new().a(a()).b(b()).construct()));
+    balanced("round trip", || drop(ItemBuilder::new().a(a()).b(b()).construct().into_builder()));
+    balanced("defaults", || drop(ItemBuilder::new().b(b()).finish_with_defaults()));
+    balanced("partial", || drop(ItemBuilder::new().a(a()).into_partial()));
+    balanced("failed try_construct", || {
+        let (builder, err) = ItemBuilder::new().a(a()).try_construct().unwrap_err();
+        drop(err);
+        drop(builder.b(b()).try_construct().unwrap());
+    });
+    balanced("rejected value", || {
+        let err = ItemBuilder::new().a(a()).validated_b(vec![10], rules::all_in(0..=9));
+        drop(err.unwrap_err().into_builder());
+    });
+    balanced("dynamic round trip", || {
+        let dynamic = DynItemBuilder::from(ItemBuilder::new().a(a()));
+        drop(dynamic.into_typed::<Set, Set>().unwrap_err().into_typed::<Set, Unset>());
+    });
+    balanced("rejected combination", || {
+        let builder = ItemBuilder::new().a(a()).b(b());
+        drop(builder.construct_validated(|_, _| Err(())).unwrap_err());
+    });
+}
+
+#[test]
+fn item_builder_merges() {
+    balanced("merge", || {
+        let merged = ItemBuilder::new().a(a()).b(b()) | ItemBuilder::new().a(a());
+        drop(merged.construct());
+    });
+    balanced("intersect", || drop(ItemBuilder::new().a(a()).b(b()) & ItemBuilder::new().b(b())));
+    balanced("masked", || drop(typestuff::mask::MaskedItemBuilder::new().a(a()).a(a())));
+    balanced("batch", || drop(ItemBuilder::build_many(3, |_, builder| builder.a(a()).b(b()))));
+    balanced("one-shot", || drop(Item::build_with(|builder| builder.b(b()).a(a()))));
+}
+
+#[test]
+fn exclusive_groups_clear() {
+    balanced("switched to token", || {
+        let builder = ConnectionBuilder::new().username(a()).password(a()).token(a());
+        drop(builder.host(a()).construct());
+    });
+    balanced("switched back", || drop(ConnectionBuilder::new().token(a()).password(a())));
+}
+
+#[test]
+fn slots_drop_once() {
+    let drops = Cell::new(0);
+
+    drop(Slot::<Counted, Unset>::empty());
+    assert_eq!(drops.get(), 0);
+
+    // writing over a value drops it, writing into an empty slot doesn't
+    let slot = Slot::empty().write(Counted(&drops)).write(Counted(&drops));
+    assert_eq!(drops.get(), 1);
+    let slot = slot.clear();
+    assert_eq!(drops.get(), 2);
+
+    let (value, slot) = slot.write(Counted(&drops)).take();
+    drop(slot);
+    assert_eq!(drops.get(), 2);
+    drop(value);
+    assert_eq!(drops.get(), 3);
+
+    // an unset slot drops what it is handed, a set one keeps it
+    drop(Slot::<_, Unset>::from_option(Some(Counted(&drops))));
+    assert_eq!(drops.get(), 4);
+    let slot = Slot::<_, Set>::from_option(Some(Counted(&drops)));
+    assert_eq!(drops.get(), 4);
+    drop(slot.into_option());
+    assert_eq!(drops.get(), 5);
+}
+
+#[test]
+fn generic_builders_drop_once() {
+    let drops = Cell::new(0);
+
+    drop(PairBuilder::<Counted, Counted, _, _>::new());
+    assert_eq!(drops.get(), 0);
+
+    drop(PairBuilder::new().left(Counted(&drops)).right(Counted(&drops)).left(Counted(&drops)));
+    assert_eq!(drops.get(), 3);
+
+    let pair = PairBuilder::new().right(Counted(&drops)).left(Counted(&drops)).construct();
+    assert_eq!(drops.get(), 3);
+    drop(pair);
+    assert_eq!(drops.get(), 5);
+
+    let builder = ScopedBuilder::<Counted, Counted, _, _>::new().guard(Counted(&drops));
+    drop(builder);
+    assert_eq!(drops.get(), 6);
+}
+
+/// Counts its drops like `Counted`, and panics while being dropped if asked to.
+struct Bomb<'c>(&'c Cell<usize>, bool);
+
+impl Drop for Bomb<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+        if self.1 {
+            panic!("destructor failed");
+        }
+    }
+}
+
+/// Panics when converted into a field, after the builder has been moved into the setter.
+struct Faulty;
+
+impl From<Faulty> for String {
+    fn from(_: Faulty) -> String {
+        panic!("conversion failed")
+    }
+}
+
+/// The panics injected by the tests.
+const INJECTED: &[&str] = &["warm-up", "destructor failed", "conversion failed"];
+
+/// Run `f`, which has to panic. The default hook would print and capture a backtrace, which
+/// allocates, so the injected panics are silenced and everything else is passed on.
+fn unwinds<R, F: FnOnce() -> R>(f: F) {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let message = info.payload().downcast_ref::<&str>();
+            if !message.is_some_and(|message| INJECTED.contains(message)) {
+                default(info);
+            }
+        }));
+    });
+    assert!(panic::catch_unwind(AssertUnwindSafe(f)).is_err());
+}
+
+#[test]
+fn panicking_setters_drop_once() {
+    // the first panic of a thread allocates for good, the counting starts after it
+    unwinds(|| panic!("warm-up"));
+
+    balanced("a conversion unwinding", || {
+        let builder = ItemBuilder::new().a(a()).b(b());
+        unwinds(|| builder.coerce_a(Faulty));
+    });
+    balanced("a conversion unwinding on an empty slot", || {
+        unwinds(|| ItemBuilder::new().b(b()).coerce_a(Faulty));
+    });
+
+    // the old value panics in its destructor, the new one has already been written
+    let drops = Cell::new(0);
+    unwinds(|| Slot::new(Bomb(&drops, true)).write(Bomb(&drops, false)));
+    assert_eq!(drops.get(), 2);
+
+    let drops = Cell::new(0);
+    unwinds(|| Slot::new(Bomb(&drops, true)).clear());
+    assert_eq!(drops.get(), 1);
+}
+
+#[test]
+fn abandoned_thunks_drop_their_captures() {
+    balanced("an abandoned thunk", || {
+        let (captured, other) = (a(), b());
+        drop(LazyItemBuilder::new().a_with(move || captured).b_with(move || other));
+    });
+    balanced("a replaced thunk", || {
+        let captured = a();
+        let builder = LazyItemBuilder::new().a_with(move || captured).a(a());
+        drop(builder.b_with(b).construct());
+    });
+}
+
+#[test]
+fn abandoned_futures_drop_their_captures() {
+    balanced("an abandoned future", || {
+        let (captured, other) = (a(), b());
+        drop(AsyncItemBuilder::new().a_from(async move { captured }).b_from(async { other }));
+    });
+    balanced("a replaced future", || {
+        let captured = a();
+        drop(AsyncItemBuilder::new().a_from(async move { captured }).a(a()).b(b()));
+    });
+}
+
+#[test]
+fn boxed_builder_drops() {
+    balanced("an empty boxed builder", || drop(BoxedItemBuilder::new()));
+    balanced("a boxed builder with a field", || drop(BoxedItemBuilder::new().a(a()).a(a())));
+    balanced("a boxed builder constructing", || {
+        drop(BoxedItemBuilder::new().b(b()).unset_b().b(b()).a(a()).construct());
+    });
+    balanced("a boxed builder unboxed", || drop(BoxedItemBuilder::new().a(a()).unboxed()));
+}
@@ -0,0 +1,60 @@
+//! The part of the crate that is left without `std`: the slots, the const builder and the drop
+//! order builders, used from outside like a `no_std` crate depending on it would.
+//! `cargo test --no-default-features` runs these, with `std` the unit tests cover the same.
+#![cfg(not(feature = "std"))]
+
+use std::cell::RefCell;
+
+use typestuff::config::{Config, ConfigBuilder, DEFAULT_CONFIG};
+use typestuff::drop_order::{PairBuilder, ScopedBuilder};
+use typestuff::slot::Slot;
+use typestuff::{Set, Unset};
+
+/// Records its name in the log when dropped.
+struct Recorder<'l>(&'static str, &'l RefCell<Vec<&'static str>>);
+
+impl Drop for Recorder<'_> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+#[test]
+fn slots() {
+    let log = RefCell::new(Vec::new());
+    let empty: Slot<Recorder, Unset> = Slot::empty();
+    assert!(empty.as_option().is_none());
+
+    let slot: Slot<_, Set> = empty.write(Recorder("first", &log));
+    assert_eq!(slot.get().0, "first");
+    // overwriting drops the old value, clearing the new one
+    let slot = slot.write(Recorder("second", &log)).clear();
+    assert_eq!(*log.borrow(), ["first", "second"]);
+
+    let (value, slot) = slot.write(Recorder("third", &log)).take();
+    assert_eq!(value.0, "third");
+    assert!(slot.into_option().is_none());
+    drop(value);
+    assert_eq!(*log.borrow(), ["first", "second", "third"]);
+}
+
+#[test]
+fn const_builder() {
+    const BUILT: Config = ConfigBuilder::new().retries(1).timeout(2).construct();
+    assert_eq!(BUILT, Config { timeout: 2, retries: 1 });
+    assert_eq!(DEFAULT_CONFIG, Config::builder().timeout(30).retries(3).construct());
+}
+
+#[test]
+fn drop_order() {
+    let log = RefCell::new(Vec::new());
+    drop(PairBuilder::new().right(Recorder("right", &log)).left(Recorder("left", &log)));
+    assert_eq!(*log.borrow(), ["left", "right"]);
+
+    log.borrow_mut().clear();
+    drop(ScopedBuilder::new().guard(Recorder("guard", &log)).resource(Recorder("resource", &log)));
+    assert_eq!(*log.borrow(), ["resource", "guard"]);
+
+    let pair = PairBuilder::new().left(1).right("r").construct();
+    assert_eq!((pair.left, pair.right), (1, "r"));
+}
@@ -0,0 +1,6 @@
+use typestuff::{Item, ItemBuilder};
+
+fn main() {
+    let item = ItemBuilder::new().b(vec![1]).a("first".into()).a("second".into()).construct();
+    assert_eq!(item, Item { a: "second".into(), b: vec![1] });
+}
@@ -0,0 +1,42 @@
+//! The descriptors of derived builders, registered next to the ones of this crate.
+#![cfg(all(feature = "derive", feature = "registry"))]
+
+use typestuff::registry::{self, BuilderDescriptor};
+use typestuff::TypesafeBuilder;
+
+#[derive(TypesafeBuilder)]
+pub struct Server {
+    pub host: String,
+    #[builder(default)]
+    pub port: u16,
+}
+
+#[derive(TypesafeBuilder)]
+#[builder(const_check = "typestuff::policy::secrets_are_sensitive")]
+struct ApiCredentials {
+    user: String,
+    #[builder(sensitive)]
+    token: String,
+}
+
+// a single test, the registry is shared between the test threads
+#[test]
+fn derived_builders_register() {
+    registry::init();
+    registry::register(&SERVER_BUILDER);
+    registry::register(&API_CREDENTIALS_BUILDER);
+    registry::register(&SERVER_BUILDER);
+
+    let names: Vec<_> = registry::iter().map(|descriptor| descriptor.name).collect();
+    assert_eq!(names, ["ItemBuilder", "ExportBuilder", "ServerBuilder", "ApiCredentialsBuilder"]);
+
+    let server: &BuilderDescriptor = registry::iter().nth(2).unwrap();
+    assert_eq!(server.fields, ServerBuilder::FIELDS);
+    assert!(server.has_defaults());
+    let credentials = &API_CREDENTIALS_BUILDER;
+    assert!(!credentials.has_defaults());
+    assert!(credentials.fields[1].sensitive);
+
+    let credentials = ApiCredentials::builder().token("t".into()).user("u".into()).construct();
+    assert_eq!((credentials.user.as_str(), credentials.token.as_str()), ("u", "t"));
+}
@@ -0,0 +1,3482 @@
+//! `#[derive(TypesafeBuilder)]`, generating the same kind of builder as the hand-written
+//! `ItemBuilder` of `typestuff` for any struct with named fields, or enum with such variants:
+//!
+//! ```ignore
+//! #[derive(TypesafeBuilder)]
+//! pub struct Config {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! let config = Config::builder().port(80).host("localhost".into()).construct();
+//! ```
+//!
+//! The builder `ConfigBuilder` has one `FieldState` parameter per field and stores each field in
+//! a `Slot`, setters move the slots into a builder with that parameter changed and `construct()`
+//! only exists once every parameter is `Set`. The slots drop whatever has been set, so the
+//! builder needs no destructor of its own.
+//!
+//! An abandoned builder drops the fields that are set in declaration order, the order its slots
+//! are declared in. `#[builder(drop_order = "reverse")]` on the type declares them the other way
+//! round, for targets like a guard that has to outlive the resource it protects. Once
+//! `construct()` moved the fields into the target, the target's own order applies.
+//!
+//! Fields marked with `#[builder(default)]` are optional: `construct()` is available whatever
+//! their state, unset ones are filled with `Default::default()`. `#[builder(default = expr)]`
+//! fills them with `expr` instead, evaluated only if the field hasn't been set.
+//!
+//! `#[builder(from_str_default)]` makes a field optional with a default from the environment:
+//! unset, `construct()` parses the variable `BUILDER_<FIELD>` with `FromStr`, like
+//! `BUILDER_WORKERS` for `workers`, and falls back to `Default::default()` if it doesn't exist.
+//! A variable that can't be parsed panics, `try_construct()` returns a `BuildError::InvalidEnv`
+//! instead. With the `no-panic` feature of `typestuff` only `try_construct()` is generated.
+//!
+//! Setters take the field's type as it is. With `#[builder(into)]` the setter of that field takes
+//! anything that converts `Into` it instead, so `#[builder(into)] host: String` can be set with
+//! `.host("localhost")`. Several arguments can be combined, like `#[builder(default, into)]`.
+//! On a `Cow<'a, str>` field `into` stores borrowed strings as they are: `.name("static")`
+//! allocates nothing, `.name(owned)` moves the `String` in. `#[builder(as_ref = str)]` makes the
+//! setter take anything that is `AsRef<str>` instead, owning it with `ToOwned`.
+//!
+//! `#[builder(strict)]` on the struct makes setting a field twice a compile error: the setters
+//! are only available while their field is `Unset`. Configuration where a second assignment is
+//! a bug can opt into this, `unset_<field>()` and push setters still work as usual.
+//!
+//! Builders are `Clone` if the types of all fields are, so a partly configured builder can be
+//! forked. Only the fields that are set are cloned, the clone is in the same state.
+//!
+//! They are `Debug` if the types of all fields are, showing the values of the fields that are set
+//! and `<unset>` for the others.
+//!
+//! `FIELDS` describes the fields with the `FieldInfo` table of `typestuff::reflection`, the same
+//! the hand-written builders have, so `const_check!`, the registry and the runtime helpers work
+//! on derived builders too. `set_fields()` and `missing_fields()` tell the fields apart at
+//! runtime, to report something like "missing: retries, timeout", and `is_complete()` whether
+//! `construct()` is available. The states are read from the type, so they can't disagree.
+//!
+//! `#[builder(sensitive)]` flags a field holding a secret in `FIELDS`. A policy of
+//! `typestuff::policy` runs on the table while compiling with
+//! `#[builder(const_check = "typestuff::policy::secrets_are_sensitive")]` on the type, a builder
+//! breaking it doesn't compile and the error carries the policy's message. Any `const fn` taking
+//! the table works, the path is resolved where the type is defined. Generic types have no single
+//! table to check, call `const_check!` on an instantiation of their builder instead.
+//!
+//! With the `registry` feature of `typestuff` every builder of a type without generic parameters
+//! comes with a `BuilderDescriptor` static named after it, `SERVER_BUILDER` for `ServerBuilder`,
+//! which `typestuff::registry::register(&SERVER_BUILDER)` adds to the registry. Without the
+//! feature there is no static.
+//!
+//! `#[builder(into_builder)]` on a struct adds the way back: `value.into_builder()` moves the
+//! fields into a complete builder, to change some of them and construct a new value. It is
+//! opt-in since it takes the value apart, which a struct implementing `Drop` doesn't allow.
+//!
+//! Fields marked with `#[builder(phase = 2)]` are set in a second phase, like the resources of
+//! `typestuff::job` that have to stay on their thread. The derive declares a `JobSpec` struct of
+//! the other fields next to `Job`: `Job::builder()` starts a `JobSpecBuilder`, which has no
+//! setters for the phase-2 fields and constructs the spec, and `JobBuilder::from_spec(spec)`
+//! continues with the phase-2 fields and constructs the `Job`. The spec and its builder only hold
+//! the phase-1 fields, so they are `Send` whenever those are. Two-phase structs can't be generic
+//! and don't take the struct attributes that build or take apart the whole value at once.
+//!
+//! `#[builder(build_with)]` on a struct adds `Config::build_with(|b| b.host(..).port(80))`,
+//! building a value without naming the builder. The closure has to return a complete builder, so
+//! leaving out a required field is a type error in the closure.
+//!
+//! `#[builder(expires_in = Duration::from_secs(60))]` on a struct makes its builders expire, for
+//! values holding credentials or signed URLs. The deadline is taken when the builder is started
+//! and `construct()` becomes fallible: it returns `Result<Config, BuildError>`, with
+//! `BuildError::Expired` once the deadline has passed. The documentation of the builder says so.
+//! `ConfigBuilder::new_with_clock(&clock)` and `construct_with_clock(&clock)` take the time from
+//! a `typestuff::clock::Clock` instead of the system, so tests don't have to sleep. An expiring
+//! builder has no `Complete` to be nested with, and can't be combined with `build_with` or
+//! `require_ticket`, which expect a terminal that can't fail.
+//!
+//! `#[builder(require_ticket)]` on a struct hands out the builder together with a
+//! `typestuff::ticket::BuildTicket`: `Transfer::builder()` returns `(builder, ticket)` and the
+//! only terminal is `construct_with_ticket(ticket)`, there is no `construct()` to forget the ticket
+//! with. Such builders have no `new()` or `Default` and can't be nested, and neither
+//! `into_builder` nor `build_with` can be combined with it since they would bypass the ticket.
+//!
+//! `#[builder(rename = with_port)]` names the setters of a field `with_port`, `unset_with_port`
+//! and so on, the field keeps its name. `#[builder(skip)]` leaves a field out of the builder: it
+//! has no slot, no state and no setters, and `construct()` fills it with `Default::default()`, or
+//! with `expr` given `#[builder(skip, default = expr)]`.
+//!
+//! `#[builder(preset = ServerDefaults(host, port))]` on a struct generates a plain struct
+//! `ServerDefaults` with those fields, and `builder.apply(defaults)` sets all of them in one call.
+//! A base preset can be applied first and refined by the setters afterwards, or the other way
+//! round. Presets are only supported on structs without generic parameters.
+//!
+//! `#[builder(migrate_from(ItemV1Builder, fields(a, b), map(old = "b", new = "values"),
+//! transform(field = "a", with = "upgrade_a")))]` on a struct moves a builder of the previous
+//! version over: `ItemV2Builder::from_item_v1(v1)` takes an `ItemV1Builder` in any state, moves
+//! the fields of the same name, `b` into `values` and `a` through `upgrade_a`. Fields only the
+//! new version has start out unset, and the states of the result say which fields came over
+//! set. `fields(...)` lists the fields of the old builder in order, checked against its `FIELDS`
+//! while compiling. Old fields with nowhere to go are dropped and listed in the documentation of
+//! `from_item_v1`, with `strict` among the arguments dropping one is a compile error instead.
+//!
+//! `#[builder(aliases)]` on a struct names the states that come up the most, so a builder that
+//! is half done can be stored in a field or passed around without spelling out every state:
+//! `EmptyServerBuilder` and `CompleteServerBuilder`, and per field `ServerBuilderWithHost` with
+//! only `host` set and `ServerBuilderWithoutHost` with everything but `host` set. Like presets,
+//! aliases are only supported on structs without generic parameters.
+//!
+//! Every field also gets `unset_<field>()`, dropping the value if there is one and moving the
+//! field back to `Unset`.
+//!
+//! Collection fields can be filled one element at a time: `#[builder(push = tag)]` on a field
+//! `tags: Vec<String>` adds a setter `tag` appending to the list. The first call starts from an
+//! empty collection and sets the field, later calls extend it. Anything that is `Default` and
+//! `Extend`s its own items works, so a `HashMap` field takes `(key, value)` pairs.
+//!
+//! `#[builder(setter(with_capacity))]` on a `Vec`, `String`, `HashMap` or any other type with a
+//! `with_capacity` constructor adds `tags_with_capacity(n)`, setting the field to an empty
+//! collection with room for `n` elements so the push setter doesn't reallocate until it is used
+//! up. The reservation sets the field like any other value, the field's setter replaces it.
+//!
+//! `#[builder(template = duration)]` adds the setters of `typestuff::presets` next to the one of
+//! the field: `timeout_secs(30)` and `timeout_ms(500)` on a `Duration`, `root_path("/srv")` with
+//! `template = path` on a `PathBuf`, and with `template = addr` the try-setter
+//! `try_listen("0.0.0.0:80")`, parsing the string and returning a `BuildError::Invalid` of the
+//! field if it doesn't. They forward to the field's setter, so they move the same state.
+//!
+//! `#[builder(shared)]` stores a field in an `Arc`, so builders cloned from each other or set from
+//! the same `Arc` share one value instead of holding a copy each. The setter takes the value or
+//! an `Arc` of it, and `construct()` moves the value out if the builder held the last reference
+//! and clones it otherwise. A field declared as an `Arc` is stored as it is, the value stays
+//! shared with the target. The derive only sees the path of the type: `Arc<T>` and the paths of
+//! std like `::std::sync::Arc<T>` are recognized, an alias or a renamed import of `Arc` isn't.
+//! `#[builder(shared = as_is)]` says the type is an `Arc` already and `#[builder(shared = wrap)]`
+//! that it isn't. A type whose path ends in `Arc` but isn't std's, like `triomphe::Arc<T>`, is
+//! wrapped with a warning asking for one of them.
+//!
+//! `#[builder(guard = "flags::compression_enabled")]` puts a field behind a runtime flag: its
+//! setter asks the function first and returns `Result<builder, GuardError>`, handing the builder
+//! back unchanged in the `GuardError` if the guard says no, so the call can be retried once the
+//! flag is on. The guard takes no arguments, or the value about to be set by reference or as
+//! something it borrows as, like `fn(&str) -> bool` for a `String`. The typestate tracks the field
+//! like any other. Nothing else sets a guarded field, so it can't have push or template setters
+//! or be nested, or be part of a preset. See `typestuff::guard`.
+//!
+//! `#[builder(diff)]` on a struct adds `old.diff(&new)` to the struct and to its complete
+//! builder, returning a `typestuff::diff::Diff` of the fields that differ by `PartialEq`, with
+//! their values rendered by `Debug`. Fields marked `#[builder(sensitive)]` show up as changed with
+//! both values redacted, so a diff of credentials can be shown to an operator.
+//!
+//! `#[builder(generate_usage_test)]` on a struct generates a `#[cfg(test)]` test calling every
+//! setter once and comparing the constructed value with a struct literal of the same values, so
+//! a setter writing to the wrong slot fails it even where the types line up, like two `String`
+//! fields. A field is set to its `#[builder(test_value = expr)]`, or to `Default::default()`
+//! without one. Fields of the same type need distinct test values for a mix-up to show. The
+//! test is named after the struct, `server_builder_sets_every_field` for `Server`, and the struct
+//! has to be `PartialEq`.
+//!
+//! `#[builder(derive_default)]` on a struct implements `Default` for it through the builder:
+//! `Config::default()` is what `ConfigBuilder::new().construct()` returns, so the two can't
+//! drift apart. Every field needs a `#[builder(default)]` or `#[builder(default = expr)]` for
+//! this, a field without one is a compile error pointing at it.
+//!
+//! `#[builder(report)]` on the type lists what the derive generated for it, in a warning at the
+//! argument: the size of the expansion in tokens and the types, traits, functions and impls in
+//! it. It is there to see what `minimal` and `features(...)` save, and would fail a build denying
+//! warnings, so it is meant to be removed again.
+//!
+//! Besides the setters and `construct()`, every builder gets `Clone`, `Debug` and `Default`,
+//! `unset_<field>()`, the reflection of `FIELDS` and `missing_fields()`, the `nested` traits and a
+//! registry descriptor. `#[builder(minimal)]` leaves all of these out, and
+//! `#[builder(features(clone, debug))]` only generates the ones named, out of `clone`, `debug`,
+//! `default`, `unset`, `reflection`, `nested` and `registry`. The builder's documentation lists
+//! the features it was generated with.
+//!
+//! `#[builder(setter_traits)]`, or `setter_traits` among the features, adds a trait per setter
+//! like those of `typestuff::setters`: `ServerBuilderSetHost<V>` with `set_host(value)`,
+//! implemented for the builder in every state and every `V` the setter takes. Code forwarding
+//! values to a builder can be written once against the traits instead of against every state,
+//! `fn forward<B: ServerBuilderSetHost<V>, V>(b: B, v: V) -> B::Out`. The traits are off by
+//! default, a trait and an impl per field make the builder slower to compile.
+//!
+//! A field whose type has a derived builder as well can be configured in place: with
+//! `#[builder(nested)] server: Server` the setter `server_with(|b| b.host("a").port(80))` hands
+//! the closure an empty `ServerBuilder` and only accepts it back complete, so leaving out a field
+//! of `Server` is a compile error at the call. The derive implements `typestuff::nested`'s traits
+//! for this, hand-written builders implementing them can be nested as well.
+//!
+//! Generic structs get generic builders: the struct's own parameters, lifetimes and consts
+//! included, come first and the states of the fields follow, so `Message<'a, P>` is built by a
+//! `MessageBuilder<'a, P, ...>`. Bounds and the `where` clause are carried over, defaults of type
+//! parameters are dropped since the states after them have none.
+//!
+//! Enums get a builder per variant. `Request::builder()` only picks the variant: with
+//! `enum Request { Get { url: String }, Post { url: String, body: Vec<u8> } }`, `.get()` hands out
+//! an empty `RequestGetBuilder` and `.post()` a `RequestPostBuilder`, each with the setters of that
+//! variant's fields. `construct()` returns the enum once the fields of the picked variant are set.
+//! Unit variants have a builder without fields, tuple variants aren't supported.
+//!
+//! A misused attribute is a compile error pointing at it, saying what to change: an unknown
+//! argument, one given twice, `default` on a skipped field, `push` without the name of its setter.
+//! The parser keeps going after such an error, so every misuse in the definition is reported at
+//! once instead of one per build.
+//!
+//! The macro has no dependencies, it parses the struct definition from the raw tokens and builds
+//! the output as source text.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+#[proc_macro_derive(TypesafeBuilder, attributes(builder))]
+pub fn derive_typesafe_builder(input: TokenStream) -> TokenStream {
+    match Target::parse(input) {
+        Ok(targets) => {
+            let mut output: String = targets.iter().map(Target::builder).collect();
+            if targets[0].variant.is_some() {
+                output += &selector(&targets);
+            }
+            let mut output: TokenStream = output.parse().expect("generated code is valid Rust");
+            if let Some(span) = targets[0].report {
+                output.extend(warning(span, &report(&targets[0].name, output.clone())));
+            }
+            output.extend(
+                targets.iter().flat_map(|target| &target.fields).filter_map(Field::warning),
+            );
+            output
+        }
+        Err(errors) => errors.into_iter().map(Error::into_tokens).collect(),
+    }
+}
+
+/// A rejected input and the tokens it is about. Attribute errors don't stop the parser, every
+/// misuse is collected and reported at once.
+struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Error { span, message: message.into() }
+    }
+
+    /// `compile_error!("...");` with every token at the error's span, so the compiler points at
+    /// the offending attribute instead of the derive.
+    fn into_tokens(self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let mut args = Group::new(Delimiter::Parenthesis, TokenTree::from(message).into());
+        args.set_span(self.span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.span);
+        let mut semi = Punct::new(';', Spacing::Alone);
+        semi.set_span(self.span);
+        let tokens: [TokenTree; 4] =
+            [Ident::new("compile_error", self.span).into(), bang.into(), args.into(), semi.into()];
+        tokens.iter().cloned().collect()
+    }
+}
+
+/// A warning pointing at `span`. Proc macros can't emit warnings on stable, so this uses a
+/// deprecated unit struct right away, which the `deprecated` lint reports with `message` as the
+/// note: "use of deprecated unit struct `_::TypesafeBuilderWarning`: ...". Every token is at
+/// `span`, the lint isn't reported at the spans of the derive.
+fn warning(span: Span, message: &str) -> TokenStream {
+    fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+        let respan = |token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::from(respanned)
+            }
+            mut token => {
+                token.set_span(span);
+                token
+            }
+        };
+        tokens.into_iter().map(respan).collect()
+    }
+    let warning = format!(
+        "const _: () = {{
+            #[deprecated(note = {:?})]
+            struct TypesafeBuilderWarning;
+            let _ = TypesafeBuilderWarning;
+        }};",
+        message,
+    );
+    respan(warning.parse().expect("the warning is valid Rust"), span)
+}
+
+/// What `report` says about the generated `output`: its size in tokens and the items in it, so
+/// the cost of the features turned on shows up where the type is defined.
+fn report(name: &str, output: TokenStream) -> String {
+    #[derive(Default)]
+    struct Items {
+        types: Vec<String>,
+        traits: Vec<String>,
+        functions: Vec<String>,
+        impls: Vec<String>,
+        checks: Vec<String>,
+    }
+
+    fn add(list: &mut Vec<String>, item: String) {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+
+    fn size(tokens: TokenStream) -> usize {
+        let size = |token| match token {
+            TokenTree::Group(group) => 1 + size(group.stream()),
+            _ => 1,
+        };
+        tokens.into_iter().map(size).sum()
+    }
+
+    fn ident(token: Option<&TokenTree>) -> Option<String> {
+        match token {
+            Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+            _ => None,
+        }
+    }
+
+    fn scan(tokens: TokenStream, items: &mut Items) {
+        let tokens: Vec<_> = tokens.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            let keyword = match token {
+                TokenTree::Ident(keyword) => keyword.to_string(),
+                _ => continue,
+            };
+            let next = ident(tokens.get(i + 1));
+            match (keyword.as_str(), next) {
+                ("struct", Some(name)) => add(&mut items.types, name),
+                ("trait", Some(name)) => add(&mut items.traits, name),
+                ("fn", Some(name)) => add(&mut items.functions, name),
+                // the trait is the last name outside of angle brackets before `for`, an impl
+                // without one adds functions
+                ("impl", _) => {
+                    let mut depth = 0;
+                    let mut last = None;
+                    for token in &tokens[i + 1..] {
+                        match token {
+                            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+                            TokenTree::Punct(punct) if punct.as_char() == '>' => depth -= 1,
+                            TokenTree::Ident(name) if name.to_string() == "for" => {
+                                add(&mut items.impls, last.take().unwrap_or_default());
+                                break;
+                            }
+                            TokenTree::Ident(name) if depth == 0 => last = Some(name.to_string()),
+                            TokenTree::Group(body) if body.delimiter() == Delimiter::Brace => {
+                                scan(body.stream(), items);
+                                break;
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                // the items the macros of `typestuff` wrap are generated all the same
+                ("__unless_no_panic", _) => {
+                    if let Some(TokenTree::Group(wrapped)) = tokens.get(i + 2) {
+                        scan(wrapped.stream(), items);
+                    }
+                }
+                ("__builder_descriptor", _) => add(&mut items.checks, "registry".into()),
+                ("const_check", _) => add(&mut items.checks, "const_check".into()),
+                _ => (),
+            }
+        }
+    }
+
+    let mut items = Items::default();
+    let tokens = size(output.clone());
+    scan(output, &mut items);
+    let lists = [
+        ("types", items.types),
+        ("traits", items.traits),
+        ("functions", items.functions),
+        ("impls", items.impls),
+        ("checks", items.checks),
+    ];
+    let lists = lists.iter().filter(|(_, list)| !list.is_empty());
+    let lists = lists.map(|(kind, list)| format!("{} {}", kind, list.join(", ")));
+    format!("`{}` generates {} tokens: {}", name, tokens, lists.collect::<Vec<_>>().join("; "))
+}
+
+/// The span of the first of `tokens`, or of the derive if there are none.
+fn span_of(tokens: &[TokenTree]) -> Span {
+    tokens.first().map_or_else(Span::call_site, TokenTree::span)
+}
+
+#[derive(Clone)]
+struct Field {
+    name: String,
+    /// The visibility the field is declared with.
+    vis: String,
+    /// The name of the setter, the field's name unless it is renamed.
+    setter: String,
+    ty: String,
+    /// The expression filling the field if it isn't set, `None` for required fields.
+    default: Option<String>,
+    /// Where `from_str_default` was given, the default is parsed from `BUILDER_<FIELD>` then.
+    from_str_default: Option<Span>,
+    /// The path of the function the setter asks whether it may set the field.
+    guard: Option<String>,
+    /// Whether the setter takes `impl Into<ty>`.
+    into: bool,
+    /// The type the setter takes an `impl AsRef` of, owning it with `ToOwned`.
+    as_ref: Option<String>,
+    /// The name of the setter appending a single element.
+    push: Option<String>,
+    /// Whether the field's type has a builder of its own, configured by `<name>_with`.
+    nested: bool,
+    /// Whether the field is left out of the builder and always filled with its default.
+    skip: bool,
+    /// Whether the field holds a secret, flagged as `sensitive` in `FIELDS`.
+    sensitive: bool,
+    /// The setter template of `typestuff::presets` generating convenience setters.
+    template: Option<Template>,
+    /// How the builder holds the value in an `Arc` shared with other builders, `None` if it
+    /// holds the value itself.
+    shared: Option<Shared>,
+    /// Whether a collection field has a setter preallocating it, `<setter>_with_capacity`.
+    with_capacity: bool,
+    /// The value the generated usage test sets the field to, `Default::default()` if `None`.
+    test_value: Option<String>,
+    /// Where `phase = 2` was given, `None` for fields of the first phase.
+    phase: Option<Span>,
+    span: Span,
+}
+
+impl Field {
+    /// What the type of the field says about it being an `Arc`. Only the path is there to go by:
+    /// an alias or a renamed import of `Arc` looks like any other type, a type of another crate
+    /// named `Arc` like one of std.
+    fn arc_path(&self) -> ArcPath {
+        let ty = self.ty.replace(' ', "");
+        let path = ty.split('<').next().unwrap_or_default();
+        let std = ["Arc", "sync::Arc", "std::sync::Arc", "alloc::sync::Arc"];
+        if std.contains(&path.trim_start_matches("::")) && ty.len() > path.len() {
+            ArcPath::Std
+        } else if path.ends_with("::Arc") {
+            ArcPath::Ambiguous
+        } else {
+            ArcPath::No
+        }
+    }
+
+    /// Whether the builder stores the field in an `Arc` of its own. A field declared as an `Arc`
+    /// already is stored as it is, an ambiguous one is wrapped since that shares it either way.
+    fn wraps(&self) -> bool {
+        match self.shared {
+            None | Some(Shared::AsIs) => false,
+            Some(Shared::Wrap) => true,
+            Some(Shared::Detect) => self.arc_path() != ArcPath::Std,
+        }
+    }
+
+    /// The warning about a shared field whose type may or may not be an `Arc`, asking for the
+    /// argument that decides it.
+    fn warning(&self) -> Option<TokenStream> {
+        if self.shared != Some(Shared::Detect) || self.arc_path() != ArcPath::Ambiguous {
+            return None;
+        }
+        let message = format!(
+            "`shared` can't tell whether `{}` is `std::sync::Arc` and stores it in another `Arc`. \
+             Write `shared = as_is` to store it as it is, or `shared = wrap` to keep it wrapped",
+            type_name(&self.ty),
+        );
+        Some(warning(self.span, &message))
+    }
+
+    /// The type the slot of the field holds, an `Arc` of the field's type for shared fields.
+    fn stored(&self) -> String {
+        if self.wraps() {
+            format!("::std::sync::Arc<{}>", self.ty)
+        } else {
+            self.ty.clone()
+        }
+    }
+
+    /// `value` of the field's type as it is stored.
+    fn wrap(&self, value: &str) -> String {
+        if self.wraps() {
+            format!("::std::sync::Arc::new({})", value)
+        } else {
+            value.into()
+        }
+    }
+
+    /// The path of the function turning the stored value back into the field's type, moving it
+    /// out of the `Arc` if this builder held the last reference and cloning it otherwise.
+    fn unwrap(&self) -> Option<&'static str> {
+        if self.wraps() {
+            Some("::std::sync::Arc::unwrap_or_clone")
+        } else {
+            None
+        }
+    }
+}
+
+/// How `#[builder(shared)]` holds a field.
+#[derive(Clone, Copy, PartialEq)]
+enum Shared {
+    /// `shared`, in an `Arc` of its own unless the type is one already.
+    Detect,
+    /// `shared = wrap`, in an `Arc` of its own whatever the type.
+    Wrap,
+    /// `shared = as_is`, the type is an `Arc` already.
+    AsIs,
+}
+
+/// Whether the type of a field is an `Arc`, as far as its path tells.
+#[derive(Clone, Copy, PartialEq)]
+enum ArcPath {
+    /// `Arc<T>` or a path of std's, like `std::sync::Arc<T>`.
+    Std,
+    /// A path ending in `Arc` that isn't std's, like `triomphe::Arc<T>`.
+    Ambiguous,
+    /// Anything else.
+    No,
+}
+
+/// The setter templates of `typestuff::presets`, each forwarding to the setter of the field.
+#[derive(Clone, Copy)]
+enum Template {
+    /// `<setter>_secs` and `<setter>_ms` for `Duration` fields.
+    Duration,
+    /// `<setter>_path` taking anything that can be viewed as a path, for `PathBuf` fields.
+    Path,
+    /// `try_<setter>` parsing a string, for `IpAddr`, `SocketAddr` and anything else `FromStr`.
+    Addr,
+}
+
+/// Which part of a struct the builder constructs. Structs with `phase = 2` fields are built by
+/// two builders: the one of `<Name>Spec`, a struct of the other fields that the derive declares,
+/// and the one of the struct itself, starting from a complete spec.
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    /// The whole struct or variant, no field has `phase = 2`.
+    Whole,
+    /// The `<Name>Spec` of the first phase.
+    Spec,
+    /// The struct from a spec and the fields of the second phase.
+    Build,
+}
+
+/// A generic parameter of the struct.
+#[derive(Clone)]
+struct Generic {
+    /// The parameter as it is declared, with bounds but without a default, like `T: Clone`.
+    decl: String,
+    /// The parameter as it is used, like `T` or `'a`.
+    name: String,
+}
+
+/// The parts of the struct the builder is generated from. An enum has one target per variant,
+/// sharing everything but `variant` and the fields.
+struct Target {
+    vis: String,
+    name: String,
+    generics: Vec<Generic>,
+    where_clause: String,
+    /// Whether setters are only available while their field is unset.
+    strict: bool,
+    /// Whether values can be taken apart into a complete builder again.
+    into_builder: bool,
+    /// Whether the builder comes with a `BuildTicket` that constructing it consumes.
+    require_ticket: bool,
+    /// Whether values can be built through a closure configuring the builder.
+    build_with: bool,
+    /// The path of a `const fn` checking `FIELDS` at compile-time.
+    const_check: Option<String>,
+    /// The name of the preset struct and the fields it holds.
+    preset: Option<(String, Vec<(String, Span)>)>,
+    /// Whether type aliases are generated for the common states of the builder.
+    aliases: bool,
+    /// The builder of the previous version of the struct and how its fields carry over.
+    migrate_from: Option<Migration>,
+    /// Whether the slots are declared in reverse, so an abandoned builder drops its fields last
+    /// to first.
+    reverse_drop: bool,
+    /// How long a builder can be finished after it was started, making `construct()` fallible.
+    expires_in: Option<String>,
+    /// The parts of the builder generated next to `new`, the setters and `construct()`.
+    features: Features,
+    /// Whether a test setting every field and comparing the result is generated.
+    usage_test: bool,
+    /// Whether the target's `Default` is the value an empty builder constructs.
+    derive_default: bool,
+    /// Whether values and complete builders can be compared field by field.
+    diff: bool,
+    /// Where `report` was given, the generated items are listed in a warning there.
+    report: Option<Span>,
+    /// Two fields whose values `construct()` mixes up on purpose, to see the usage test fail.
+    swap: Option<(String, String)>,
+    /// The name of the variant this builder constructs, `None` for structs.
+    variant: Option<String>,
+    /// The part of a struct built in two phases this target is.
+    phase: Phase,
+    /// The fields of the first phase, which the builder of the second phase holds in a spec.
+    spec: Vec<Field>,
+    fields: Vec<Field>,
+    /// The fields marked with `#[builder(skip)]`, they have no slot.
+    skipped: Vec<Field>,
+}
+
+/// The parts of a builder that building a value doesn't need. All of them but the setter traits
+/// are generated unless `minimal` or `features(...)` on the type asks for fewer, the setter
+/// traits only with `setter_traits` on the type or among the features.
+#[derive(Clone, Copy)]
+struct Features {
+    /// `Clone` for builders whose set fields are.
+    clone: bool,
+    /// `Debug` for builders whose set fields are.
+    debug: bool,
+    /// `Default` for the empty builder.
+    default: bool,
+    /// `unset_<field>()` for every field.
+    unset: bool,
+    /// `FIELDS`, `STATES`, `set_fields()`, `missing_fields()` and `is_complete()`.
+    reflection: bool,
+    /// The traits of `typestuff::nested`, to configure the target as a field of another.
+    nested: bool,
+    /// The descriptor the `registry` feature of `typestuff` registers.
+    registry: bool,
+    /// A trait per setter, implemented for every state, like those of `typestuff::setters`.
+    setter_traits: bool,
+}
+
+/// The features `features(...)` takes, listed in the error about unknown ones.
+const FEATURES: &str =
+    "`clone`, `debug`, `default`, `unset`, `reflection`, `nested`, `registry` or `setter_traits`";
+
+impl Features {
+    const ALL: Features = Features {
+        clone: true,
+        debug: true,
+        default: true,
+        unset: true,
+        reflection: true,
+        nested: true,
+        registry: true,
+        // a trait and an impl per field is a lot to compile for what few builders need
+        setter_traits: false,
+    };
+
+    const NONE: Features = Features {
+        clone: false,
+        debug: false,
+        default: false,
+        unset: false,
+        reflection: false,
+        nested: false,
+        registry: false,
+        setter_traits: false,
+    };
+
+    /// Turn on the feature called `name`, `false` if there is none.
+    fn enable(&mut self, name: &str) -> bool {
+        let feature = match name {
+            "clone" => &mut self.clone,
+            "debug" => &mut self.debug,
+            "default" => &mut self.default,
+            "unset" => &mut self.unset,
+            "reflection" => &mut self.reflection,
+            "nested" => &mut self.nested,
+            "registry" => &mut self.registry,
+            "setter_traits" => &mut self.setter_traits,
+            _ => return false,
+        };
+        *feature = true;
+        true
+    }
+
+    /// The names of the features that are on, for the documentation of the builder.
+    fn names(&self) -> Vec<&'static str> {
+        let features = [
+            ("clone", self.clone),
+            ("debug", self.debug),
+            ("default", self.default),
+            ("unset", self.unset),
+            ("reflection", self.reflection),
+            ("nested", self.nested),
+            ("registry", self.registry),
+            ("setter_traits", self.setter_traits),
+        ];
+        features.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+    }
+}
+
+/// `migrate_from(OldBuilder, fields(...), map(...), transform(...), strict)`: the builder of an
+/// older version of the struct, whose fields move over into the builder of this one.
+#[derive(Clone)]
+struct Migration {
+    /// The old builder as it is written, like `ItemV1Builder` or `v1::ItemBuilder`.
+    from: String,
+    /// The last segment of `from`, which names the migration.
+    name: String,
+    /// The fields of the old builder in declaration order, which is the order of its states.
+    fields: Vec<(String, Span)>,
+    /// `(old, new)` for each old field moving into a field of another name.
+    map: Vec<((String, Span), (String, Span))>,
+    /// `(old, function)` for each old field whose value is passed through a function.
+    transform: Vec<((String, Span), String)>,
+    /// Whether dropping a field of the old builder is an error instead of a note.
+    strict: bool,
+    span: Span,
+}
+
+/// Skip outer attributes like `#[doc = "..."]`, collecting the arguments of the
+/// `#[builder(...)]` ones among them. The arguments are separated by commas, the commas inside of
+/// an expression are in a group of their own.
+fn builder_arguments(
+    tokens: &[TokenTree],
+    i: &mut usize,
+    errors: &mut Vec<Error>,
+) -> Vec<Vec<TokenTree>> {
+    let mut arguments = Vec::new();
+    while let (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(attr))) =
+        (tokens.get(*i), tokens.get(*i + 1))
+    {
+        if punct.as_char() != '#' {
+            break;
+        }
+        *i += 2;
+
+        let attr: Vec<_> = attr.stream().into_iter().collect();
+        match attr.first() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "builder" => (),
+            _ => continue,
+        }
+        let args: Vec<_> = match attr.get(1) {
+            Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => {
+                args.stream().into_iter().collect()
+            }
+            _ => {
+                let message = "expected arguments in parentheses, like `#[builder(default)]`";
+                errors.push(Error::new(span_of(&attr), message));
+                continue;
+            }
+        };
+        arguments.extend(
+            args.split(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ','))
+                .filter(|arg| !arg.is_empty())
+                .map(<[_]>::to_vec),
+        );
+    }
+    arguments
+}
+
+/// The name of a builder argument, like `default` in `default = 1`, and where it is.
+fn argument_name(arg: &[TokenTree], errors: &mut Vec<Error>) -> Option<(String, Span)> {
+    match arg.first() {
+        Some(TokenTree::Ident(ident)) => Some((ident.to_string(), ident.span())),
+        _ => {
+            let message = "expected the name of a builder attribute, like `default` or `into`";
+            errors.push(Error::new(span_of(arg), message));
+            None
+        }
+    }
+}
+
+/// The arguments seen so far and where they were given, to reject duplicates and report
+/// conflicting arguments at each of them.
+#[derive(Default)]
+struct Given(Vec<(String, Span)>);
+
+impl Given {
+    /// Record `name`, or report it if it was given before.
+    fn first(&mut self, name: &str, span: Span, errors: &mut Vec<Error>) -> bool {
+        if self.0.iter().any(|(given, _)| given == name) {
+            errors.push(Error::new(span, format!("`{}` is given twice, remove one", name)));
+            return false;
+        }
+        self.0.push((name.into(), span));
+        true
+    }
+
+    /// Where `name` was given, if it was.
+    fn span(&self, name: &str) -> Option<Span> {
+        self.0.iter().find(|(given, _)| given == name).map(|(_, span)| *span)
+    }
+}
+
+/// What the `#[builder(...)]` attributes of the struct ask for.
+#[derive(Default)]
+struct StructAttributes {
+    strict: bool,
+    into_builder: bool,
+    require_ticket: bool,
+    build_with: bool,
+    const_check: Option<String>,
+    preset: Option<(String, Vec<(String, Span)>)>,
+    aliases: bool,
+    migrate_from: Option<Migration>,
+    reverse_drop: bool,
+    expires_in: Option<String>,
+    features: Option<Features>,
+    usage_test: bool,
+    derive_default: bool,
+    diff: bool,
+    report: Option<Span>,
+    setter_traits: bool,
+    swap: Option<(String, String)>,
+    given: Given,
+}
+
+/// The arguments a struct takes, listed in the error about unknown ones.
+const STRUCT_ARGUMENTS: &str = "`strict`, `into_builder`, `require_ticket`, `build_with`, \
+                                `const_check`, `aliases`, `preset`, `migrate_from`, `drop_order`, \
+                                `expires_in`, `minimal`, `features`, `setter_traits`, \
+                                `generate_usage_test`, `derive_default`, `diff` or \
+                                `report`";
+
+/// The builder arguments of the struct: `strict`, `into_builder`, `require_ticket`,
+/// `build_with`, `const_check = "path"`, `aliases`, `preset = Name(field, ...)`,
+/// `migrate_from(...)`, `drop_order = "declaration"` or `drop_order = "reverse"`,
+/// `expires_in = ttl`, `minimal`, `features(...)`, `setter_traits`, `generate_usage_test`,
+/// `derive_default`, `diff` and `report`.
+fn struct_attributes(
+    tokens: &[TokenTree],
+    i: &mut usize,
+    errors: &mut Vec<Error>,
+) -> StructAttributes {
+    let mut attributes = StructAttributes::default();
+    for arg in builder_arguments(tokens, i, errors) {
+        let (name, span) = match argument_name(&arg, errors) {
+            Some(name) => name,
+            None => continue,
+        };
+        if !attributes.given.first(&name, span, errors) {
+            continue;
+        }
+        match (name.as_str(), arg.len()) {
+            ("strict", 1) => attributes.strict = true,
+            ("into_builder", 1) => attributes.into_builder = true,
+            ("require_ticket", 1) => attributes.require_ticket = true,
+            ("build_with", 1) => attributes.build_with = true,
+            ("aliases", 1) => attributes.aliases = true,
+            ("generate_usage_test", 1) => attributes.usage_test = true,
+            ("derive_default", 1) => attributes.derive_default = true,
+            ("setter_traits", 1) => attributes.setter_traits = true,
+            ("diff", 1) => attributes.diff = true,
+            ("report", 1) => attributes.report = Some(span),
+            ("strict", _)
+            | ("into_builder", _)
+            | ("require_ticket", _)
+            | ("build_with", _)
+            | ("aliases", _)
+            | ("generate_usage_test", _)
+            | ("derive_default", _)
+            | ("setter_traits", _)
+            | ("diff", _)
+            | ("report", _) => {
+                let message = format!("`{}` takes no arguments, write `#[builder({0})]`", name);
+                errors.push(Error::new(span, message));
+            }
+            ("const_check", _) => match &arg[1..] {
+                [TokenTree::Punct(eq), TokenTree::Literal(path)]
+                    if eq.as_char() == '=' && path.to_string().starts_with('"') =>
+                {
+                    attributes.const_check = Some(path.to_string().trim_matches('"').into());
+                }
+                _ => {
+                    let message = "expected the path of a check in quotes, like \
+                                   `const_check = \"typestuff::policy::secrets_are_sensitive\"`";
+                    errors.push(Error::new(span_of(&arg), message));
+                }
+            },
+            ("preset", _) => match preset(&arg) {
+                Ok(preset) => attributes.preset = Some(preset),
+                Err(error) => errors.push(error),
+            },
+            ("drop_order", _) => match &arg[1..] {
+                [TokenTree::Punct(eq), TokenTree::Literal(order)] if eq.as_char() == '=' => {
+                    match order.to_string().as_str() {
+                        "\"declaration\"" => (),
+                        "\"reverse\"" => attributes.reverse_drop = true,
+                        _ => {
+                            let message = "expected `drop_order = \"declaration\"` or \
+                                           `drop_order = \"reverse\"`";
+                            errors.push(Error::new(order.span(), message));
+                        }
+                    }
+                }
+                _ => {
+                    let message = "expected the order in quotes, like `drop_order = \"reverse\"`";
+                    errors.push(Error::new(span_of(&arg), message));
+                }
+            },
+            // `minimal` is `features()`, the features given are added to what is there
+            ("minimal", 1) => {
+                attributes.features.get_or_insert(Features::NONE);
+            }
+            ("minimal", _) => {
+                let message = "`minimal` takes no arguments, pick features with `features(...)`";
+                errors.push(Error::new(span, message));
+            }
+            ("features", _) => match &arg[1..] {
+                [TokenTree::Group(names)] if names.delimiter() == Delimiter::Parenthesis => {
+                    let features = attributes.features.get_or_insert(Features::NONE);
+                    for feature in names.stream() {
+                        match feature {
+                            TokenTree::Ident(name) if features.enable(&name.to_string()) => (),
+                            TokenTree::Punct(comma) if comma.as_char() == ',' => (),
+                            other => {
+                                let message =
+                                    format!("unknown feature `{}`, expected {}", other, FEATURES);
+                                errors.push(Error::new(other.span(), message));
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    let message =
+                        "expected the features to generate, like `features(clone, debug)`";
+                    errors.push(Error::new(span_of(&arg), message));
+                }
+            },
+            ("expires_in", _) => match &arg[1..] {
+                [TokenTree::Punct(eq), ttl @ ..] if eq.as_char() == '=' && !ttl.is_empty() => {
+                    let ttl = ttl.iter().cloned().collect::<TokenStream>().to_string();
+                    attributes.expires_in = Some(ttl);
+                }
+                _ => {
+                    let message = "expected the time to live of the builder, like \
+                                   `expires_in = std::time::Duration::from_secs(60)`";
+                    errors.push(Error::new(span_of(&arg), message));
+                }
+            },
+            ("migrate_from", _) => match migration(&arg) {
+                Ok(migration) => attributes.migrate_from = Some(migration),
+                Err(error) => errors.push(error),
+            },
+            // a bug put in on purpose for the derive's own tests, which check that the usage test
+            // catches it. not listed with the other arguments
+            ("__swap_fields", _) => match &arg[1..] {
+                [TokenTree::Group(fields)] => {
+                    match fields.stream().into_iter().collect::<Vec<_>>().as_slice() {
+                        [TokenTree::Ident(a), TokenTree::Punct(_), TokenTree::Ident(b)] => {
+                            attributes.swap = Some((a.to_string(), b.to_string()))
+                        }
+                        _ => errors.push(Error::new(span, "expected `__swap_fields(a, b)`")),
+                    }
+                }
+                _ => errors.push(Error::new(span, "expected `__swap_fields(a, b)`")),
+            },
+            _ => {
+                let message = format!(
+                    "unknown builder attribute `{}` on a struct, expected {}",
+                    name, STRUCT_ARGUMENTS,
+                );
+                errors.push(Error::new(span, message));
+            }
+        }
+    }
+    // the terminal of an expiring builder is fallible, which the others don't expect
+    if let Some(span) = attributes.given.span("expires_in") {
+        for argument in ["require_ticket", "build_with", "derive_default"] {
+            if attributes.given.span(argument).is_some() {
+                let message = format!(
+                    "`expires_in` makes `construct()` fallible, it can't be combined with `{}`",
+                    argument,
+                );
+                errors.push(Error::new(span, message));
+            }
+        }
+    }
+    if attributes.require_ticket {
+        let bypasses = [
+            ("into_builder", "hands out builders"),
+            ("build_with", "constructs values"),
+            ("migrate_from", "hands out builders"),
+            ("derive_default", "constructs values"),
+        ];
+        for (argument, what) in bypasses {
+            if let Some(span) = attributes.given.span(argument) {
+                let message = format!(
+                    "`{}` {} without a ticket, it can't be combined with `require_ticket`",
+                    argument, what,
+                );
+                errors.push(Error::new(span, message));
+            }
+        }
+    }
+    attributes
+}
+
+/// The name and the fields of `preset = Name(field, ...)`.
+fn preset(arg: &[TokenTree]) -> Result<(String, Vec<(String, Span)>), Error> {
+    let expected = "expected the name of the preset and its fields, like `preset = Base(port)`";
+    let (name, fields) = match arg {
+        [_, TokenTree::Punct(eq), TokenTree::Ident(name), TokenTree::Group(fields)]
+            if eq.as_char() == '=' && fields.delimiter() == Delimiter::Parenthesis =>
+        {
+            (name.to_string(), fields.stream())
+        }
+        _ => return Err(Error::new(span_of(arg), expected)),
+    };
+    let mut names = Vec::new();
+    for field in fields {
+        match field {
+            TokenTree::Ident(field) => names.push((field.to_string(), field.span())),
+            TokenTree::Punct(comma) if comma.as_char() == ',' => (),
+            other => return Err(Error::new(other.span(), expected)),
+        }
+    }
+    Ok((name, names))
+}
+
+/// The arguments of `migrate_from(...)`. The old builder comes first, the others are
+/// `fields(a, b)`, any number of `map(old = "b", new = "values")` and
+/// `transform(field = "a", with = "upgrade_a")`, and `strict`.
+fn migration(arg: &[TokenTree]) -> Result<Migration, Error> {
+    let expected = "expected the old builder and its fields, like \
+                    `migrate_from(ItemV1Builder, fields(a, b))`";
+    let args: Vec<_> = match arg {
+        [_, TokenTree::Group(args)] if args.delimiter() == Delimiter::Parenthesis => {
+            args.stream().into_iter().collect()
+        }
+        _ => return Err(Error::new(span_of(arg), expected)),
+    };
+    let mut args = args.split(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ','));
+    let from = match args.next() {
+        Some(from @ [.., TokenTree::Ident(name)]) => (from, name.to_string()),
+        _ => return Err(Error::new(span_of(arg), expected)),
+    };
+    let mut migration = Migration {
+        from: from.0.iter().cloned().collect::<TokenStream>().to_string(),
+        name: from.1,
+        fields: Vec::new(),
+        map: Vec::new(),
+        transform: Vec::new(),
+        strict: false,
+        span: span_of(from.0),
+    };
+    let mut fields = None;
+    for arg in args.filter(|arg| !arg.is_empty()) {
+        let (name, group) = match arg {
+            [TokenTree::Ident(name)] if name.to_string() == "strict" => {
+                migration.strict = true;
+                continue;
+            }
+            [TokenTree::Ident(name), TokenTree::Group(group)]
+                if group.delimiter() == Delimiter::Parenthesis =>
+            {
+                (name.to_string(), group.stream())
+            }
+            _ => {
+                let message = "expected `fields(...)`, `map(...)`, `transform(...)` or `strict`";
+                return Err(Error::new(span_of(arg), message));
+            }
+        };
+        match name.as_str() {
+            "fields" => {
+                let mut names = Vec::new();
+                for field in group {
+                    match field {
+                        TokenTree::Ident(field) => names.push((field.to_string(), field.span())),
+                        TokenTree::Punct(comma) if comma.as_char() == ',' => (),
+                        other => return Err(Error::new(other.span(), expected)),
+                    }
+                }
+                fields = Some(names);
+            }
+            "map" => match pairs(group, ["old", "new"])?.as_slice() {
+                [old, new] => migration.map.push((old.clone(), new.clone())),
+                _ => unreachable!(),
+            },
+            "transform" => match pairs(group, ["field", "with"])?.as_slice() {
+                [field, (with, _)] => migration.transform.push((field.clone(), with.clone())),
+                _ => unreachable!(),
+            },
+            _ => {
+                let message = "expected `fields(...)`, `map(...)`, `transform(...)` or `strict`";
+                return Err(Error::new(span_of(arg), message));
+            }
+        }
+    }
+    match fields {
+        Some(fields) => migration.fields = fields,
+        None => {
+            let message = "list the fields of the old builder in order, like `fields(a, b)`";
+            return Err(Error::new(migration.span, message));
+        }
+    }
+    Ok(migration)
+}
+
+/// The values of `keys[0] = "value", keys[1] = value`, in the order of `keys`. Values are names
+/// or paths, given in quotes or as they are.
+fn pairs(group: TokenStream, keys: [&str; 2]) -> Result<Vec<(String, Span)>, Error> {
+    let tokens: Vec<_> = group.into_iter().collect();
+    let expected = format!("expected `{} = \"...\", {} = \"...\"`", keys[0], keys[1]);
+    let mut values = vec![None, None];
+    for pair in tokens.split(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ',')) {
+        let (key, value) = match pair {
+            [TokenTree::Ident(key), TokenTree::Punct(eq), value @ ..]
+                if eq.as_char() == '=' && !value.is_empty() =>
+            {
+                (key.to_string(), value)
+            }
+            [] => continue,
+            _ => return Err(Error::new(span_of(pair), expected)),
+        };
+        let index = match keys.iter().position(|known| *known == key) {
+            Some(index) => index,
+            None => return Err(Error::new(span_of(pair), expected)),
+        };
+        let text = value.iter().cloned().collect::<TokenStream>().to_string();
+        values[index] = Some((text.trim_matches('"').replace(' ', ""), span_of(value)));
+    }
+    values
+        .into_iter()
+        .map(|value| value.ok_or_else(|| Error::new(span_of(&tokens), &expected)))
+        .collect()
+}
+
+/// What `#[builder(default)]` fills a field with.
+const DEFAULT: &str = "::core::default::Default::default()";
+
+/// What the `#[builder(...)]` attributes of a field ask for.
+#[derive(Default)]
+struct FieldAttributes {
+    default: Option<String>,
+    from_str_default: Option<Span>,
+    guard: Option<String>,
+    into: bool,
+    as_ref: Option<String>,
+    push: Option<String>,
+    nested: bool,
+    rename: Option<String>,
+    skip: bool,
+    sensitive: bool,
+    template: Option<Template>,
+    shared: Option<Shared>,
+    with_capacity: bool,
+    test_value: Option<String>,
+    phase: Option<Span>,
+}
+
+/// The arguments a field takes, listed in the error about unknown ones.
+const FIELD_ARGUMENTS: &str =
+    "`default`, `from_str_default`, `guard`, `into`, `as_ref`, `push`, `nested`, `rename`, \
+     `skip`, `sensitive`, `template`, `shared`, `setter`, `test_value` or `phase`";
+
+/// The builder arguments of a field: `default`, `default = expr`, `from_str_default`,
+/// `guard = "path"`, `into`, `as_ref = type`, `push = name`,
+/// `nested`, `rename = name`, `skip`, `sensitive`, `template = duration`, `template = path` or
+/// `template = addr`, `shared`, `shared = wrap` or `shared = as_is`, `setter(with_capacity)`,
+/// `test_value = expr` and `phase = 1` or
+/// `phase = 2`.
+fn field_attributes(
+    tokens: &[TokenTree],
+    i: &mut usize,
+    errors: &mut Vec<Error>,
+) -> FieldAttributes {
+    let mut attributes = FieldAttributes::default();
+    let mut given = Given::default();
+    for arg in builder_arguments(tokens, i, errors) {
+        let (name, span) = match argument_name(&arg, errors) {
+            Some(name) => name,
+            None => continue,
+        };
+        if !given.first(&name, span, errors) {
+            continue;
+        }
+        let mut reject = |message: &str| errors.push(Error::new(span, message));
+        match (name.as_str(), arg.get(1)) {
+            ("default", None) => attributes.default = Some(DEFAULT.into()),
+            ("default", Some(TokenTree::Punct(punct)))
+                if punct.as_char() == '=' && arg.len() > 2 =>
+            {
+                let expr = arg[2..].iter().cloned().collect::<TokenStream>().to_string();
+                attributes.default = Some(expr);
+            }
+            ("default", _) => reject("expected `default` or `default = expr`"),
+            ("from_str_default", None) => attributes.from_str_default = Some(span),
+            ("from_str_default", _) => reject(
+                "`from_str_default` takes no arguments, the variable is named after the field",
+            ),
+            ("test_value", Some(TokenTree::Punct(punct)))
+                if punct.as_char() == '=' && arg.len() > 2 =>
+            {
+                let expr = arg[2..].iter().cloned().collect::<TokenStream>().to_string();
+                attributes.test_value = Some(expr);
+            }
+            ("test_value", _) => {
+                reject("expected the value for the usage test, like `test_value = 1`")
+            }
+            ("guard", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => match &arg[2..] {
+                [TokenTree::Literal(path)] if path.to_string().starts_with('"') => {
+                    attributes.guard = Some(path.to_string().trim_matches('"').into());
+                }
+                _ => reject("expected the path of a guard in quotes, like `guard = \"flags::on\"`"),
+            },
+            ("guard", _) => {
+                reject("`guard` needs the function to ask, like `guard = \"flags::on\"`")
+            }
+            ("into", None) => attributes.into = true,
+            ("into", _) => reject("`into` takes no arguments, write `#[builder(into)]`"),
+            ("as_ref", Some(TokenTree::Punct(punct)))
+                if punct.as_char() == '=' && arg.len() > 2 =>
+            {
+                let ty = arg[2..].iter().cloned().collect::<TokenStream>().to_string();
+                attributes.as_ref = Some(ty);
+            }
+            ("as_ref", _) => {
+                reject("`as_ref` needs the type the setter borrows, like `as_ref = str`")
+            }
+            ("push", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => match &arg[2..] {
+                [TokenTree::Ident(setter)] => attributes.push = Some(setter.to_string()),
+                _ => reject("expected the name of a setter, like `push = tag`"),
+            },
+            ("push", _) => reject("`push` needs the name of its setter, like `push = tag`"),
+            ("nested", None) => attributes.nested = true,
+            ("nested", _) => reject("`nested` takes no arguments, write `#[builder(nested)]`"),
+            ("rename", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => {
+                // an identifier, or a string literal like serde's `rename = "name"`
+                match &arg[2..] {
+                    [TokenTree::Ident(setter)] => attributes.rename = Some(setter.to_string()),
+                    [TokenTree::Literal(setter)] if setter.to_string().starts_with('"') => {
+                        attributes.rename = Some(setter.to_string().trim_matches('"').into());
+                    }
+                    _ => reject("expected the name of the setter, like `rename = port`"),
+                }
+            }
+            ("rename", _) => reject("`rename` needs the name of the setter, like `rename = port`"),
+            ("skip", None) => attributes.skip = true,
+            ("skip", _) => reject("`skip` takes no arguments, write `#[builder(skip)]`"),
+            ("sensitive", None) => attributes.sensitive = true,
+            ("sensitive", _) => {
+                reject("`sensitive` takes no arguments, write `#[builder(sensitive)]`")
+            }
+            ("template", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => {
+                match arg[2..].iter().map(ToString::to_string).collect::<Vec<_>>().as_slice() {
+                    [template] if template == "duration" => {
+                        attributes.template = Some(Template::Duration)
+                    }
+                    [template] if template == "path" => attributes.template = Some(Template::Path),
+                    [template] if template == "addr" => attributes.template = Some(Template::Addr),
+                    _ => reject(
+                        "expected `template = duration`, `template = path` or `template = addr`",
+                    ),
+                }
+            }
+            ("setter", Some(TokenTree::Group(setters)))
+                if setters.delimiter() == Delimiter::Parenthesis && arg.len() == 2 =>
+            {
+                for setter in setters.stream() {
+                    match setter {
+                        TokenTree::Ident(setter) if setter.to_string() == "with_capacity" => {
+                            attributes.with_capacity = true
+                        }
+                        TokenTree::Punct(comma) if comma.as_char() == ',' => (),
+                        other => errors.push(Error::new(
+                            other.span(),
+                            "unknown setter, expected `with_capacity`",
+                        )),
+                    }
+                }
+            }
+            ("setter", _) => {
+                reject("`setter` needs the setters to add, like `setter(with_capacity)`")
+            }
+            ("shared", None) => attributes.shared = Some(Shared::Detect),
+            ("shared", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => {
+                match arg[2..].iter().map(ToString::to_string).collect::<Vec<_>>().as_slice() {
+                    [shared] if shared == "wrap" => attributes.shared = Some(Shared::Wrap),
+                    [shared] if shared == "as_is" => attributes.shared = Some(Shared::AsIs),
+                    _ => reject("expected `shared`, `shared = wrap` or `shared = as_is`"),
+                }
+            }
+            ("shared", _) => reject("expected `shared`, `shared = wrap` or `shared = as_is`"),
+            ("template", _) => reject("`template` needs a setter template, like `template = path`"),
+            ("phase", Some(TokenTree::Punct(punct))) if punct.as_char() == '=' => {
+                match arg[2..].iter().map(ToString::to_string).collect::<Vec<_>>().as_slice() {
+                    [phase] if phase == "1" => (),
+                    [phase] if phase == "2" => attributes.phase = Some(span),
+                    _ => reject(
+                        "a struct is built in two phases, expected `phase = 1` or `phase = 2`",
+                    ),
+                }
+            }
+            ("phase", _) => reject("`phase` needs the phase of the field, like `phase = 2`"),
+            _ => reject(&format!(
+                "unknown builder attribute `{}` on a field, expected {}",
+                name, FIELD_ARGUMENTS,
+            )),
+        }
+    }
+
+    // a skipped field has no setters, everything about them conflicts with `skip`
+    if attributes.skip {
+        let setters = [
+            "guard",
+            "into",
+            "as_ref",
+            "push",
+            "nested",
+            "rename",
+            "template",
+            "shared",
+            "setter",
+            "test_value",
+            "phase",
+        ];
+        for setter in setters {
+            if let Some(span) = given.span(setter) {
+                let message = format!("a skipped field has no setters, remove `{}`", setter);
+                errors.push(Error::new(span, message));
+            }
+        }
+        if let Some(span) = given.span("sensitive") {
+            let message = "a skipped field isn't in `FIELDS` to be flagged, remove `sensitive`";
+            errors.push(Error::new(span, message));
+        }
+        if attributes.default.as_deref() == Some(DEFAULT) {
+            let message = "a skipped field is filled with `Default::default()` already, remove \
+                           `default` or give it a value with `default = expr`";
+            errors.push(Error::new(given.span("default").unwrap(), message));
+        }
+    }
+    // the setter of a shared field takes anything converting into the `Arc` already, and the
+    // value is behind the `Arc` where neither `push` nor `nested` can change it in place
+    if attributes.shared.is_some() {
+        let conflicts = [
+            ("into", "takes the value or an `Arc` of it already"),
+            ("as_ref", "takes the value or an `Arc` of it already"),
+            ("push", "can't append to a value behind an `Arc`"),
+            ("nested", "can't build a value behind an `Arc` in place"),
+        ];
+        for (argument, why) in conflicts {
+            if let Some(span) = given.span(argument) {
+                let message = format!("a shared field {}, remove `{}`", why, argument);
+                errors.push(Error::new(span, message));
+            }
+        }
+    }
+    // these set the field without going through its setter, which asks the guard
+    if attributes.guard.is_some() {
+        for argument in ["push", "nested", "template", "setter"] {
+            if let Some(span) = given.span(argument) {
+                let message = format!(
+                    "`{}` would set a guarded field without asking its guard, remove it",
+                    argument,
+                );
+                errors.push(Error::new(span, message));
+            }
+        }
+    }
+    // the variable is read when the field isn't set, in place of the default
+    if let (Some(span), Some(_)) = (attributes.from_str_default, given.span("default")) {
+        let message = "`default` and `from_str_default` both fill the field if it isn't set, \
+                       remove one of them";
+        errors.push(Error::new(span, message));
+    }
+    if let (Some(span), true) = (attributes.from_str_default, attributes.skip) {
+        let message = "a skipped field is always filled with its default, give it with \
+                       `default = expr` instead of `from_str_default`";
+        errors.push(Error::new(span, message));
+    }
+    if let (true, Some(span)) = (attributes.into, given.span("as_ref")) {
+        let message = "`into` and `as_ref` both pick what the setter takes, remove one of them";
+        errors.push(Error::new(span, message));
+    }
+    attributes
+}
+
+/// Parse the generic parameters following the `<` at `i`, leaving `i` after the closing `>`.
+/// Like the types of the fields they aren't grouped, so the nesting is tracked by hand.
+fn generics(tokens: &[TokenTree], i: &mut usize) -> Result<Vec<Generic>, Error> {
+    let mut params = vec![Vec::new()];
+    let mut depth = 0;
+    let mut arrow = false;
+    loop {
+        *i += 1;
+        let token = match tokens.get(*i) {
+            Some(token) => token,
+            None => {
+                return Err(Error::new(span_of(&tokens[*i - 1..]), "unclosed generic parameters"))
+            }
+        };
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    params.push(Vec::new());
+                    continue;
+                }
+                '>' if depth == 0 => break,
+                '<' => depth += 1,
+                '>' if !arrow => depth -= 1,
+                _ => (),
+            }
+            arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        params.last_mut().unwrap().push(token.clone());
+    }
+    *i += 1;
+
+    params
+        .into_iter()
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            // a default is the only `=` outside of angle brackets, everything after it goes
+            let mut depth = 0;
+            let end = param
+                .iter()
+                .position(|token| match token {
+                    TokenTree::Punct(punct) => {
+                        match punct.as_char() {
+                            '<' => depth += 1,
+                            '>' => depth -= 1,
+                            _ => (),
+                        }
+                        punct.as_char() == '=' && depth == 0
+                    }
+                    _ => false,
+                })
+                .unwrap_or(param.len());
+            let name = match (&param[0], param.get(1)) {
+                (TokenTree::Punct(tick), Some(lifetime)) if tick.as_char() == '\'' => {
+                    format!("'{}", lifetime)
+                }
+                (TokenTree::Ident(ident), Some(name)) if ident.to_string() == "const" => {
+                    name.to_string()
+                }
+                (TokenTree::Ident(ident), _) => ident.to_string(),
+                _ => return Err(Error::new(param[0].span(), "expected a generic parameter")),
+            };
+            let decl = param[..end].iter().cloned().collect::<TokenStream>().to_string();
+            Ok(Generic { decl, name })
+        })
+        .collect()
+}
+
+/// Collect a visibility like `pub` or `pub(crate)`, empty for private items.
+fn visibility(tokens: &[TokenTree], i: &mut usize) -> String {
+    match tokens.get(*i) {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+            *i += 1;
+            if let Some(TokenTree::Group(group)) = tokens.get(*i) {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    *i += 1;
+                    return format!("pub{}", group);
+                }
+            }
+            "pub".into()
+        }
+        _ => String::new(),
+    }
+}
+
+impl Target {
+    /// Parse a struct into its target, or an enum into one target per variant. Every misuse of
+    /// the attributes is reported, only an item the parser can't make sense of stops it early.
+    fn parse(input: TokenStream) -> Result<Vec<Self>, Vec<Error>> {
+        let mut errors = Vec::new();
+        match Self::parse_item(input, &mut errors) {
+            Ok(targets) if errors.is_empty() => Ok(targets),
+            Ok(_) => Err(errors),
+            Err(error) => {
+                errors.push(error);
+                Err(errors)
+            }
+        }
+    }
+
+    fn parse_item(input: TokenStream, errors: &mut Vec<Error>) -> Result<Vec<Self>, Error> {
+        let tokens: Vec<_> = input.into_iter().collect();
+        let mut i = 0;
+        let attributes = struct_attributes(&tokens, &mut i, errors);
+        let vis = visibility(&tokens, &mut i);
+
+        let is_enum = match tokens.get(i) {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => false,
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "enum" => true,
+            other => {
+                let message = "TypesafeBuilder can only be derived for structs and enums";
+                return Err(Error::new(
+                    other.map_or_else(Span::call_site, TokenTree::span),
+                    message,
+                ));
+            }
+        };
+        if is_enum {
+            let arguments = [
+                "into_builder",
+                "require_ticket",
+                "build_with",
+                "preset",
+                "aliases",
+                "migrate_from",
+                "expires_in",
+                "generate_usage_test",
+                "derive_default",
+                "diff",
+            ];
+            for argument in arguments {
+                if let Some(span) = attributes.given.span(argument) {
+                    let message = format!("`{}` is only supported on structs, remove it", argument);
+                    errors.push(Error::new(span, message));
+                }
+            }
+        }
+        i += 1;
+        let (name, name_span) = match tokens.get(i) {
+            Some(TokenTree::Ident(ident)) => (ident.to_string(), ident.span()),
+            _ => {
+                return Err(Error::new(span_of(&tokens[i - 1..]), "expected the name of the type"))
+            }
+        };
+        i += 1;
+        let generics = match tokens.get(i) {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => generics(&tokens, &mut i)?,
+            _ => Vec::new(),
+        };
+        // the check runs in a free constant, which has no parameters to instantiate `FIELDS` with
+        if let (true, Some(span)) = (!generics.is_empty(), attributes.given.span("const_check")) {
+            let message = "`const_check` isn't supported on generic types, call \
+                           `typestuff::const_check!` on an instantiation of the builder instead";
+            errors.push(Error::new(span, message));
+        }
+        // the test has no parameters to instantiate the struct with
+        if let (true, Some(span)) =
+            (!generics.is_empty(), attributes.given.span("generate_usage_test"))
+        {
+            let message = "`generate_usage_test` isn't supported on generic types, write the test \
+                           for an instantiation by hand";
+            errors.push(Error::new(span, message));
+        }
+
+        // everything up to the body is the `where` clause
+        let mut where_clause = TokenStream::new();
+        while let Some(token) = tokens.get(i) {
+            match token {
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    let target = |variant, fields: Vec<Field>| {
+                        let (skipped, fields) = fields.into_iter().partition(|field| field.skip);
+                        Target {
+                            vis: vis.clone(),
+                            name: name.clone(),
+                            generics: generics.clone(),
+                            where_clause: where_clause.to_string(),
+                            strict: attributes.strict,
+                            into_builder: attributes.into_builder,
+                            require_ticket: attributes.require_ticket,
+                            build_with: attributes.build_with,
+                            const_check: attributes.const_check.clone(),
+                            preset: attributes.preset.clone(),
+                            aliases: attributes.aliases,
+                            migrate_from: attributes.migrate_from.clone(),
+                            reverse_drop: attributes.reverse_drop,
+                            expires_in: attributes.expires_in.clone(),
+                            // `setter_traits` adds to the features, whichever they are
+                            features: Features {
+                                setter_traits: attributes.setter_traits
+                                    || attributes.features.is_some_and(|on| on.setter_traits),
+                                ..attributes.features.unwrap_or(Features::ALL)
+                            },
+                            usage_test: attributes.usage_test,
+                            derive_default: attributes.derive_default,
+                            diff: attributes.diff,
+                            report: attributes.report,
+                            swap: attributes.swap.clone(),
+                            variant,
+                            phase: Phase::Whole,
+                            spec: Vec::new(),
+                            fields,
+                            skipped,
+                        }
+                    };
+                    if !is_enum {
+                        let target = target(None, Self::fields(group.stream(), errors)?);
+                        target.check_preset(&attributes.given, errors);
+                        target.check_migration(errors);
+                        // the empty builder only constructs a value if every field has a default
+                        for field in &target.fields {
+                            if target.derive_default && field.default.is_none() {
+                                let message = format!(
+                                    "`derive_default` needs a default for every field, add \
+                                     `#[builder(default)]` or `#[builder(default = expr)]` to `{}`",
+                                    field.name,
+                                );
+                                errors.push(Error::new(field.span, message));
+                            }
+                            // only the plain `construct()` has a fallible twin reading the
+                            // variables
+                            let terminal = target.require_ticket || target.expires_in.is_some();
+                            if let (Some(span), true) = (field.from_str_default, terminal) {
+                                let message = "`from_str_default` can't be combined with \
+                                               `require_ticket` or `expires_in`, which replace \
+                                               `construct()`";
+                                errors.push(Error::new(span, message));
+                            }
+                            // the test has no way to know what the guard wants
+                            if let (true, Some(_)) = (target.usage_test, &field.guard) {
+                                let message = format!(
+                                    "`generate_usage_test` can't set the guarded field `{}`, \
+                                     write the test by hand",
+                                    field.name,
+                                );
+                                let span = attributes.given.span("generate_usage_test").unwrap();
+                                errors.push(Error::new(span, message));
+                            }
+                        }
+                        for swapped in target.swap.iter().flat_map(|(a, b)| [a, b]) {
+                            if !target.fields.iter().any(|field| field.name == *swapped) {
+                                let message = format!("`{}` isn't a field of the builder", swapped);
+                                errors.push(Error::new(
+                                    attributes.given.span("__swap_fields").unwrap(),
+                                    message,
+                                ));
+                            }
+                        }
+                        if let (true, Some(span)) =
+                            (!target.generics.is_empty(), attributes.given.span("aliases"))
+                        {
+                            let message = "`aliases` isn't supported on generic structs, name \
+                                           the states with type aliases of your own";
+                            errors.push(Error::new(span, message));
+                        }
+                        if target.fields.iter().any(|field| field.phase.is_some()) {
+                            return Ok(target.split_phases(&attributes.given, errors));
+                        }
+                        return Ok(vec![target]);
+                    }
+                    let variants = Self::variants(group.stream(), errors)?;
+                    let phases = variants.iter().flat_map(|(_, fields)| fields);
+                    for span in phases.filter_map(|field| field.phase) {
+                        let message = "`phase` is only supported on the fields of structs, \
+                                       remove it";
+                        errors.push(Error::new(span, message));
+                    }
+                    return Ok(variants
+                        .into_iter()
+                        .map(|(variant, fields)| target(Some(variant), fields))
+                        .collect());
+                }
+                TokenTree::Group(_) | TokenTree::Punct(_) if where_clause.is_empty() => break,
+                _ => where_clause.extend(Some(token.clone())),
+            }
+            i += 1;
+        }
+        let message = "TypesafeBuilder needs named fields, like `struct Config { port: u16 }`";
+        Err(Error::new(name_span, message))
+    }
+
+    /// The struct's generic parameters as they are used, followed by `states`.
+    fn arguments(&self, states: Vec<String>) -> String {
+        let names = self.generics.iter().map(|generic| generic.name.clone());
+        names.chain(states).collect::<Vec<_>>().join(", ")
+    }
+
+    /// The struct's generic parameters as they are declared, followed by `states`.
+    fn parameters(&self, states: Vec<String>) -> String {
+        let decls = self.generics.iter().map(|generic| generic.decl.clone());
+        decls.chain(states).collect::<Vec<_>>().join(", ")
+    }
+
+    /// The `where` clause of the struct with `bounds` added to it.
+    fn where_with(&self, bounds: Vec<String>) -> String {
+        let clause = self.where_clause.trim_end().trim_end_matches(',');
+        if clause.is_empty() {
+            format!("where {}", bounds.join(", "))
+        } else {
+            format!("{}, {}", clause, bounds.join(", "))
+        }
+    }
+
+    /// Split a struct with `phase = 2` fields into the targets of its two builders, the spec of
+    /// the other fields and the struct itself.
+    fn split_phases(self, given: &Given, errors: &mut Vec<Error>) -> Vec<Self> {
+        let first = self.fields.iter().find_map(|field| field.phase).unwrap();
+        if !self.generics.is_empty() {
+            let message = "`phase = 2` isn't supported on generic structs, remove it";
+            errors.push(Error::new(first, message));
+        }
+        let arguments = [
+            "into_builder",
+            "require_ticket",
+            "build_with",
+            "const_check",
+            "preset",
+            "aliases",
+            "migrate_from",
+            "expires_in",
+            "generate_usage_test",
+            "derive_default",
+            "diff",
+        ];
+        for argument in arguments {
+            if let Some(span) = given.span(argument) {
+                let message = format!(
+                    "`{}` isn't supported on structs with `phase = 2` fields, remove it",
+                    argument,
+                );
+                errors.push(Error::new(span, message));
+            }
+        }
+
+        let (build, spec): (Vec<_>, Vec<_>) =
+            self.fields.into_iter().partition(|field| field.phase.is_some());
+        let spec_target = Target {
+            vis: self.vis.clone(),
+            name: format!("{}Spec", self.name),
+            generics: Vec::new(),
+            where_clause: String::new(),
+            strict: self.strict,
+            into_builder: false,
+            require_ticket: false,
+            build_with: false,
+            const_check: None,
+            preset: None,
+            aliases: false,
+            migrate_from: None,
+            reverse_drop: self.reverse_drop,
+            expires_in: None,
+            features: self.features,
+            usage_test: false,
+            derive_default: false,
+            diff: false,
+            report: None,
+            swap: None,
+            variant: None,
+            phase: Phase::Spec,
+            spec: Vec::new(),
+            fields: spec.clone(),
+            skipped: Vec::new(),
+        };
+        let build_target = Target { phase: Phase::Build, spec, fields: build, ..self };
+        vec![spec_target, build_target]
+    }
+
+    /// Split the body of the enum into its variants and their fields. Unit variants have no
+    /// fields, the fields of tuple variants have no names to call their setters by.
+    fn variants(
+        body: TokenStream,
+        errors: &mut Vec<Error>,
+    ) -> Result<Vec<(String, Vec<Field>)>, Error> {
+        let tokens: Vec<_> = body.into_iter().collect();
+        let mut variants = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            for arg in builder_arguments(&tokens, &mut i, errors) {
+                let message = "builder attributes go on the enum or the fields of a variant, \
+                               move it there";
+                errors.push(Error::new(span_of(&arg), message));
+            }
+            let name = match tokens.get(i) {
+                Some(TokenTree::Ident(ident)) => ident.to_string(),
+                _ => {
+                    return Err(Error::new(span_of(&tokens[i..]), "expected the name of a variant"))
+                }
+            };
+            i += 1;
+            let fields = match tokens.get(i) {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                    i += 1;
+                    Self::fields(group.stream(), errors)?
+                }
+                Some(TokenTree::Group(group)) => {
+                    let message = format!(
+                        "the variant `{}` needs named fields for its setters, like `{0} {{ .. }}`",
+                        name,
+                    );
+                    errors.push(Error::new(group.span(), message));
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            };
+            // skip a discriminant, up to and including the comma
+            while let Some(token) = tokens.get(i) {
+                i += 1;
+                if matches!(token, TokenTree::Punct(punct) if punct.as_char() == ',') {
+                    break;
+                }
+            }
+            variants.push((name, fields));
+        }
+        if variants.is_empty() {
+            let message = "TypesafeBuilder needs an enum with at least one variant";
+            return Err(Error::new(Span::call_site(), message));
+        }
+        Ok(variants)
+    }
+
+    /// The name of the builder, like `ConfigBuilder` or `RequestPostBuilder` for a variant.
+    fn builder_name(&self) -> String {
+        format!("{}{}Builder", self.name, self.variant.as_deref().unwrap_or(""))
+    }
+
+    /// What `construct()` builds, like `Config` or `Request::Post`.
+    fn path(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{}::{}", self.name, variant),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Split the body of the struct into fields. Commas inside the angle brackets of a type like
+    /// `HashMap<K, V>` aren't in a group of their own, so the nesting depth is tracked by hand.
+    fn fields(body: TokenStream, errors: &mut Vec<Error>) -> Result<Vec<Field>, Error> {
+        let tokens: Vec<_> = body.into_iter().collect();
+        let mut fields = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let attributes = field_attributes(&tokens, &mut i, errors);
+            let vis = visibility(&tokens, &mut i);
+            let (name, span) = match tokens.get(i) {
+                Some(TokenTree::Ident(ident)) => (ident.to_string(), ident.span()),
+                _ => return Err(Error::new(span_of(&tokens[i..]), "expected the name of a field")),
+            };
+            match tokens.get(i + 1) {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => i += 2,
+                _ => {
+                    return Err(Error::new(
+                        span,
+                        format!("expected `:` after the field `{}`", name),
+                    ))
+                }
+            }
+
+            let mut ty = TokenStream::new();
+            let mut depth = 0;
+            let mut arrow = false;
+            while let Some(token) = tokens.get(i) {
+                if let TokenTree::Punct(punct) = token {
+                    match punct.as_char() {
+                        ',' if depth == 0 => break,
+                        '<' => depth += 1,
+                        // the `>` of `->` doesn't close anything
+                        '>' if !arrow => depth -= 1,
+                        _ => (),
+                    }
+                    arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+                } else {
+                    arrow = false;
+                }
+                ty.extend(Some(token.clone()));
+                i += 1;
+            }
+            i += 1;
+            let default = match (attributes.default, attributes.skip) {
+                (None, true) => Some(DEFAULT.into()),
+                (None, _) if attributes.from_str_default.is_some() => Some(format!(
+                    "::typestuff::env::from_str_default({:?})",
+                    name.trim_start_matches("r#"),
+                )),
+                (default, _) => default,
+            };
+            fields.push(Field {
+                setter: attributes.rename.unwrap_or_else(|| name.clone()),
+                name,
+                vis,
+                ty: ty.to_string(),
+                default,
+                from_str_default: attributes.from_str_default,
+                guard: attributes.guard,
+                into: attributes.into,
+                as_ref: attributes.as_ref,
+                push: attributes.push,
+                nested: attributes.nested,
+                skip: attributes.skip,
+                sensitive: attributes.sensitive,
+                template: attributes.template,
+                shared: attributes.shared,
+                with_capacity: attributes.with_capacity,
+                test_value: attributes.test_value,
+                phase: attributes.phase,
+                span,
+            });
+        }
+        Ok(fields)
+    }
+
+    /// The builder type with the state of field `index` replaced by `state`, the others are left
+    /// as their parameters.
+    fn builder_type(&self, replace: Option<(usize, &str)>) -> String {
+        let states: Vec<_> = (0..self.fields.len())
+            .map(|i| match replace {
+                Some((index, state)) if index == i => state.to_string(),
+                _ => state_param(i),
+            })
+            .collect();
+        format!("{}<{}>", self.builder_name(), self.arguments(states))
+    }
+
+    /// The preset's fields have to be fields of the builder, and the preset has no parameters
+    /// to spell out the types of generic fields with.
+    fn check_preset(&self, given: &Given, errors: &mut Vec<Error>) {
+        let fields = match &self.preset {
+            Some((_, fields)) => fields,
+            None => return,
+        };
+        if !self.generics.is_empty() {
+            let message = "`preset` isn't supported on generic structs, remove it";
+            errors.push(Error::new(given.span("preset").unwrap(), message));
+            return;
+        }
+        for (preset, span) in fields {
+            if !self.fields.iter().any(|field| field.name == *preset) {
+                let message = format!(
+                    "`{}` isn't a field of the builder, remove it from the preset",
+                    preset,
+                );
+                errors.push(Error::new(*span, message));
+            }
+            if self.fields.iter().any(|field| field.name == *preset && field.guard.is_some()) {
+                let message = format!(
+                    "`{}` is guarded, a preset would set it without asking its guard, remove it \
+                     from the preset",
+                    preset,
+                );
+                errors.push(Error::new(*span, message));
+            }
+        }
+    }
+
+    /// The deadline of a builder started now by the system clock, for builders that expire.
+    fn started(&self) -> Option<String> {
+        let clock = "&::typestuff::clock::SystemClock";
+        let ttl = self.expires_in.as_ref()?;
+        Some(format!("__typestuff_deadline: ::typestuff::expiry::deadline({}, {})", clock, ttl))
+    }
+
+    /// The members of the builder that aren't fields, which setters move over as they are.
+    fn carried(&self) -> Vec<&'static str> {
+        let mut carried = Vec::new();
+        if self.variant.is_some() {
+            carried.push("__typestuff_target");
+        }
+        if self.phase == Phase::Build {
+            carried.push("__typestuff_spec");
+        }
+        if self.expires_in.is_some() {
+            carried.push("__typestuff_deadline");
+        }
+        carried
+    }
+
+    /// The field of the old builder each field moves over from, `None` for new fields. A field
+    /// mapped from another one doesn't take the old field of its own name, which is dropped
+    /// unless it is mapped elsewhere.
+    fn migration_sources(&self, migration: &Migration) -> Vec<Option<usize>> {
+        let old = |name: &str| migration.fields.iter().position(|(field, _)| field == name);
+        let mapped = |name: &str| migration.map.iter().any(|((from, _), _)| from == name);
+        self.fields
+            .iter()
+            .map(|field| match migration.map.iter().find(|(_, (to, _))| *to == field.name) {
+                Some(((from, _), _)) => old(from),
+                None if !mapped(&field.name) => old(&field.name),
+                None => None,
+            })
+            .collect()
+    }
+
+    fn check_migration(&self, errors: &mut Vec<Error>) {
+        let migration = match &self.migrate_from {
+            Some(migration) => migration,
+            None => return,
+        };
+        if !self.generics.is_empty() {
+            let message = "`migrate_from` isn't supported on generic structs, remove it";
+            errors.push(Error::new(migration.span, message));
+            return;
+        }
+        let old = |name: &str| migration.fields.iter().any(|(field, _)| field == name);
+        let unknown = |name: &str| {
+            format!("`{}` isn't a field of `{}`, add it to `fields(...)`", name, migration.name)
+        };
+        let mut seen = Vec::new();
+        for ((from, from_span), (to, to_span)) in &migration.map {
+            if !old(from) {
+                errors.push(Error::new(*from_span, unknown(from)));
+            }
+            if !self.fields.iter().any(|field| field.name == *to) {
+                let message = format!("`{}` isn't a field of the builder, remove the `map`", to);
+                errors.push(Error::new(*to_span, message));
+            }
+            for name in [from, to] {
+                if seen.contains(&name) {
+                    let message = format!("`{}` is mapped twice, remove one `map`", name);
+                    errors.push(Error::new(*from_span, message));
+                }
+                seen.push(name);
+            }
+        }
+        let mut seen = Vec::new();
+        for ((field, span), _) in &migration.transform {
+            if !old(field) {
+                errors.push(Error::new(*span, unknown(field)));
+            } else if seen.contains(&field) {
+                let message = format!("`{}` is transformed twice, remove one `transform`", field);
+                errors.push(Error::new(*span, message));
+            }
+            seen.push(field);
+        }
+
+        // what isn't moved over is dropped, which `strict` rejects
+        let sources = self.migration_sources(migration);
+        if migration.strict {
+            for (index, (field, span)) in migration.fields.iter().enumerate() {
+                if !sources.contains(&Some(index)) {
+                    let message = format!(
+                        "`{}` of `{}` has no field to move into, `map` it or remove `strict`",
+                        field, migration.name,
+                    );
+                    errors.push(Error::new(*span, message));
+                }
+            }
+        }
+    }
+
+    /// `from_<old>()`, moving the fields of the old builder over, and the check that the fields
+    /// it was told about are those of the old builder.
+    fn migration(&self) -> String {
+        let migration = match &self.migrate_from {
+            Some(migration) => migration,
+            None => return String::new(),
+        };
+        let sources = self.migration_sources(migration);
+        let mut states = Vec::new();
+        let mut moved = Vec::new();
+        let mut fresh = Vec::new();
+        for (field, source) in self.fields.iter().zip(&sources) {
+            let source = match source {
+                Some(source) => *source,
+                None => {
+                    states.push("::typestuff::Unset".to_string());
+                    moved.push(format!("{}: ::typestuff::slot::Slot::empty()", field.name));
+                    fresh.push(format!("`{}`", field.name));
+                    continue;
+                }
+            };
+            let old = &migration.fields[source].0;
+            states.push(migration_param(source));
+            moved.push(match migration.transform.iter().find(|((from, _), _)| from == old) {
+                Some((_, with)) => format!(
+                    "{}: ::typestuff::slot::Slot::from_option(builder.{}.into_option().map({}))",
+                    field.name, old, with,
+                ),
+                None => format!("{}: builder.{}", field.name, old),
+            });
+        }
+        moved.extend(self.started());
+        let dropped: Vec<_> = (migration.fields.iter().enumerate())
+            .filter(|(index, _)| !sources.contains(&Some(*index)))
+            .map(|(_, (field, _))| format!("`{}`", field))
+            .collect();
+
+        let mut doc = format!("Move the fields of a `{}` over.", migration.name);
+        if !fresh.is_empty() {
+            doc += &format!(" New fields start out unset: {}.", fresh.join(", "));
+        }
+        if !dropped.is_empty() {
+            doc += &format!(" Dropped, with no field to move into: {}.", dropped.join(", "));
+        }
+        let params: Vec<_> = (0..migration.fields.len())
+            .map(|index| format!("{}: ::typestuff::FieldState", migration_param(index)))
+            .collect();
+        let old_states: Vec<_> = (0..migration.fields.len()).map(migration_param).collect();
+        let old_unset = vec!["::typestuff::Unset"; migration.fields.len()];
+        let names: Vec<_> =
+            migration.fields.iter().map(|(field, _)| format!("{:?}", field)).collect();
+        let old_name = migration.name.trim_end_matches("Builder");
+        let out = format!("{}<{}>", self.builder_name(), states.join(", "));
+        let message = format!(
+            "`fields(...)` of `migrate_from` has to list the fields of `{}` in order",
+            migration.name,
+        );
+        format!(
+            "#[allow(dead_code)]
+            impl {unset} {{
+                #[doc = {doc:?}]
+                pub fn from_{snake}<{params}>(builder: {from}<{old_states}>) -> {out} {{
+                    {name} {{ {moved} }}
+                }}
+            }}
+
+            const _: () = ::core::assert!(
+                ::typestuff::reflection::names_are(<{from}<{old_unset}>>::FIELDS, &[{names}]),
+                {message:?},
+            );",
+            unset = self.builder_in("::typestuff::Unset"),
+            doc = doc,
+            snake = screaming_snake_case(old_name).to_lowercase(),
+            params = params.join(", "),
+            from = migration.from,
+            old_states = old_states.join(", "),
+            out = out,
+            name = self.builder_name(),
+            moved = moved.join(", "),
+            old_unset = old_unset.join(", "),
+            names = names.join(", "),
+            message = message,
+        )
+    }
+
+    /// The preset struct and `apply`, setting all of its fields at once.
+    fn preset(&self) -> String {
+        let (preset, names) = match &self.preset {
+            Some(preset) => preset,
+            None => return String::new(),
+        };
+        let mut declared = String::new();
+        let mut once = Vec::new();
+        let fields = self.fields.iter().enumerate();
+        let in_preset = |field: &Field| names.iter().any(|(name, _)| *name == field.name);
+        for (index, field) in fields.filter(|(_, field)| in_preset(field)) {
+            declared += &format!("pub {}: {},\n", field.name, field.ty);
+            if self.strict {
+                once.push(format!("{}: ::typestuff::IsUnset", state_param(index)));
+            }
+        }
+        let mut moved = Vec::new();
+        let mut states = Vec::new();
+        for (index, field) in self.fields.iter().enumerate() {
+            if in_preset(field) {
+                let value = field.wrap(&format!("preset.{}", field.name));
+                moved.push(format!("{}: self.{}.write({})", field.name, field.name, value));
+                states.push("::typestuff::Set".to_string());
+            } else {
+                moved.push(format!("{0}: self.{0}", field.name));
+                states.push(state_param(index));
+            }
+        }
+        moved.extend(self.carried().iter().map(|member| format!("{0}: self.{0}", member)));
+        let out = format!("{}<{}>", self.builder_name(), states.join(", "));
+        let params: Vec<_> = (0..self.fields.len())
+            .map(|i| format!("{}: ::typestuff::FieldState", state_param(i)))
+            .collect();
+        format!(
+            "#[doc = \"Values for some of the fields of [`{target}`], set all at once by `apply`.\"]
+            {vis} struct {preset} {{
+                {declared}
+            }}
+
+            #[allow(dead_code)]
+            impl<{params}> {generic} {where_clause} {{
+                /// Set every field of the preset, the other fields keep their states.
+                #[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                pub fn apply(self, preset: {preset}) -> {out} where {once} {{
+                    {name} {{ {moved} }}
+                }}
+            }}",
+            target = self.name,
+            vis = self.vis,
+            preset = preset,
+            declared = declared,
+            params = params.join(", "),
+            generic = self.builder_type(None),
+            where_clause = self.where_clause,
+            out = out,
+            once = once.join(", "),
+            name = self.builder_name(),
+            moved = moved.join(", "),
+        )
+    }
+
+    /// `Empty<Builder>` and `Complete<Builder>`, and for every field `<Builder>With<Field>` with
+    /// only that field set and `<Builder>Without<Field>` with all but that field set.
+    fn aliases(&self) -> String {
+        if !self.aliases {
+            return String::new();
+        }
+        let name = self.builder_name();
+        let alias = |alias: String, doc: String, set: &dyn Fn(usize) -> bool| {
+            let states: Vec<_> = (0..self.fields.len())
+                .map(|i| if set(i) { "::typestuff::Set" } else { "::typestuff::Unset" })
+                .collect();
+            format!(
+                "#[doc = \"`{name}` with {doc}.\"]
+                #[allow(dead_code)]
+                {vis} type {alias} = {name}<{states}>;\n",
+                name = name,
+                doc = doc,
+                vis = self.vis,
+                alias = alias,
+                states = states.join(", "),
+            )
+        };
+        let mut aliases = alias(format!("Empty{}", name), "no field set".into(), &|_| false);
+        aliases += &alias(format!("Complete{}", name), "every field set".into(), &|_| true);
+        for (index, field) in self.fields.iter().enumerate() {
+            let camel = camel_case(&field.name);
+            let only = format!("only `{}` set", field.name);
+            aliases += &alias(format!("{}With{}", name, camel), only, &|i| i == index);
+            let others = format!("every field but `{}` set", field.name);
+            aliases += &alias(format!("{}Without{}", name, camel), others, &|i| i != index);
+        }
+        aliases
+    }
+
+    /// The slot `construct()` takes the value of `field` from, its own unless `__swap_fields`
+    /// mixes it up with another one.
+    fn slot_of<'a>(&'a self, field: &'a str) -> &'a str {
+        match &self.swap {
+            Some((a, b)) if a == field => b,
+            Some((a, b)) if b == field => a,
+            _ => field,
+        }
+    }
+
+    /// A test calling every setter once with the field's `test_value` and comparing the result
+    /// with a struct literal of the same values, so a setter writing to the wrong slot or a
+    /// `construct()` mixing up fields of the same type fails it.
+    fn usage_test(&self) -> String {
+        if !self.usage_test {
+            return String::new();
+        }
+        // evaluated once for the setter and once for the literal, typed so `into` fields and
+        // literals like `Default::default()` know what to turn into
+        let value = |field: &Field| {
+            let value = field.test_value.clone().unwrap_or_else(|| {
+                format!("<{} as ::core::default::Default>::default()", field.ty)
+            });
+            let value =
+                if field.into { format!("::core::convert::Into::into({})", value) } else { value };
+            format!("{{ let value: {} = {}; value }}", field.ty, value)
+        };
+        let setters: String = self
+            .fields
+            .iter()
+            .map(|field| format!(".{}({})", field.setter, value(field)))
+            .collect();
+        let mut literal: Vec<_> =
+            self.fields.iter().map(|field| format!("{}: {}", field.name, value(field))).collect();
+        for field in &self.skipped {
+            literal.push(format!("{}: {}", field.name, field.default.as_ref().unwrap()));
+        }
+        let built = if self.require_ticket {
+            format!(
+                "{{
+                    let (builder, ticket) = {}::builder();
+                    builder{}.construct_with_ticket(ticket)
+                }}",
+                self.name, setters,
+            )
+        } else if self.expires_in.is_some() {
+            format!("{}::builder(){}.construct().unwrap()", self.name, setters)
+        } else if self.fields.iter().any(|field| field.from_str_default.is_some()) {
+            // `construct()` is left out with `no-panic`
+            format!("{}::builder(){}.try_construct().unwrap()", self.name, setters)
+        } else {
+            format!("{}::builder(){}.construct()", self.name, setters)
+        };
+        // the bug put in by `__swap_fields` fails the test, the derive's tests run it themselves
+        let ignore = match &self.swap {
+            Some(_) => "#[ignore = \"`__swap_fields` mixes up fields on purpose\"]",
+            None => "",
+        };
+        let message = format!(
+            "a setter of `{}` wrote to the wrong field, or `construct()` mixed up the fields",
+            self.builder_name(),
+        );
+        format!(
+            "#[cfg(test)]
+            #[test]
+            {ignore}
+            fn {snake}_builder_sets_every_field() {{
+                let built = {built};
+                let expected = {name} {{ {literal} }};
+                ::core::assert!(built == expected, {message:?});
+            }}",
+            ignore = ignore,
+            snake = screaming_snake_case(&self.name).to_lowercase(),
+            built = built,
+            name = self.name,
+            literal = literal.join(", "),
+            message = message,
+        )
+    }
+
+    /// The builder type with every field in `state`.
+    fn builder_in(&self, state: &str) -> String {
+        let states = vec![state.to_string(); self.fields.len()];
+        format!("{}<{}>", self.builder_name(), self.arguments(states))
+    }
+
+    fn builder(&self) -> String {
+        let name = &self.builder_name();
+        let path = &self.path();
+        let vis = &self.vis;
+        let states: Vec<_> = (0..self.fields.len())
+            .map(|i| format!("{}: ::typestuff::FieldState", state_param(i)))
+            .collect();
+        let params = self.parameters(states);
+        let generics = self.parameters(Vec::new());
+        let target = format!("{}<{}>", self.name, self.arguments(Vec::new()));
+        let where_clause = &self.where_clause;
+        let unset = self.builder_in("::typestuff::Unset");
+        let generic = self.builder_type(None);
+
+        // `construct()` is generic over the states of the optional fields, the required ones
+        // have to be `Set`
+        let mut optional = Vec::new();
+        let mut complete = Vec::new();
+        for (index, field) in self.fields.iter().enumerate() {
+            if field.default.is_some() {
+                optional.push(format!("{}: ::typestuff::FieldState", state_param(index)));
+                complete.push(state_param(index));
+            } else {
+                complete.push("::typestuff::Set".into());
+            }
+        }
+        let optional = self.parameters(optional);
+        let complete = format!("{}<{}>", name, self.arguments(complete));
+
+        let mut slots = String::new();
+        let mut empty = String::new();
+        let mut cloned = String::new();
+        let mut clone_bounds = Vec::new();
+        let mut debug = String::new();
+        let mut debug_bounds = Vec::new();
+        let mut infos = Vec::new();
+        let mut states = Vec::new();
+        // a variant may not use every parameter of the enum, the marker uses them all
+        if self.variant.is_some() {
+            let marker = format!("::core::marker::PhantomData<fn() -> {}>", target);
+            slots += &format!("__typestuff_target: {},\n", marker);
+            empty += "__typestuff_target: ::core::marker::PhantomData,\n";
+            cloned += "__typestuff_target: ::core::marker::PhantomData,\n";
+        }
+        // the deadline is taken when the builder is started, clones expire with the original
+        if let Some(started) = self.started() {
+            slots += "__typestuff_deadline: ::core::option::Option<::std::time::SystemTime>,\n";
+            empty += &format!("{},\n", started);
+            cloned += "__typestuff_deadline: self.__typestuff_deadline,\n";
+        }
+        // the second phase holds the spec as it is and takes its fields apart in `construct()`
+        let spec = format!("{}Spec", self.name);
+        let mut construct = String::new();
+        if self.phase == Phase::Build {
+            slots += &format!("__typestuff_spec: {},\n", spec);
+            for field in &self.spec {
+                construct += &format!("{0}: self.__typestuff_spec.{0},\n", field.name);
+            }
+        }
+        // `try_construct()` reports unparsable environment defaults instead of panicking
+        let mut try_construct = construct.clone();
+        let mut field_slots = Vec::new();
+        let mut setters = String::new();
+        let mut setter_traits = String::new();
+        for (index, field) in self.fields.iter().enumerate() {
+            let (field_name, ty) = (&field.name, &field.ty);
+            let stored = &field.stored();
+            field_slots.push(format!(
+                "{}: ::typestuff::slot::Slot<{}, {}>,\n",
+                field_name,
+                stored,
+                state_param(index),
+            ));
+            empty += &format!("{}: ::typestuff::slot::Slot::empty(),\n", field_name);
+            // the slots only clone what is set. the bound is on the slot instead of the type,
+            // a bound like `Server: Clone` on a type that isn't would be an error of its own
+            cloned += &format!("{0}: ::core::clone::Clone::clone(&self.{0}),\n", field_name);
+            clone_bounds.push(format!(
+                "::typestuff::slot::Slot<{}, {}>: ::core::clone::Clone",
+                stored,
+                state_param(index),
+            ));
+            infos.push(format!(
+                "::typestuff::reflection::FieldInfo {{
+                    name: {:?},
+                    type_name: {:?},
+                    required: {},
+                    has_default: {},
+                    sensitive: {},
+                }}",
+                field_name.trim_start_matches("r#"),
+                type_name(ty),
+                field.default.is_none(),
+                field.default.is_some(),
+                field.sensitive,
+            ));
+            states.push(format!("<{} as ::typestuff::FieldState>::IS_SET", state_param(index)));
+
+            // unset fields show up as `<unset>`
+            debug += &format!(".field({:?}, &self.{})", field_name, field_name);
+            debug_bounds.push(format!(
+                "::typestuff::slot::Slot<{}, {}>: ::core::fmt::Debug",
+                stored,
+                state_param(index),
+            ));
+            let slot = self.slot_of(field_name);
+            if field.from_str_default.is_some() {
+                try_construct += &format!(
+                    "{}: match self.{}.into_option() {{
+                        ::core::option::Option::Some(value) => {}(value),
+                        ::core::option::Option::None => {{
+                            ::typestuff::env::try_from_str_default({:?})?
+                        }}
+                    }},\n",
+                    field_name,
+                    slot,
+                    field.unwrap().unwrap_or_default(),
+                    field_name.trim_start_matches("r#"),
+                );
+            }
+            let line = match (&field.default, field.unwrap()) {
+                (Some(default), Some(unwrap)) => format!(
+                    "{}: self.{}.into_option().map({}).unwrap_or_else(|| {}),\n",
+                    field_name, slot, unwrap, default,
+                ),
+                (None, Some(unwrap)) => {
+                    format!("{}: {}(self.{}.into_inner()),\n", field_name, unwrap, slot)
+                }
+                (Some(default), None) => format!(
+                    "{}: self.{}.into_option().unwrap_or_else(|| {}),\n",
+                    field_name,
+                    slot,
+                    default,
+                ),
+                (None, None) => format!("{}: self.{}.into_inner(),\n", field_name, slot),
+            };
+            if field.from_str_default.is_none() {
+                try_construct += &line;
+            }
+            construct += &line;
+
+            let (param, value) = if field.into || field.shared.is_some() {
+                let param = format!("impl ::core::convert::Into<{}>", stored);
+                (param, format!("{}.into()", field_name))
+            } else if let Some(borrowed) = &field.as_ref {
+                let param = format!("impl ::core::convert::AsRef<{}>", borrowed);
+                (param, format!("::std::borrow::ToOwned::to_owned({}.as_ref())", field_name))
+            } else {
+                (ty.clone(), field_name.clone())
+            };
+            // the fields of the new builder, with `slot` as the one for this field
+            let moved = |slot: &str| {
+                let mut moved: Vec<_> = self
+                    .fields
+                    .iter()
+                    .map(|other| {
+                        if other.name == *field_name {
+                            format!("{}: {}", field_name, slot)
+                        } else {
+                            format!("{0}: self.{0}", other.name)
+                        }
+                    })
+                    .collect();
+                moved.extend(self.carried().iter().map(|member| format!("{0}: self.{0}", member)));
+                moved.join(", ")
+            };
+            // strict builders only set fields that haven't been set yet
+            let once = if self.strict {
+                format!("{}: ::typestuff::IsUnset", state_param(index))
+            } else {
+                String::new()
+            };
+            let mut out = self.builder_type(Some((index, "::typestuff::Set")));
+            if let Some(guard) = &field.guard {
+                // the guard sees the value as it is stored, a denied setter hands the builder back
+                out = format!(
+                    "::core::result::Result<{}, ::typestuff::guard::GuardError<Self>>",
+                    out,
+                );
+                setters += &format!(
+                    "#[doc = \"Guarded by `{guard}`, which has to allow the value.\"]
+                    pub fn {setter}(self, {field}: {ty}) -> {out} where {once} {{
+                        let {field}: {stored} = {value};
+                        if !::typestuff::guard::Guard::allows(&{guard}, &{field}) {{
+                            let error = ::typestuff::guard::GuardError::__derived({field:?}, self);
+                            return ::core::result::Result::Err(error);
+                        }}
+                        ::core::result::Result::Ok({name} {{ {moved} }})
+                    }}\n",
+                    guard = guard,
+                    setter = field.setter,
+                    field = field_name,
+                    ty = param,
+                    out = out,
+                    once = once,
+                    stored = stored,
+                    value = value,
+                    name = name,
+                    moved = moved(&format!("self.{0}.write({0})", field_name)),
+                );
+            } else {
+                setters += &format!(
+                    "#[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                    pub fn {setter}(self, {field}: {ty}) -> {out} where {once} {{
+                        {name} {{ {moved} }}
+                    }}\n",
+                    setter = field.setter,
+                    field = field_name,
+                    ty = param,
+                    once = once,
+                    out = out,
+                    name = name,
+                    moved = moved(&format!("self.{}.write({})", field_name, value)),
+                );
+            }
+
+            // a trait per setter, implemented for every state and whatever the setter accepts,
+            // for code forwarding values to builders it doesn't name. the `impl Trait` argument
+            // of the setter becomes a parameter of the trait
+            if self.features.setter_traits {
+                let (value, impl_params, mut bounds) = match param.strip_prefix("impl ") {
+                    Some(bound) => (
+                        "__TypestuffV".to_string(),
+                        format!("{}, __TypestuffV", params),
+                        vec![format!("__TypestuffV: {}", bound)],
+                    ),
+                    None => (param.clone(), params.clone(), Vec::new()),
+                };
+                if self.strict {
+                    bounds.push(once.clone());
+                }
+                let impl_where = if bounds.is_empty() {
+                    where_clause.to_string()
+                } else {
+                    self.where_with(bounds)
+                };
+                setter_traits += &format!(
+                    "#[doc = \"Set `{field}` of [`{name}`] in any state, like `{setter}()`.\"]
+                    {vis} trait {trait_name}<__TypestuffV> {{
+                        type Out;
+
+                        fn {method}(self, {field}: __TypestuffV) -> Self::Out;
+                    }}
+
+                    impl<{impl_params}> {trait_name}<{value}> for {generic} {impl_where} {{
+                        type Out = {out};
+
+                        fn {method}(self, {field}: {value}) -> Self::Out {{
+                            self.{setter}({field})
+                        }}
+                    }}\n",
+                    field = field_name,
+                    name = name,
+                    setter = field.setter,
+                    vis = vis,
+                    trait_name = format!("{}Set{}", name, camel_case(&field.setter)),
+                    method = format!("set_{}", field.setter.trim_start_matches("r#")),
+                    impl_params = impl_params,
+                    value = value,
+                    generic = generic,
+                    impl_where = impl_where,
+                    out = out,
+                );
+            }
+
+            if self.features.unset {
+                setters += &format!(
+                    "#[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                    pub fn unset_{setter}(self) -> {out} {{
+                        {name} {{ {moved} }}
+                    }}\n",
+                    setter = field.setter,
+                    out = self.builder_type(Some((index, "::typestuff::Unset"))),
+                    name = name,
+                    moved = moved(&format!("self.{}.clear()", field_name)),
+                );
+            }
+
+            if let Some(push) = &field.push {
+                setters += &format!(
+                    "#[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                    pub fn {push}(
+                        self,
+                        item: <{ty} as ::core::iter::IntoIterator>::Item,
+                    ) -> {out} {{
+                        let mut {field} = self.{field}.into_option().unwrap_or_default();
+                        let item = ::core::option::Option::Some(item);
+                        ::core::iter::Extend::extend(&mut {field}, item);
+                        {name} {{ {moved} }}
+                    }}\n",
+                    push = push,
+                    field = field_name,
+                    ty = ty,
+                    out = self.builder_type(Some((index, "::typestuff::Set"))),
+                    name = name,
+                    moved = moved(&format!("::typestuff::slot::Slot::new({})", field_name)),
+                );
+            }
+
+            let out = self.builder_type(Some((index, "::typestuff::Set")));
+            // the reservation is a value like any other, the setter of the field replaces it
+            if field.with_capacity {
+                setters += &format!(
+                    "#[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                    pub fn {setter}_with_capacity(self, capacity: usize) -> {out} where {once} {{
+                        self.{setter}(<{ty}>::with_capacity(capacity))
+                    }}\n",
+                    setter = field.setter,
+                    out = out,
+                    once = once,
+                    ty = ty,
+                );
+            }
+            setters += &match field.template {
+                Some(Template::Duration) => format!(
+                    "pub fn {setter}_secs(self, secs: u64) -> {out} where {once} {{
+                        self.{setter}(::std::time::Duration::from_secs(secs))
+                    }}
+
+                    pub fn {setter}_ms(self, millis: u64) -> {out} where {once} {{
+                        self.{setter}(::std::time::Duration::from_millis(millis))
+                    }}\n",
+                    setter = field.setter,
+                    out = out,
+                    once = once,
+                ),
+                Some(Template::Path) => format!(
+                    "pub fn {setter}_path<__TypestuffP>(self, path: __TypestuffP) -> {out}
+                    where
+                        __TypestuffP: ::core::convert::AsRef<::std::path::Path>,
+                        {once}
+                    {{
+                        self.{setter}(::std::path::Path::to_path_buf(path.as_ref()))
+                    }}\n",
+                    setter = field.setter,
+                    out = out,
+                    once = once,
+                ),
+                Some(Template::Addr) => format!(
+                    "pub fn try_{setter}(
+                        self,
+                        input: &str,
+                    ) -> ::core::result::Result<{out}, ::typestuff::error::BuildError>
+                    where
+                        {once}
+                    {{
+                        match <{ty} as ::core::str::FromStr>::from_str(input) {{
+                            ::core::result::Result::Ok(parsed) => {{
+                                ::core::result::Result::Ok(self.{setter}(parsed))
+                            }}
+                            ::core::result::Result::Err(err) => ::core::result::Result::Err(
+                                ::typestuff::presets::invalid({name:?}, input, err),
+                            ),
+                        }}
+                    }}\n",
+                    setter = field.setter,
+                    out = out,
+                    once = once,
+                    ty = ty,
+                    name = field_name.trim_start_matches("r#"),
+                ),
+                None => String::new(),
+            };
+
+            if field.nested {
+                setters += &format!(
+                    "#[must_use = \"setter returns a new builder; did you mean to chain?\"]
+                    pub fn {setter}_with<__TypestuffF, __TypestuffB>(
+                        self,
+                        configure: __TypestuffF,
+                    ) -> {out}
+                    where
+                        __TypestuffF: ::core::ops::FnOnce(
+                            <{ty} as ::typestuff::nested::HasBuilder>::Builder,
+                        ) -> __TypestuffB,
+                        __TypestuffB: ::typestuff::nested::Complete<Output = {ty}>,
+                        {once}
+                    {{
+                        let {field} = ::typestuff::nested::build(configure);
+                        {name} {{ {moved} }}
+                    }}\n",
+                    setter = field.setter,
+                    field = field_name,
+                    ty = ty,
+                    once = once,
+                    out = self.builder_type(Some((index, "::typestuff::Set"))),
+                    name = name,
+                    moved = moved(&format!("self.{}.write({})", field_name, field_name)),
+                );
+            }
+        }
+
+        // the slots are dropped in the order they are declared in, which is the drop order of an
+        // abandoned builder
+        if self.reverse_drop {
+            field_slots.reverse();
+        }
+        slots += &field_slots.concat();
+
+        // skipped fields are filled with their defaults whatever the builder holds
+        for field in &self.skipped {
+            let line = format!("{}: {},\n", field.name, field.default.as_ref().unwrap());
+            construct += &line;
+            try_construct += &line;
+        }
+
+        // the fields are moved into the slots as they are, nothing is copied but the skipped
+        // fields are dropped, `construct()` fills them in again
+        let round_trip = if self.into_builder {
+            let mut names: Vec<_> = self.fields.iter().map(|field| field.name.clone()).collect();
+            let slot = |field: &Field| {
+                format!("{}: ::typestuff::slot::Slot::new({})", field.name, field.wrap(&field.name))
+            };
+            let mut slots: Vec<_> = self.fields.iter().map(slot).collect();
+            slots.extend(self.started());
+            if !self.skipped.is_empty() {
+                names.push("..".into());
+            }
+            format!(
+                "impl<{generics}> ::core::convert::From<{target}> for {set} {where_clause} {{
+                    fn from(value: {target}) -> Self {{
+                        let {path} {{ {names} }} = value;
+                        {name} {{ {slots} }}
+                    }}
+                }}
+
+                #[allow(dead_code)]
+                impl<{generics}> {target} {where_clause} {{
+                    /// A complete builder holding the fields of this value, to change some of
+                    /// them and construct it again.
+                    {vis} fn into_builder(self) -> {set} {{
+                        ::core::convert::From::from(self)
+                    }}
+                }}",
+                generics = generics,
+                target = target,
+                set = self.builder_in("::typestuff::Set"),
+                where_clause = where_clause,
+                path = path,
+                names = names.join(", "),
+                name = name,
+                slots = slots.join(", "),
+                vis = vis,
+            )
+        } else {
+            String::new()
+        };
+
+        // ticketed builders are started with their ticket and can't be nested, the builder of the
+        // outer value would have nothing to redeem it with
+        let ticket = "::typestuff::ticket::BuildTicket";
+        // the second phase can't be started empty, `builder()` starts with the spec instead
+        let spec_builder =
+            format!("{}Builder<{}>", spec, vec!["::typestuff::Unset"; self.spec.len()].join(", "));
+        // `build_with` builds through the traits of `typestuff::nested`, whatever the features
+        let nested = self.features.nested || self.build_with;
+        let (start, start_fn, has_builder) = if self.require_ticket {
+            (
+                format!("({}, {})", unset, ticket),
+                format!("{}::new_with_ticket", name),
+                String::new(),
+            )
+        } else if self.phase == Phase::Build {
+            (spec_builder.clone(), format!("<{}>::new", spec_builder), String::new())
+        } else if !nested {
+            (unset.clone(), format!("{}::new", name), String::new())
+        } else {
+            let has_builder = format!(
+                "impl<{generics}> ::typestuff::nested::HasBuilder for {target} {where_clause} {{
+                    type Builder = {unset};
+
+                    fn builder() -> {unset} {{
+                        {name}::new()
+                    }}
+                }}",
+                generics = generics,
+                target = target,
+                where_clause = where_clause,
+                unset = unset,
+                name = name,
+            );
+            (unset.clone(), format!("{}::new", name), has_builder)
+        };
+
+        // `construct()` panics on an environment default it can't parse, so it and everything
+        // going through it is left out with the `no-panic` feature of `typestuff`
+        let env_defaults = self.fields.iter().any(|field| field.from_str_default.is_some());
+        let panicking = |items: String| {
+            if env_defaults && !items.is_empty() {
+                format!("::typestuff::__unless_no_panic! {{ {} }}", items)
+            } else {
+                items
+            }
+        };
+
+        let build_with = if self.build_with {
+            format!(
+                "#[allow(dead_code)]
+                impl<{generics}> {target} {where_clause} {{
+                    /// Build a value without naming the builder. The closure has to return a
+                    /// complete builder, forgetting a required field is a type error in the
+                    /// closure.
+                    {vis} fn build_with<__TypestuffF, __TypestuffB>(
+                        configure: __TypestuffF,
+                    ) -> Self
+                    where
+                        __TypestuffF: ::core::ops::FnOnce({unset}) -> __TypestuffB,
+                        __TypestuffB: ::typestuff::nested::Complete<Output = Self>,
+                    {{
+                        ::typestuff::nested::build(configure)
+                    }}
+                }}",
+                generics = generics,
+                target = target,
+                where_clause = where_clause,
+                vis = vis,
+                unset = unset,
+            )
+        } else {
+            String::new()
+        };
+
+        // every field has a default, so the empty builder is complete and `Default` can't drift
+        // apart from the defaults of the builder
+        let derive_default = if self.derive_default {
+            format!(
+                "impl<{generics}> ::core::default::Default for {target} {where_clause} {{
+                    /// The value an empty builder constructs, every field filled with its default.
+                    fn default() -> Self {{
+                        <{unset}>::new().construct()
+                    }}
+                }}",
+                generics = generics,
+                target = target,
+                where_clause = where_clause,
+                unset = unset,
+            )
+        } else {
+            String::new()
+        };
+        let (build_with, derive_default) = (panicking(build_with), panicking(derive_default));
+
+        // the value compares every field, the builder only those it holds. sensitive fields are
+        // reported as changed without their values
+        let diff = if self.diff {
+            let name = |field: &Field| field.name.trim_start_matches("r#").to_string();
+            let values: String = self
+                .fields
+                .iter()
+                .chain(&self.skipped)
+                .map(|field| {
+                    format!(
+                        ".__field({:?}, {}, &self.{2}, &other.{2})",
+                        name(field),
+                        field.sensitive,
+                        field.name,
+                    )
+                })
+                .collect();
+            let slots: String = self
+                .fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        ".__field({:?}, {}, self.{2}.get(), other.{2}.get())",
+                        name(field),
+                        field.sensitive,
+                        field.name,
+                    )
+                })
+                .collect();
+            // the bounds only matter for generic types, on the others they would move the error
+            // about a field that isn't `Debug` away from the field
+            let compared =
+                |ty: String| format!("{}: ::core::cmp::PartialEq + ::core::fmt::Debug", ty);
+            let (value_where, builder_where) = if self.generics.is_empty() {
+                (where_clause.to_string(), where_clause.to_string())
+            } else {
+                let types = self.fields.iter().chain(&self.skipped).map(|field| field.ty.clone());
+                let stored = self.fields.iter().map(Field::stored);
+                (
+                    self.where_with(types.map(compared).collect()),
+                    self.where_with(stored.map(compared).collect()),
+                )
+            };
+            format!(
+                "#[allow(dead_code)]
+                impl<{generics}> {target} {value_where} {{
+                    /// The fields that differ between `self` (old) and `other` (new).
+                    {vis} fn diff(&self, other: &Self) -> ::typestuff::diff::Diff {{
+                        ::typestuff::diff::Diff::default(){values}
+                    }}
+                }}
+
+                #[allow(dead_code)]
+                impl<{generics}> {set} {builder_where} {{
+                    /// The fields that differ between the values `self` (old) and `other` (new)
+                    /// would construct.
+                    pub fn diff(&self, other: &Self) -> ::typestuff::diff::Diff {{
+                        ::typestuff::diff::Diff::default(){slots}
+                    }}
+                }}",
+                generics = generics,
+                target = target,
+                value_where = value_where,
+                vis = vis,
+                values = values,
+                set = self.builder_in("::typestuff::Set"),
+                builder_where = builder_where,
+                slots = slots,
+            )
+        } else {
+            String::new()
+        };
+
+        // the builders of variants are handed out by the builder of the enum
+        let entry = match self.variant {
+            Some(_) => String::new(),
+            None => format!(
+                "#[allow(dead_code)]
+                impl<{generics}> {target} {where_clause} {{
+                    /// A builder with no field set yet.
+                    {vis} fn builder() -> {start} {{
+                        {start_fn}()
+                    }}
+                }}
+
+                {has_builder}
+
+                {build_with}
+
+                {derive_default}
+
+                {diff}
+
+                {round_trip}
+
+                {preset}
+
+                {aliases}
+
+                {migration}
+
+                {usage_test}",
+                round_trip = round_trip,
+                usage_test = self.usage_test(),
+                preset = self.preset(),
+                aliases = self.aliases(),
+                migration = self.migration(),
+                generics = generics,
+                target = target,
+                where_clause = where_clause,
+                vis = vis,
+                start = start,
+                start_fn = start_fn,
+                has_builder = has_builder,
+                build_with = build_with,
+                derive_default = derive_default,
+                diff = diff,
+            ),
+        };
+
+        // expiring builders can take their deadline from another clock, and have a fallible
+        // terminal checking it
+        let with_clock = match &self.expires_in {
+            Some(ttl) => format!(
+                "/// A builder with no field set yet, expiring `{ttl}` after the time `clock` tells.
+                pub fn new_with_clock(clock: &impl ::typestuff::clock::Clock) -> Self {{
+                    let deadline = ::typestuff::expiry::deadline(clock, {ttl});
+                    {name} {{ __typestuff_deadline: deadline, ..Self::new() }}
+                }}",
+                ttl = ttl,
+                name = name,
+            ),
+            None => String::new(),
+        };
+        if self.expires_in.is_some() {
+            setters += "/// When the builder expires, `None` if it never does.
+                pub fn deadline(&self) -> ::core::option::Option<::std::time::SystemTime> {
+                    self.__typestuff_deadline
+                }
+
+                /// Whether the deadline has passed, `construct()` fails from then on.
+                pub fn is_expired(&self) -> bool {
+                    let clock = &::typestuff::clock::SystemClock;
+                    ::typestuff::expiry::check(clock, self.__typestuff_deadline).is_err()
+                }\n";
+        }
+
+        let new = if self.phase == Phase::Build {
+            format!(
+                "/// The builder of the second phase, holding the fields of a complete spec.
+                pub fn from_spec(spec: {spec}) -> Self {{
+                    {name} {{ __typestuff_spec: spec, {empty} }}
+                }}",
+                spec = spec,
+                name = name,
+                empty = empty,
+            )
+        } else if self.require_ticket {
+            format!(
+                "/// A builder with no field set yet and the ticket `construct_with_ticket` takes.
+                pub fn new_with_ticket() -> {start} {{
+                    ({name} {{ {empty} }}, {ticket}::__derived())
+                }}",
+                start = start,
+                name = name,
+                empty = empty,
+                ticket = ticket,
+            )
+        } else {
+            format!(
+                "pub fn new() -> Self {{
+                    {name} {{ {empty} }}
+                }}
+
+                {with_clock}",
+                name = name,
+                empty = empty,
+                with_clock = with_clock,
+            )
+        };
+        // `new` is the last item of an `impl` block the main template closes, the `Default` impl
+        // opens one of its own for the template to close
+        let new = if self.features.default && self.phase != Phase::Build && !self.require_ticket {
+            format!(
+                "{new}
+                }}
+
+                impl<{generics}> ::core::default::Default for {unset} {where_clause} {{
+                    fn default() -> Self {{
+                        Self::new()
+                    }}",
+                new = new,
+                generics = generics,
+                unset = unset,
+                where_clause = where_clause,
+            )
+        } else {
+            new
+        };
+
+        let complete_impl = if nested {
+            format!(
+                "impl<{optional}> ::typestuff::nested::Complete for {complete} {where_clause} {{
+                    type Output = {target};
+
+                    fn construct(self) -> {target} {{
+                        <{complete}>::construct(self)
+                    }}
+                }}",
+                optional = optional,
+                complete = complete,
+                where_clause = where_clause,
+                target = target,
+            )
+        } else {
+            String::new()
+        };
+        let terminal = if self.require_ticket {
+            format!(
+                "#[allow(dead_code)]
+                impl<{optional}> {complete} {where_clause} {{
+                    /// Consume the builder and the ticket that came with it and construct the
+                    /// value, optional fields that weren't set are filled with their defaults.
+                    pub fn construct_with_ticket(self, ticket: {ticket}) -> {target} {{
+                        ticket.__redeem();
+                        {path} {{ {construct} }}
+                    }}
+                }}",
+                optional = optional,
+                complete = complete,
+                where_clause = where_clause,
+                ticket = ticket,
+                target = target,
+                path = path,
+                construct = construct,
+            )
+        } else if self.expires_in.is_some() {
+            format!(
+                "#[allow(dead_code)]
+                impl<{optional}> {complete} {where_clause} {{
+                    /// Consume the builder and construct the value from the fields that were
+                    /// set, unless the builder has expired. Optional fields that weren't set are
+                    /// filled with their defaults.
+                    pub fn construct(
+                        self,
+                    ) -> ::core::result::Result<{target}, ::typestuff::error::BuildError> {{
+                        self.construct_with_clock(&::typestuff::clock::SystemClock)
+                    }}
+
+                    /// `construct()`, telling whether the builder has expired by `clock`.
+                    pub fn construct_with_clock(
+                        self,
+                        clock: &impl ::typestuff::clock::Clock,
+                    ) -> ::core::result::Result<{target}, ::typestuff::error::BuildError> {{
+                        ::typestuff::expiry::check(clock, self.__typestuff_deadline)?;
+                        ::core::result::Result::Ok({path} {{ {construct} }})
+                    }}
+                }}",
+                optional = optional,
+                complete = complete,
+                where_clause = where_clause,
+                target = target,
+                path = path,
+                construct = construct,
+            )
+        } else {
+            let terminal = panicking(format!(
+                "#[allow(dead_code)]
+                impl<{optional}> {complete} {where_clause} {{
+                    /// Consume the builder and construct the value from the fields that were
+                    /// set, optional fields that weren't are filled with their defaults.
+                    pub fn construct(self) -> {target} {{
+                        {path} {{ {construct} }}
+                    }}
+                }}
+
+                {complete_impl}",
+                optional = optional,
+                complete = complete,
+                where_clause = where_clause,
+                target = target,
+                path = path,
+                construct = construct,
+                complete_impl = complete_impl,
+            ));
+            if env_defaults {
+                format!(
+                    "{terminal}
+
+                    #[allow(dead_code)]
+                    impl<{optional}> {complete} {where_clause} {{
+                        /// `construct()`, returning a `BuildError::InvalidEnv` for a
+                        /// `BUILDER_<FIELD>` variable it can't parse instead of panicking.
+                        pub fn try_construct(
+                            self,
+                        ) -> ::core::result::Result<{target}, ::typestuff::error::BuildError> {{
+                            ::core::result::Result::Ok({path} {{ {try_construct} }})
+                        }}
+                    }}",
+                    terminal = terminal,
+                    optional = optional,
+                    complete = complete,
+                    where_clause = where_clause,
+                    target = target,
+                    path = path,
+                    try_construct = try_construct,
+                )
+            } else {
+                terminal
+            }
+        };
+
+        // generic builders have no single table for a static to point at
+        let registry = self.generics.is_empty() && self.features.registry;
+        let descriptor = if registry {
+            format!(
+                "::typestuff::__builder_descriptor!({} {}, {}, {:?});",
+                vis,
+                screaming_snake_case(name),
+                unset,
+                name,
+            )
+        } else {
+            String::new()
+        };
+
+        // the spec has no `Clone` to clone the second phase with, and a bound on it would be an
+        // error wherever it doesn't hold
+        let clone = if self.phase == Phase::Build || !self.features.clone {
+            String::new()
+        } else {
+            format!(
+                "impl<{params}> ::core::clone::Clone for {generic} {clone_where} {{
+                    fn clone(&self) -> Self {{
+                        {name} {{ {cloned} }}
+                    }}
+                }}",
+                params = params,
+                generic = generic,
+                clone_where = self.where_with(clone_bounds),
+                name = name,
+                cloned = cloned,
+            )
+        };
+
+        // the first phase declares the spec it constructs
+        let declaration = if self.phase == Phase::Spec {
+            let fields: String = self
+                .fields
+                .iter()
+                .map(|field| format!("{} {}: {},\n", field.vis, field.name, field.ty))
+                .collect();
+            format!(
+                "/// The fields of the first phase, finished by `from_spec` of the second.
+                {vis} struct {name} {{ {fields} }}",
+                vis = vis,
+                name = self.name,
+                fields = fields,
+            )
+        } else {
+            String::new()
+        };
+
+        // the note on the fallible terminal, where it shows up next to the builder's setters
+        let mut doc =
+            format!("Builder for [`{}`], checking at compile-time that every field is set.", path);
+        if let Some(ttl) = &self.expires_in {
+            doc += &format!(
+                " It expires `{}` after it was started, from then on `construct()` returns \
+                 `BuildError::Expired` instead of the value.",
+                ttl,
+            );
+        }
+
+        let all = Features { setter_traits: self.features.setter_traits, ..Features::ALL };
+        if self.features.names() != all.names() {
+            let features = self.features.names();
+            doc += &format!(" Generated with `features({})`.", features.join(", "));
+        }
+
+        // `const_check` and the registry need the table, whatever the features
+        let table = if self.features.reflection || self.const_check.is_some() || registry {
+            format!(
+                "/// The fields, in declaration order, like `ItemBuilder::FIELDS`.
+                pub const FIELDS: &'static [::typestuff::reflection::FieldInfo] = &[{}];",
+                infos.join(", "),
+            )
+        } else {
+            String::new()
+        };
+        let reflection = if self.features.reflection {
+            format!(
+                "/// Whether each field of `FIELDS` is set, in the same order.
+                pub const STATES: &'static [bool] = &[{states}];
+
+                /// The fields that have been set, in declaration order.
+                pub fn set_fields(&self) -> impl ::core::iter::Iterator<Item = &'static str> {{
+                    let fields = <{unset}>::FIELDS.iter().zip(Self::STATES);
+                    fields.filter(|(_, set)| **set).map(|(field, _)| field.name)
+                }}
+
+                /// The required fields that haven't been set, in declaration order.
+                pub fn missing_fields(&self) -> impl ::core::iter::Iterator<Item = &'static str> {{
+                    let fields = <{unset}>::FIELDS.iter().zip(Self::STATES);
+                    let missing = fields.filter(|(field, set)| field.required && !**set);
+                    missing.map(|(field, _)| field.name)
+                }}
+
+                /// Whether every required field is set, that is whether `construct()` is
+                /// available.
+                pub fn is_complete(&self) -> bool {{
+                    self.missing_fields().next().is_none()
+                }}",
+                states = states.join(", "),
+                unset = unset,
+            )
+        } else {
+            String::new()
+        };
+        let debug = if self.features.debug {
+            format!(
+                "impl<{params}> ::core::fmt::Debug for {generic} {debug_where} {{
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {{
+                        f.debug_struct({name:?}){debug}.finish()
+                    }}
+                }}",
+                params = params,
+                generic = generic,
+                debug_where = self.where_with(debug_bounds),
+                name = name,
+                debug = debug,
+            )
+        } else {
+            String::new()
+        };
+
+        let const_check = match &self.const_check {
+            Some(check) => format!("::typestuff::const_check!(<{}>::FIELDS, {});", unset, check),
+            None => String::new(),
+        };
+
+        format!(
+            "{declaration}
+
+            #[doc = {doc:?}]
+            #[must_use = \"builder must be used to construct a value\"]
+            {vis} struct {name}<{params}> {where_clause} {{
+                {slots}
+            }}
+
+            #[allow(dead_code)]
+            impl<{generics}> {unset} {where_clause} {{
+                {table}
+
+                {new}
+            }}
+
+            {clone}
+
+            {debug}
+
+            #[allow(dead_code)]
+            impl<{params}> {generic} {where_clause} {{
+                {reflection}
+
+                {setters}
+            }}
+
+            {setter_traits}
+
+            {terminal}
+
+            {const_check}
+
+            {descriptor}
+
+            {entry}",
+            declaration = declaration,
+            name = name,
+            vis = vis,
+            params = params,
+            generics = generics,
+            where_clause = where_clause,
+            slots = slots,
+            unset = unset,
+            new = new,
+            generic = generic,
+            clone = clone,
+            debug = debug,
+            table = table,
+            reflection = reflection,
+            setters = setters,
+            setter_traits = setter_traits,
+            terminal = terminal,
+            const_check = const_check,
+            descriptor = descriptor,
+            doc = doc,
+            entry = entry,
+        )
+    }
+}
+
+/// The name of the registry descriptor of a builder, like `HTTP_SERVER_BUILDER` for
+/// `HttpServerBuilder` or `HTTPServerBuilder`.
+fn screaming_snake_case(name: &str) -> String {
+    let chars: Vec<_> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        let word = i > 0
+            && c.is_uppercase()
+            && chars[i - 1] != '_'
+            && (!chars[i - 1].is_uppercase()
+                || matches!(chars.get(i + 1), Some(next) if next.is_lowercase()));
+        if word {
+            snake.push('_');
+        }
+        snake.extend(c.to_uppercase());
+    }
+    snake
+}
+
+/// The builder of an enum, like `RequestBuilder` for `Request`. It only picks the variant, with a
+/// method per variant handing out the empty builder of that variant.
+fn selector(variants: &[Target]) -> String {
+    let target = &variants[0];
+    let name = format!("{}Builder", target.name);
+    let generics = target.parameters(Vec::new());
+    let arguments = target.arguments(Vec::new());
+    let where_clause = &target.where_clause;
+
+    let mut methods = String::new();
+    for variant in variants {
+        methods += &format!(
+            "#[doc = \"Build [`{path}`].\"]
+            pub fn {method}(self) -> {builder} {{
+                <{builder}>::new()
+            }}\n",
+            path = variant.path(),
+            method = method_name(variant.variant.as_deref().unwrap_or_default()),
+            builder = variant.builder_in("::typestuff::Unset"),
+        );
+    }
+
+    format!(
+        "#[doc = \"Picks the variant of [`{target}`] to build.\"]
+        #[must_use = \"builder must be used to construct a value\"]
+        {vis} struct {name}<{generics}> {where_clause} {{
+            __typestuff_target: ::core::marker::PhantomData<fn() -> {target}<{arguments}>>,
+        }}
+
+        #[allow(dead_code)]
+        impl<{generics}> {name}<{arguments}> {where_clause} {{
+            {methods}
+        }}
+
+        #[allow(dead_code)]
+        impl<{generics}> {target}<{arguments}> {where_clause} {{
+            /// Pick the variant to build.
+            {vis} fn builder() -> {name}<{arguments}> {{
+                {name} {{ __typestuff_target: ::core::marker::PhantomData }}
+            }}
+        }}
+
+        impl<{generics}> ::typestuff::nested::HasBuilder for {target}<{arguments}> {where_clause} {{
+            type Builder = {name}<{arguments}>;
+
+            fn builder() -> Self::Builder {{
+                <{target}<{arguments}>>::builder()
+            }}
+        }}",
+        target = target.name,
+        vis = target.vis,
+        name = name,
+        generics = generics,
+        arguments = arguments,
+        where_clause = where_clause,
+        methods = methods,
+    )
+}
+
+/// The method picking a variant, the variant's name in snake case: `Post` is picked by `post()`,
+/// `PostForm` by `post_form()`. Names that turn into keywords are raw identifiers.
+fn method_name(variant: &str) -> String {
+    let mut method = String::new();
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            method.push('_');
+        }
+        method.extend(c.to_lowercase());
+    }
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+        "priv", "try", "typeof", "unsized", "virtual", "yield",
+    ];
+    if KEYWORDS.contains(&method.as_str()) {
+        method.insert_str(0, "r#");
+    }
+    method
+}
+
+/// A type as written in the source, without the spaces the tokens were joined with:
+/// `HashMap < String, Vec < u8 > >` is `HashMap<String, Vec<u8>>`.
+fn type_name(ty: &str) -> String {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '\'');
+    let chars: Vec<_> = ty.chars().collect();
+    let mut name = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let keep = c != ' ' || word(name.chars().last()) && word(chars.get(i + 1).copied());
+        if keep {
+            name.push(c);
+        }
+    }
+    name.replace(',', ", ").replace(';', "; ").replace("->", " -> ")
+}
+
+/// A field's name in camel case for the aliases: `retry_count` is `RetryCount`.
+fn camel_case(field: &str) -> String {
+    let field = field.trim_start_matches("r#");
+    let mut camel = String::new();
+    for part in field.split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.extend(chars);
+        }
+    }
+    camel
+}
+
+/// The name of the state parameter of field `index`, unlikely to clash with the user's types.
+fn state_param(index: usize) -> String {
+    format!("__TypestuffS{}", index)
+}
+
+/// The parameter of the state of field `index` of the builder migrated from.
+fn migration_param(index: usize) -> String {
+    format!("__TypestuffM{}", index)
+}